@@ -1,9 +1,28 @@
 mod service;
 
 pub use service::{
-    add_tag_to_task, adjust_task_focus, archive_task, create_task, delete_tasks,
-    get_focus_summary, get_overview, insert_subtask_and_start, pause_running_task, pause_task,
-    remove_tag_from_task, rename_task, reparent_task, respond_rest_suggestion, resume_task,
-    start_task, stop_task,
+    accept_rest_suggestion_with_minutes, add_tag_to_task, adjust_task_focus, annotate_session,
+    archive_task, archive_tasks_by_tag, auto_stop_stale_paused_tasks, break_cycle,
+    clip_running_across_sleep, coalesce_task_sessions, complete_task, create_subtasks, create_task,
+    debug_intervals, delete_tasks, end_break, enforce_workday_end, expire_stale_rest_suggestions,
+    export_task_subtree, find_cycles, find_duplicate_tasks, get_billable_report,
+    get_break_adherence, get_completion_rate, get_container_tasks, get_descendants,
+    get_events_in_window, get_focus_density, get_focus_summary, get_fragmentation,
+    get_in_progress_tasks, get_inclusive_excluding, get_inclusive_overlap_warnings,
+    get_latest_event_id, get_longest_untracked_gap, get_midnight_crossing_sessions,
+    get_overdue_tasks, get_overview, get_overview_since, get_overwork_status,
+    get_pending_rest_suggestion, get_projects, get_rest_signals, get_rolling_average,
+    get_running_task, get_settings, get_snapshots, get_stale_tasks, get_status_counts,
+    get_summary_card_data, get_tag_daily_totals, get_tags_by_key, get_task_detail, get_task_events,
+    get_task_tree_nested, get_tasks_due_between, get_time_breakdown, get_time_by_tag_set,
+    get_top_tags, get_totals_for, get_untracked_gaps, get_weekly_summary, get_work_bounds,
+    import_csv_sessions, insert_subtask_and_start, list_untagged_tasks, log_interruption,
+    nudge_task_order, pause_running_task, pause_task, preview_current_rest, quick_start,
+    rebuild_baselines, reconcile_tags, remove_tag_from_task, rename_task, reopen_task,
+    reparent_task, reparent_tasks_by_tag, reset_task_time, respond_all_pending_rest_suggestions,
+    respond_rest_suggestion, restore_task, restore_tasks_by_tag, resume_task, set_tag_color,
+    set_task_due, set_task_reference, simulate_rest_rules, snapshot_overview, start_break,
+    start_task, stop_task, suggest_tags, tag_and_view, toggle_task, update_setting, update_task,
+    verify_tag_consistency, verify_totals,
 };
 
@@ -1,14 +1,35 @@
-use std::collections::{HashMap, HashSet};
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
-use chrono::{Duration as ChronoDuration, Local, TimeZone};
-use rusqlite::{params, Connection, OptionalExtension, Transaction};
+use chrono::{
+    DateTime, Duration as ChronoDuration, Local, NaiveDate, NaiveDateTime, NaiveTime, TimeZone,
+    Timelike,
+};
+use rusqlite::{
+    params, Connection, ErrorCode, OptionalExtension, Transaction, TransactionBehavior,
+};
 use serde_json::json;
 use uuid::Uuid;
 
 use crate::domain::{
-    DayTaskBreakdown, FocusSummaryDay, FocusSummaryResponse, FocusTimelineSegment,
-    NotificationRecord, OverviewResponse, RestSuggestionRecord, TaskRecord,
+    BillableReport, BillableTaskLine, BreakAdherence, CompletionRateResponse, CreatedSubtasks,
+    CsvImportReport, CsvImportRowError, DayTaskBreakdown, DebugAdjustment, DebugInterval,
+    DebugIntervalsResponse, DescendantRecord, DuplicateTaskCandidate, DuplicateTaskGroup,
+    DuplicateTasksResponse, ExportedTaskNode, FocusDensityDay, FocusDensityResponse,
+    FocusSummaryDay, FocusSummaryResponse, FocusTimelineSegment, FragmentationDay,
+    FragmentationResponse, InProgressTaskRecord, InclusiveExcludingResponse,
+    InclusiveOverlapWarning, MidnightCrossingSession, NotificationRecord, OverdueTaskRecord,
+    OverviewDelta, OverviewOptions, OverviewResponse, OverviewSnapshot, OverviewSnapshotSummary,
+    OverworkStatus, PausedAutoStop, ProjectRecord, ProjectsResponse, RestPreview, RestRulesConfig,
+    RestSignals, RestSimulationResponse, RestSuggestionRecord, RestTierCount, RollingAverageDay,
+    RollingAverageResponse, SettingRecord, SleepClip, StaleTaskRecord, StatusCounts,
+    SummaryCardData, TagDailyTotal, TagDailyTotalsResponse, TagDiscrepancy, TagRecord, TagRef,
+    TagSetTotal, TagSetTotalsResponse, TagSuggestion, TaskDetail, TaskEventRecord, TaskEventsPage,
+    TaskRecord, TaskSession, TaskSubtreeExport, TaskTotals, TaskTotalsResponse, TaskTreeNode,
+    TaskTreeResponse, TimeBreakdownEntry, TimeBreakdownResponse, TimeEventRecord, TimeEventsPage,
+    TotalsDrift, UntrackedGap, UntrackedGapsResponse, WeeklySummary, WeeklyTopTask, WorkBoundsDay,
+    WorkBoundsResponse, WorkdayEndStop,
 };
 use crate::infra::{AppError, AppResult};
 
@@ -17,6 +38,7 @@ const STATUS_RUNNING: &str = "running";
 const STATUS_PAUSED: &str = "paused";
 const STATUS_STOPPED: &str = "stopped";
 
+const EVENT_CREATE: &str = "create";
 const EVENT_START: &str = "start";
 const EVENT_PAUSE: &str = "pause";
 const EVENT_RESUME: &str = "resume";
@@ -25,6 +47,9 @@ const EVENT_ADJUST: &str = "adjust";
 const EVENT_REPARENT: &str = "reparent";
 const EVENT_TAG_ADD: &str = "tag_add";
 const EVENT_TAG_REMOVE: &str = "tag_remove";
+const EVENT_ARCHIVE: &str = "archive";
+const EVENT_RESTORE: &str = "restore";
+const EVENT_INTERRUPTION: &str = "interruption";
 
 const REST_TRIGGER_SUBTASK_END: &str = "subtask_end";
 const REST_TRIGGER_TASK_SWITCH: &str = "task_switch";
@@ -34,6 +59,82 @@ const REST_STATUS_IGNORED: &str = "ignored";
 const NOTIFICATION_KIND_REST_SUGGESTION: &str = "rest_suggestion";
 const NOTIFICATION_LEVEL_INFO: &str = "info";
 const SWITCH_WINDOW_SECONDS: i64 = 30 * 60;
+const REST_RESPONSE_REASON_EXPIRED: &str = "expired";
+const MAX_REST_MINUTES: i64 = 120;
+const MAX_PAUSE_REASON_CHARS: usize = 200;
+
+const TAG_KEY_CLIENT: &str = "client";
+const TAG_BILLABLE: &str = "billable";
+const TAG_BILLABLE_TRUE: &str = "billable:true";
+const DEFAULT_TAG_SUGGESTION_LIMIT: i64 = 5;
+
+const DEFAULT_DUPLICATE_TITLE_DISTANCE: i64 = 2;
+const MAX_DUPLICATE_TITLE_DISTANCE: i64 = 5;
+const MAX_DUPLICATE_COMPARISONS: usize = 250_000;
+const MAX_DUPLICATE_GROUPS: usize = 100;
+const MAX_DUPLICATE_TITLE_CHARS: usize = 200;
+
+const SUBTREE_EXPORT_FORMAT_VERSION: u32 = 1;
+/// Serialized shape of `TaskRecord` in overview/tree responses; 2 made tags `{name, color}`.
+const TASK_RECORD_SCHEMA_VERSION: u32 = 2;
+const IMPORT_REASON_CSV: &str = "csv_import";
+
+const SETTING_REST_SUGGESTION_TTL_SECONDS: &str = "rest_suggestion_ttl_seconds";
+const DEFAULT_REST_SUGGESTION_TTL_SECONDS: i64 = 2 * 60 * 60;
+const SETTING_MIN_SESSION_SECONDS: &str = "min_session_seconds";
+const SETTING_REST_MINUTE_TIERS: &str = "rest_minute_tiers";
+const DEFAULT_REST_MINUTE_TIERS: [i64; 3] = [3, 8, 15];
+const SETTING_WORKDAY_END: &str = "workday_end";
+const SETTING_MAX_SUBTASK_INSERT_DEPTH: &str = "max_subtask_insert_depth";
+const DEFAULT_MAX_SUBTASK_INSERT_DEPTH: i64 = 5;
+const SETTING_REST_IGNORED_TAGS: &str = "rest_ignored_tags";
+const SETTING_AUTO_PAUSE_ON_CROSS_BRANCH_START: &str = "auto_pause_on_cross_branch_start";
+const SETTING_COUNT_INTERRUPTIONS_AS_SWITCHES: &str = "count_interruptions_as_switches";
+const PAUSE_REASON_CROSS_BRANCH_START: &str = "cross_branch_start";
+const PAUSE_REASON_BREAK: &str = "break";
+const PAUSE_REASON_INSERT_SUBTASK: &str = "insert_subtask";
+const PAUSE_REASON_SLEEP: &str = "sleep";
+const RESERVED_PAUSE_REASONS: &[&str] = &[
+    PAUSE_REASON_CROSS_BRANCH_START,
+    PAUSE_REASON_BREAK,
+    PAUSE_REASON_INSERT_SUBTASK,
+    PAUSE_REASON_SLEEP,
+];
+const RESUME_REASON_BREAK_ENDED: &str = "break_ended";
+const RESUME_REASON_WAKE: &str = "wake";
+const WORKDAY_END_FORMAT: &str = "%H:%M";
+const STOP_REASON_WORKDAY_END: &str = "workday_end";
+const SETTING_AUTO_STOP_PAUSED_AFTER: &str = "auto_stop_paused_after";
+const SETTING_DAILY_MAX_SECONDS: &str = "daily_max_seconds";
+const STOP_REASON_PAUSED_TIMEOUT: &str = "paused_timeout";
+const BUSY_RETRY_ATTEMPTS: u32 = 4;
+const BUSY_RETRY_BASE_DELAY_MS: u64 = 25;
+const MAX_SESSION_NOTE_CHARS: usize = 1000;
+const MAX_REFERENCE_URL_CHARS: usize = 2048;
+const MAX_ROLLING_WINDOW_DAYS: i64 = 90;
+const MAX_STALE_IDLE_DAYS: i64 = 3650;
+const TASK_EVENTS_DEFAULT_LIMIT: i64 = 100;
+const TASK_EVENTS_MAX_LIMIT: i64 = 1000;
+const OVERVIEW_SORT_KEYS: &[&str] = &[
+    "created_asc",
+    "created_desc",
+    "title",
+    "time_desc",
+    "recent_activity",
+    "manual",
+];
+const SETTING_KEYS: &[&str] = &[
+    SETTING_REST_SUGGESTION_TTL_SECONDS,
+    SETTING_WORKDAY_END,
+    SETTING_MIN_SESSION_SECONDS,
+    SETTING_REST_MINUTE_TIERS,
+    SETTING_REST_IGNORED_TAGS,
+    SETTING_AUTO_PAUSE_ON_CROSS_BRANCH_START,
+    SETTING_MAX_SUBTASK_INSERT_DEPTH,
+    SETTING_AUTO_STOP_PAUSED_AFTER,
+    SETTING_COUNT_INTERRUPTIONS_AS_SWITCHES,
+    SETTING_DAILY_MAX_SECONDS,
+];
 
 #[derive(Debug)]
 struct TaskState {
@@ -48,6 +149,101 @@ struct TaskRow {
     title: String,
     status: String,
     created_at: i64,
+    completed_at: Option<i64>,
+    due_at: Option<i64>,
+    reference_url: Option<String>,
+    sort_order: Option<i64>,
+}
+
+/// How focus is measured. `Active` counts only running time (start/resume to pause/stop);
+/// `Engaged` spans each session from its first start to its stop, so pauses in between
+/// count too and a paused parent also accrues its subtasks' time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TimeBasis {
+    Active,
+    Engaged,
+}
+
+impl TimeBasis {
+    fn parse(raw: Option<&str>) -> AppResult<Self> {
+        match raw.unwrap_or("active") {
+            "active" => Ok(Self::Active),
+            "engaged" => Ok(Self::Engaged),
+            unsupported => Err(validation_error(format!(
+                "unsupported time_basis '{unsupported}', expected one of: active, engaged"
+            ))),
+        }
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Active => "active",
+            Self::Engaged => "engaged",
+        }
+    }
+}
+
+/// Unit of event timestamps and totals in a replay. `Millis` reads `ts_ms` where it was
+/// recorded and falls back to `ts * 1000` for rows written before it existed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Precision {
+    Seconds,
+    Millis,
+}
+
+impl Precision {
+    fn parse(raw: Option<&str>) -> AppResult<Self> {
+        match raw.unwrap_or("seconds") {
+            "seconds" => Ok(Self::Seconds),
+            "millis" => Ok(Self::Millis),
+            unsupported => Err(validation_error(format!(
+                "unsupported precision '{unsupported}', expected one of: seconds, millis"
+            ))),
+        }
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Seconds => "seconds",
+            Self::Millis => "millis",
+        }
+    }
+
+    fn ts_column(self) -> &'static str {
+        match self {
+            Self::Seconds => "ts",
+            Self::Millis => "COALESCE(ts_ms, ts * 1000)",
+        }
+    }
+
+    fn units_per_second(self) -> i64 {
+        match self {
+            Self::Seconds => 1,
+            Self::Millis => 1000,
+        }
+    }
+}
+
+#[derive(Debug)]
+enum CsvTimeColumn {
+    Combined(usize),
+    Split { date: usize, time: usize },
+}
+
+#[derive(Debug)]
+struct CsvColumns {
+    description: usize,
+    start: CsvTimeColumn,
+    end: CsvTimeColumn,
+    tags: Option<usize>,
+}
+
+#[derive(Debug)]
+struct CsvEntry {
+    title: String,
+    start_ts: i64,
+    end_ts: i64,
+    tags: Vec<String>,
 }
 
 #[derive(Debug)]
@@ -55,6 +251,15 @@ struct FocusInterval {
     task_id: String,
     start_ts: i64,
     end_ts: i64,
+    unclipped_start_ts: i64,
+    unclipped_end_ts: i64,
+}
+
+#[derive(Debug)]
+struct FocusSession {
+    task_id: String,
+    start_ts: i64,
+    end_ts: i64,
 }
 
 #[derive(Debug)]
@@ -82,14 +287,87 @@ pub fn create_task(
         ensure_task_exists(conn, parent)?;
     }
 
-    let task_id = Uuid::new_v4().to_string();
+    let tx = begin_transaction(conn)?;
+    let task_id = insert_idle_task(&tx, parent_id.as_deref(), &clean_title, now_ts())?;
+    tx.commit().map_err(to_error)?;
+
+    Ok(task_id)
+}
+
+/// Creates one child per title under `parent_id` in a single transaction. Blank titles are
+/// skipped with a warning; ids come back in input order.
+pub fn create_subtasks(
+    conn: &mut Connection,
+    parent_id: String,
+    titles: Vec<String>,
+) -> AppResult<CreatedSubtasks> {
+    ensure_task_exists(conn, &parent_id)?;
+
     let created_at = now_ts();
+    let mut task_ids = Vec::new();
+    let mut warnings = Vec::new();
+    let tx = begin_transaction(conn)?;
+    for (index, title) in titles.iter().enumerate() {
+        let Ok(clean_title) = sanitize_title(title) else {
+            warnings.push(format!("title {} is blank and was skipped", index + 1));
+            continue;
+        };
+        task_ids.push(insert_idle_task(
+            &tx,
+            Some(&parent_id),
+            &clean_title,
+            created_at,
+        )?);
+    }
+    tx.commit().map_err(to_error)?;
 
-    conn.execute(
+    Ok(CreatedSubtasks { task_ids, warnings })
+}
+
+/// Creates a task, tags it and starts it in one transaction. Fails like `start_task` when
+/// another task is already running.
+pub fn quick_start(
+    conn: &mut Connection,
+    title: String,
+    tags: Vec<String>,
+    parent_id: Option<String>,
+) -> AppResult<String> {
+    let clean_title = sanitize_title(&title)?;
+    let clean_tags = tags
+        .iter()
+        .map(|tag| sanitize_tag(tag))
+        .collect::<AppResult<Vec<_>>>()?;
+    if let Some(parent) = &parent_id {
+        ensure_task_exists(conn, parent)?;
+    }
+    if let Some(active_task_id) = find_running_task(conn)? {
+        return Err(task_running_error("start", active_task_id));
+    }
+    let previous_focus_task = latest_focus_task(conn)?;
+
+    let task_id = Uuid::new_v4().to_string();
+    let ts = now_ts();
+
+    let tx = begin_transaction(conn)?;
+    tx.execute(
         "INSERT INTO tasks (id, parent_id, title, status, created_at) VALUES (?1, ?2, ?3, ?4, ?5)",
-        params![task_id, parent_id, clean_title, STATUS_IDLE, created_at],
+        params![task_id, parent_id, clean_title, STATUS_RUNNING, ts],
     )
     .map_err(to_error)?;
+    append_event(
+        &tx,
+        &task_id,
+        EVENT_CREATE,
+        ts,
+        Some(json!({ "parent_id": parent_id })),
+    )?;
+    for tag in &clean_tags {
+        attach_tag(&tx, &task_id, tag, ts)?;
+    }
+    append_event(&tx, &task_id, EVENT_START, ts, None)?;
+    tx.commit().map_err(to_error)?;
+
+    maybe_create_task_switch_suggestion(conn, previous_focus_task, &task_id, ts)?;
 
     Ok(task_id)
 }
@@ -136,9 +414,9 @@ pub fn delete_tasks(
         )));
     }
 
-    let tx = conn.transaction().map_err(to_error)?;
+    let tx = begin_transaction(conn)?;
     if hard_delete {
-        hard_delete_task_ids(&tx, &expanded_ids)?;
+        hard_delete_task_ids(&tx, &expanded_ids, now_ts())?;
     } else {
         archive_task_ids(&tx, &expanded_ids, now_ts())?;
     }
@@ -147,673 +425,4640 @@ pub fn delete_tasks(
     Ok(())
 }
 
-pub fn reparent_task(
+/// Clears the recorded time of a task (and optionally its descendants) and sets it back to
+/// idle. Only clock events are removed; creation, tag and hierarchy history is kept.
+pub fn reset_task_time(
     conn: &mut Connection,
     task_id: String,
-    new_parent_id: Option<String>,
-) -> AppResult<()> {
-    let task = get_task_state(conn, &task_id)?;
-    let old_parent_id = task.parent_id.clone();
-
-    if new_parent_id.as_deref() == Some(task_id.as_str()) {
-        return Err(validation_error("task cannot be its own parent"));
-    }
-
-    if old_parent_id == new_parent_id {
-        return Ok(());
-    }
+    include_descendants: bool,
+) -> AppResult<Vec<String>> {
+    ensure_task_exists(conn, &task_id)?;
+    let task_ids = if include_descendants {
+        collect_subtree_ids(conn, &task_id)?
+    } else {
+        vec![task_id]
+    };
 
-    let subtree_ids = collect_subtree_ids(conn, &task_id)?;
     if let Some((_active_id, active_title, active_status)) =
-        find_active_in_subtree(conn, &subtree_ids)?
+        find_active_in_subtree(conn, &task_ids)?
     {
         return Err(conflict_error(format!(
-            "cannot reparent while task \"{active_title}\" is {active_status}; stop or pause transitions first"
+            "cannot reset task \"{active_title}\" because it is currently {active_status}"
         )));
     }
 
-    let subtree_set: HashSet<String> = subtree_ids.into_iter().collect();
+    let tx = begin_transaction(conn)?;
+    for id in &task_ids {
+        tx.execute(
+            "DELETE FROM time_events
+             WHERE task_id = ?1
+               AND event_type IN ('start', 'pause', 'resume', 'stop', 'adjust')",
+            params![id],
+        )
+        .map_err(to_error)?;
+        refresh_task_baseline(&tx, id)?;
+        tx.execute(
+            "UPDATE tasks SET status = ?1 WHERE id = ?2",
+            params![STATUS_IDLE, id],
+        )
+        .map_err(to_error)?;
+    }
+    tx.commit().map_err(to_error)?;
+
+    Ok(task_ids)
+}
 
-    if let Some(parent_id) = &new_parent_id {
-        ensure_task_exists(conn, parent_id)?;
-        if subtree_set.contains(parent_id) {
-            return Err(validation_error(
-                "cannot reparent task under itself or its descendants",
-            ));
-        }
-        ensure_ancestor_chain_valid(conn, parent_id, &task_id)?;
+pub fn restore_task(conn: &mut Connection, task_id: String) -> AppResult<()> {
+    let row: Option<(Option<String>, Option<i64>)> = conn
+        .query_row(
+            "SELECT parent_id, archived_at FROM tasks WHERE id = ?1 LIMIT 1",
+            params![task_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .optional()
+        .map_err(to_error)?;
+    let Some((parent_id, archived_at)) = row else {
+        return Err(not_found_error(format!("task {task_id} not found")));
+    };
+    let Some(archived_at) = archived_at else {
+        return Ok(());
+    };
+    if let Some(parent_id) = parent_id {
+        ensure_task_exists(conn, &parent_id)
+            .map_err(|_| conflict_error("cannot restore a task whose parent is archived"))?;
     }
 
-    let ts = now_ts();
-    let tx = conn.transaction().map_err(to_error)?;
-    tx.execute(
-        "UPDATE tasks SET parent_id = ?1 WHERE id = ?2 AND archived_at IS NULL",
-        params![new_parent_id, task_id],
-    )
-    .map_err(to_error)?;
-    append_event(
-        &tx,
-        &task_id,
-        EVENT_REPARENT,
-        ts,
-        Some(json!({
-            "old_parent_id": old_parent_id,
-            "new_parent_id": new_parent_id
-        })),
-    )?;
+    let restored_ids = collect_archived_subtree_ids(conn, &task_id, archived_at)?;
+    let tx = begin_transaction(conn)?;
+    restore_task_ids(&tx, &restored_ids, now_ts())?;
     tx.commit().map_err(to_error)?;
 
     Ok(())
 }
 
-pub fn start_task(conn: &mut Connection, task_id: String) -> AppResult<()> {
-    let previous_focus_task = latest_focus_task(conn)?;
-    let task = get_task_state(conn, &task_id)?;
-
-    if task.status == STATUS_RUNNING {
-        return Ok(());
+/// Archives every live task carrying the tag together with its subtree and returns the
+/// archived ids. Fails without changes if any affected task is running or paused.
+pub fn archive_tasks_by_tag(conn: &mut Connection, tag_name: String) -> AppResult<Vec<String>> {
+    let tag = sanitize_tag(&tag_name)?;
+    let mut root_ids = Vec::new();
+    for task_id in load_task_ids_with_tag(conn, &tag)? {
+        if load_archived_at(conn, &task_id)?.is_none() {
+            root_ids.push(task_id);
+        }
     }
+    root_ids.sort();
 
-    if task.status == STATUS_PAUSED {
-        return Err(conflict_error("task is paused, use resume_task instead"));
+    let expanded_ids = expand_unique_subtree_ids(conn, &root_ids)?;
+    if expanded_ids.is_empty() {
+        return Ok(expanded_ids);
     }
 
-    if let Some(active_task_id) = find_running_task(conn)? {
-        if active_task_id != task_id {
-            return Err(conflict_error(format!(
-                "cannot start task because task {active_task_id} is already running"
-            )));
-        }
+    if let Some((_active_id, active_title, active_status)) =
+        find_active_in_subtree(conn, &expanded_ids)?
+    {
+        return Err(conflict_error(format!(
+            "cannot archive task \"{active_title}\" because it is currently {active_status}"
+        )));
     }
 
-    let ts = now_ts();
-    let tx = conn.transaction().map_err(to_error)?;
-    tx.execute(
-        "UPDATE tasks SET status = ?1 WHERE id = ?2",
-        params![STATUS_RUNNING, task_id],
-    )
-    .map_err(to_error)?;
-    append_event(&tx, &task_id, EVENT_START, ts, None)?;
+    let tx = begin_transaction(conn)?;
+    archive_task_ids(&tx, &expanded_ids, now_ts())?;
     tx.commit().map_err(to_error)?;
 
-    maybe_create_task_switch_suggestion(conn, previous_focus_task, &task_id, ts)?;
-
-    Ok(())
+    Ok(expanded_ids)
 }
 
-pub fn pause_task(conn: &mut Connection, task_id: String) -> AppResult<()> {
-    let task = get_task_state(conn, &task_id)?;
+/// Restores every archived task carrying the tag along with the descendants archived at
+/// the same time, and returns the restored ids.
+pub fn restore_tasks_by_tag(conn: &mut Connection, tag_name: String) -> AppResult<Vec<String>> {
+    let tag = sanitize_tag(&tag_name)?;
+    let mut root_ids = load_task_ids_with_tag(conn, &tag)?
+        .into_iter()
+        .collect::<Vec<_>>();
+    root_ids.sort();
 
-    if task.status == STATUS_PAUSED {
-        return Ok(());
+    let mut restored_ids = Vec::new();
+    let mut seen = HashSet::new();
+    for root_id in &root_ids {
+        let Some(archived_at) = load_archived_at(conn, root_id)? else {
+            continue;
+        };
+        for task_id in collect_archived_subtree_ids(conn, root_id, archived_at)? {
+            if seen.insert(task_id.clone()) {
+                restored_ids.push(task_id);
+            }
+        }
     }
 
-    if task.status != STATUS_RUNNING {
-        return Err(conflict_error("only a running task can be paused"));
+    for task_id in &restored_ids {
+        let parent_id: Option<String> = conn
+            .query_row(
+                "SELECT parent_id FROM tasks WHERE id = ?1",
+                params![task_id],
+                |row| row.get(0),
+            )
+            .map_err(to_error)?;
+        if let Some(parent_id) = parent_id {
+            if !seen.contains(&parent_id) && load_archived_at(conn, &parent_id)?.is_some() {
+                return Err(conflict_error(format!(
+                    "cannot restore task {task_id} because its parent is archived"
+                )));
+            }
+        }
     }
 
-    let ts = now_ts();
-    let tx = conn.transaction().map_err(to_error)?;
-    tx.execute(
-        "UPDATE tasks SET status = ?1 WHERE id = ?2",
-        params![STATUS_PAUSED, task_id],
-    )
-    .map_err(to_error)?;
-    append_event(&tx, &task_id, EVENT_PAUSE, ts, None)?;
+    let tx = begin_transaction(conn)?;
+    restore_task_ids(&tx, &restored_ids, now_ts())?;
     tx.commit().map_err(to_error)?;
 
-    Ok(())
+    Ok(restored_ids)
 }
 
-pub fn pause_running_task(conn: &mut Connection) -> AppResult<Option<String>> {
-    let Some(task_id) = find_running_task(conn)? else {
-        return Ok(None);
-    };
-    pause_task(conn, task_id.clone())?;
-    Ok(Some(task_id))
-}
-pub fn resume_task(conn: &mut Connection, task_id: String) -> AppResult<()> {
-    let previous_focus_task = latest_focus_task(conn)?;
+pub fn reparent_task(
+    conn: &mut Connection,
+    task_id: String,
+    new_parent_id: Option<String>,
+) -> AppResult<()> {
     let task = get_task_state(conn, &task_id)?;
+    let old_parent_id = task.parent_id.clone();
 
-    if task.status == STATUS_RUNNING {
-        return Ok(());
+    if new_parent_id.as_deref() == Some(task_id.as_str()) {
+        return Err(validation_error("task cannot be its own parent"));
     }
 
-    if task.status != STATUS_PAUSED {
-        return Err(conflict_error("only a paused task can be resumed"));
+    if old_parent_id == new_parent_id {
+        return Ok(());
     }
 
-    if let Some(active_task_id) = find_running_task(conn)? {
-        if active_task_id != task_id {
-            return Err(conflict_error(format!(
-                "cannot resume task because task {active_task_id} is already running"
-            )));
-        }
-    }
+    ensure_reparent_allowed(conn, &task_id, new_parent_id.as_deref())?;
 
     let ts = now_ts();
-    let tx = conn.transaction().map_err(to_error)?;
-    tx.execute(
-        "UPDATE tasks SET status = ?1 WHERE id = ?2",
-        params![STATUS_RUNNING, task_id],
-    )
-    .map_err(to_error)?;
-    append_event(&tx, &task_id, EVENT_RESUME, ts, None)?;
+    let tx = begin_transaction(conn)?;
+    write_reparent(&tx, &task_id, old_parent_id, new_parent_id, ts)?;
     tx.commit().map_err(to_error)?;
 
-    maybe_create_task_switch_suggestion(conn, previous_focus_task, &task_id, ts)?;
-
     Ok(())
 }
 
-pub fn stop_task(conn: &mut Connection, task_id: String) -> AppResult<()> {
-    let task = get_task_state(conn, &task_id)?;
+/// Moves every live task carrying the tag under `new_parent_id`, or to the top level when
+/// it is `None`. A tagged task whose ancestor is also tagged moves along with that ancestor
+/// rather than on its own. All moves are validated before any is written, in the same
+/// transaction; returns the moved ids.
+pub fn reparent_tasks_by_tag(
+    conn: &mut Connection,
+    tag_name: String,
+    new_parent_id: Option<String>,
+) -> AppResult<Vec<String>> {
+    let tag = sanitize_tag(&tag_name)?;
+    let parent_by_task = load_tasks(conn)?
+        .into_iter()
+        .map(|task| (task.id, task.parent_id))
+        .collect::<HashMap<_, _>>();
+    let tagged_ids = load_task_ids_with_tag(conn, &tag)?
+        .into_iter()
+        .filter(|id| parent_by_task.contains_key(id) && Some(id) != new_parent_id.as_ref())
+        .collect::<HashSet<_>>();
+
+    let has_tagged_ancestor = |task_id: &String| {
+        let mut visited = HashSet::new();
+        let mut current = parent_by_task.get(task_id).cloned().flatten();
+        while let Some(id) = current {
+            if tagged_ids.contains(&id) {
+                return true;
+            }
+            if !visited.insert(id.clone()) {
+                return false;
+            }
+            current = parent_by_task.get(&id).cloned().flatten();
+        }
+        false
+    };
+    let mut root_ids = tagged_ids
+        .iter()
+        .filter(|task_id| !has_tagged_ancestor(task_id))
+        .cloned()
+        .collect::<Vec<_>>();
+    root_ids.sort();
 
-    if task.status == STATUS_STOPPED {
-        return Ok(());
+    let ts = now_ts();
+    let tx = begin_transaction(conn)?;
+    let mut moves = Vec::new();
+    for task_id in root_ids {
+        let task = get_task_state(&tx, &task_id)?;
+        if task.parent_id == new_parent_id {
+            continue;
+        }
+        ensure_reparent_allowed(&tx, &task_id, new_parent_id.as_deref())?;
+        moves.push((task_id, task.parent_id));
     }
-
-    if task.status == STATUS_IDLE {
-        return Err(conflict_error("cannot stop an idle task"));
-    }
-
-    let ts = now_ts();
-    let mut should_trigger_subtask_rest = false;
-    let tx = conn.transaction().map_err(to_error)?;
-    tx.execute(
-        "UPDATE tasks SET status = ?1 WHERE id = ?2",
-        params![STATUS_STOPPED, task_id],
-    )
-    .map_err(to_error)?;
-    append_event(&tx, &task_id, EVENT_STOP, ts, None)?;
-
-    if let Some(parent_id) = task.parent_id {
-        should_trigger_subtask_rest = maybe_auto_resume_parent(&tx, &parent_id, &task_id, ts)?;
+    for (task_id, old_parent_id) in &moves {
+        write_reparent(
+            &tx,
+            task_id,
+            old_parent_id.clone(),
+            new_parent_id.clone(),
+            ts,
+        )?;
     }
-
     tx.commit().map_err(to_error)?;
 
-    if should_trigger_subtask_rest {
-        create_rest_suggestion(conn, REST_TRIGGER_SUBTASK_END, Some(task_id.as_str()), ts)?;
-    }
-
-    Ok(())
+    Ok(moves.into_iter().map(|(task_id, _)| task_id).collect())
 }
 
-pub fn adjust_task_focus(
+/// Moves a task `delta` places among its live siblings (negative is up) and returns the
+/// sibling ids in their new order. Moves past either end stop there. Reordering pins
+/// `sort_order` for every sibling; siblings never reordered follow, oldest first. A task
+/// that is reparented or restored loses its pinned order and joins that tail.
+pub fn nudge_task_order(
     conn: &mut Connection,
     task_id: String,
-    delta_seconds: i64,
-) -> AppResult<()> {
-    ensure_task_exists(conn, &task_id)?;
-    if delta_seconds == 0 {
-        return Err(validation_error("delta_seconds cannot be zero"));
-    }
+    delta: i64,
+) -> AppResult<Vec<String>> {
+    let task = get_task_state(conn, &task_id)?;
 
-    let now = now_ts();
-    let total_focus_seconds = task_total_focus_seconds(conn, &task_id, now)?;
-    if total_focus_seconds + delta_seconds < 0 {
-        return Err(validation_error(
-            "adjustment would make task focus time negative",
-        ));
+    let tx = begin_transaction(conn)?;
+    let mut sibling_ids = {
+        let mut stmt = tx
+            .prepare(
+                "SELECT id
+                 FROM tasks
+                 WHERE parent_id IS ?1 AND archived_at IS NULL
+                 ORDER BY sort_order IS NULL, sort_order ASC, created_at ASC, id ASC",
+            )
+            .map_err(to_error)?;
+        let rows = stmt
+            .query_map(params![task.parent_id], |row| row.get::<_, String>(0))
+            .map_err(to_error)?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(to_error)?;
+        rows
+    };
+    let position = sibling_ids
+        .iter()
+        .position(|id| *id == task_id)
+        .ok_or_else(|| not_found_error(format!("task {task_id} not found or archived")))?;
+    let target = (position as i64)
+        .saturating_add(delta)
+        .clamp(0, sibling_ids.len() as i64 - 1) as usize;
+    if target != position {
+        let moved_id = sibling_ids.remove(position);
+        sibling_ids.insert(target, moved_id);
+        for (index, sibling_id) in sibling_ids.iter().enumerate() {
+            tx.execute(
+                "UPDATE tasks SET sort_order = ?1 WHERE id = ?2",
+                params![index as i64, sibling_id],
+            )
+            .map_err(to_error)?;
+        }
     }
-
-    let tx = conn.transaction().map_err(to_error)?;
-    append_event(
-        &tx,
-        &task_id,
-        EVENT_ADJUST,
-        now,
-        Some(json!({
-            "delta_seconds": delta_seconds
-        })),
-    )?;
     tx.commit().map_err(to_error)?;
 
-    Ok(())
+    Ok(sibling_ids)
 }
 
-pub fn insert_subtask_and_start(
+/// Applies a rename and/or reparent in one transaction, running the same validations as
+/// `rename_task` and `reparent_task`, and returns the refreshed record.
+pub fn update_task(
     conn: &mut Connection,
-    parent_task_id: String,
-    title: String,
-) -> AppResult<String> {
-    let clean_title = sanitize_title(&title)?;
-    let parent = get_task_state(conn, &parent_task_id)?;
+    task_id: String,
+    title: Option<String>,
+    parent_id: Option<Option<String>>,
+) -> AppResult<TaskRecord> {
+    let task = get_task_state(conn, &task_id)?;
+    let clean_title = title.as_deref().map(sanitize_title).transpose()?;
 
-    if parent.status != STATUS_RUNNING {
-        return Err(conflict_error(
-            "insert_subtask_and_start requires the parent task to be running",
-        ));
-    }
+    let parent_change = match parent_id {
+        Some(new_parent_id) if new_parent_id != task.parent_id => {
+            if new_parent_id.as_deref() == Some(task_id.as_str()) {
+                return Err(validation_error("task cannot be its own parent"));
+            }
+            ensure_reparent_allowed(conn, &task_id, new_parent_id.as_deref())?;
+            Some(new_parent_id)
+        }
+        _ => None,
+    };
 
-    if let Some(active_task_id) = find_running_task(conn)? {
-        if active_task_id != parent_task_id {
-            return Err(conflict_error(format!(
-                "cannot insert subtask while task {active_task_id} is running"
-            )));
+    if clean_title.is_some() || parent_change.is_some() {
+        let tx = begin_transaction(conn)?;
+        if let Some(clean_title) = &clean_title {
+            tx.execute(
+                "UPDATE tasks SET title = ?1 WHERE id = ?2 AND archived_at IS NULL",
+                params![clean_title, task_id],
+            )
+            .map_err(to_error)?;
         }
-    } else {
-        return Err(conflict_error("no running task found for subtask insertion"));
+        if let Some(new_parent_id) = parent_change {
+            write_reparent(&tx, &task_id, task.parent_id, new_parent_id, now_ts())?;
+        }
+        tx.commit().map_err(to_error)?;
     }
 
-    let child_task_id = Uuid::new_v4().to_string();
-    let ts = now_ts();
-    let tx = conn.transaction().map_err(to_error)?;
-
-    tx.execute(
-        "UPDATE tasks SET status = ?1 WHERE id = ?2",
-        params![STATUS_PAUSED, parent_task_id],
-    )
-    .map_err(to_error)?;
-    append_event(
-        &tx,
-        &parent_task_id,
-        EVENT_PAUSE,
-        ts,
-        Some(json!({
-            "reason": "insert_subtask",
-            "child_id": child_task_id
-        })),
-    )?;
+    load_task_records(conn, None, now_ts(), TimeBasis::Active)?
+        .into_iter()
+        .find(|record| record.id == task_id)
+        .ok_or_else(|| not_found_error(format!("task {task_id} not found or archived")))
+}
 
-    tx.execute(
-        "INSERT INTO tasks (id, parent_id, title, status, created_at) VALUES (?1, ?2, ?3, ?4, ?5)",
-        params![
-            child_task_id,
-            parent_task_id,
-            clean_title,
-            STATUS_RUNNING,
-            ts
-        ],
-    )
-    .map_err(to_error)?;
-    append_event(
-        &tx,
-        &child_task_id,
-        EVENT_START,
-        ts,
-        Some(json!({
-            "reason": "insert_subtask",
-            "parent_id": parent_task_id
-        })),
-    )?;
+/// Returns the live subtree under `task_id` with each task's depth below it (children are
+/// depth 1). The root itself is only included, at depth 0, when `include_root` is set.
+pub fn get_descendants(
+    conn: &Connection,
+    task_id: String,
+    include_root: bool,
+) -> AppResult<Vec<DescendantRecord>> {
+    ensure_task_exists(conn, &task_id)?;
+    let mut records = load_task_records(conn, None, now_ts(), TimeBasis::Active)?
+        .into_iter()
+        .map(|record| (record.id.clone(), record))
+        .collect::<HashMap<_, _>>();
 
-    tx.commit().map_err(to_error)?;
+    Ok(collect_subtree_ids_with_depth(conn, &task_id)?
+        .into_iter()
+        .filter(|(_, depth)| include_root || *depth > 0)
+        .filter_map(|(id, depth)| {
+            records
+                .remove(&id)
+                .map(|task| DescendantRecord { task, depth })
+        })
+        .collect())
+}
 
-    create_rest_suggestion(
-        conn,
-        REST_TRIGGER_TASK_SWITCH,
-        Some(parent_task_id.as_str()),
-        ts,
-    )?;
+/// Returns a task's record together with its focus sessions, oldest first. A session runs
+/// from the start/resume that opened it to the next pause or stop; an open session has
+/// no `end_ts` and is measured up to now.
+pub fn get_task_detail(conn: &Connection, task_id: String) -> AppResult<TaskDetail> {
+    let now = now_ts();
+    let task = load_task_records(conn, None, now, TimeBasis::Active)?
+        .into_iter()
+        .find(|record| record.id == task_id)
+        .ok_or_else(|| not_found_error(format!("task {task_id} not found or archived")))?;
+    let sessions = load_task_sessions(conn, &task_id, now)?
+        .into_iter()
+        .map(|(_, session)| session)
+        .collect();
+    let (first_worked_at, last_worked_at) = conn
+        .query_row(
+            "SELECT MIN(CASE WHEN event_type IN ('start', 'resume') THEN ts END),
+                    MAX(CASE WHEN event_type IN ('pause', 'stop') THEN ts END)
+             FROM time_events
+             WHERE task_id = ?1",
+            params![task_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .map_err(to_error)?;
 
-    Ok(child_task_id)
+    Ok(TaskDetail {
+        task,
+        sessions,
+        first_worked_at,
+        last_worked_at,
+    })
 }
 
-pub fn add_tag_to_task(conn: &mut Connection, task_id: String, tag_name: String) -> AppResult<()> {
+/// Attaches a note to the session that started at `session_start_ts`, stored in the
+/// payload of the start/resume event that opened it. An empty note clears it.
+pub fn annotate_session(
+    conn: &mut Connection,
+    task_id: String,
+    session_start_ts: i64,
+    note: String,
+) -> AppResult<()> {
     ensure_task_exists(conn, &task_id)?;
-    let clean_tag = sanitize_tag(&tag_name)?;
-    let ts = now_ts();
+    let clean_note = note.trim();
+    if clean_note.chars().count() > MAX_SESSION_NOTE_CHARS {
+        return Err(validation_error(format!(
+            "session note cannot exceed {MAX_SESSION_NOTE_CHARS} characters"
+        )));
+    }
 
-    let tx = conn.transaction().map_err(to_error)?;
+    let event_id = load_task_sessions(conn, &task_id, now_ts())?
+        .into_iter()
+        .find(|(_, session)| session.start_ts == session_start_ts)
+        .map(|(event_id, _)| event_id)
+        .ok_or_else(|| {
+            not_found_error(format!(
+                "task {task_id} has no session starting at {session_start_ts}"
+            ))
+        })?;
 
-    let maybe_tag_id: Option<String> = tx
+    let raw_payload: Option<String> = conn
         .query_row(
-            "SELECT id FROM tags WHERE lower(name) = lower(?1) LIMIT 1",
-            params![clean_tag],
+            "SELECT payload FROM time_events WHERE id = ?1",
+            params![event_id],
             |row| row.get(0),
         )
-        .optional()
         .map_err(to_error)?;
+    let mut payload = raw_payload
+        .as_deref()
+        .and_then(|raw| serde_json::from_str::<serde_json::Value>(raw).ok())
+        .filter(serde_json::Value::is_object)
+        .unwrap_or_else(|| json!({}));
+    if let Some(fields) = payload.as_object_mut() {
+        if clean_note.is_empty() {
+            fields.remove("note");
+        } else {
+            fields.insert("note".to_string(), json!(clean_note));
+        }
+    }
+    let payload = payload
+        .as_object()
+        .is_some_and(|fields| !fields.is_empty())
+        .then(|| payload.to_string());
 
-    let tag_id = if let Some(existing_id) = maybe_tag_id {
-        existing_id
-    } else {
-        let created_tag_id = Uuid::new_v4().to_string();
-        tx.execute(
-            "INSERT INTO tags (id, name) VALUES (?1, ?2)",
-            params![created_tag_id, clean_tag],
-        )
-        .map_err(to_error)?;
-        created_tag_id
-    };
+    conn.execute(
+        "UPDATE time_events SET payload = ?1 WHERE id = ?2",
+        params![payload, event_id],
+    )
+    .map_err(to_error)?;
+    Ok(())
+}
 
-    let inserted = tx
-        .execute(
-            "INSERT OR IGNORE INTO task_tags (task_id, tag_id, created_at) VALUES (?1, ?2, ?3)",
-            params![task_id, tag_id, ts],
+/// Starts a task. `at_ts` backdates the start event (for example when start was pressed a
+/// few minutes late); it may not lie in the future, before the task's last event, or
+/// before another task's most recent focus change. With `auto_pause_on_cross_branch_start`
+/// enabled, a running task outside the new task's branch is paused instead of rejected.
+pub fn start_task(conn: &mut Connection, task_id: String, at_ts: Option<i64>) -> AppResult<()> {
+    let previous_focus_task = latest_focus_task(conn)?;
+    let task = get_task_state(conn, &task_id)?;
+
+    if task.status == STATUS_RUNNING {
+        return Ok(());
+    }
+
+    if task.status == STATUS_PAUSED {
+        return Err(conflict_error("task is paused, use resume_task instead"));
+    }
+
+    let mut task_to_pause = None;
+    if let Some(active_task_id) = find_running_task(conn)? {
+        if active_task_id != task_id {
+            let auto_pause = read_setting_bool(conn, SETTING_AUTO_PAUSE_ON_CROSS_BRANCH_START)?;
+            if !auto_pause || in_same_branch(conn, &active_task_id, &task_id)? {
+                return Err(task_running_error("start", active_task_id));
+            }
+            task_to_pause = Some(active_task_id);
+        }
+    }
+
+    let ts = resolve_event_ts(conn, &task_id, at_ts)?;
+    let tx = begin_transaction(conn)?;
+    if let Some(paused_task_id) = &task_to_pause {
+        tx.execute(
+            "UPDATE tasks SET status = ?1 WHERE id = ?2",
+            params![STATUS_PAUSED, paused_task_id],
         )
         .map_err(to_error)?;
-
-    if inserted > 0 {
         append_event(
             &tx,
-            &task_id,
-            EVENT_TAG_ADD,
+            paused_task_id,
+            EVENT_PAUSE,
             ts,
-            Some(json!({
-                "tag": clean_tag
-            })),
+            Some(json!({ "reason": PAUSE_REASON_CROSS_BRANCH_START })),
         )?;
+        validate_task_timeline(&tx, paused_task_id)?;
     }
-
+    tx.execute(
+        "UPDATE tasks SET status = ?1 WHERE id = ?2",
+        params![STATUS_RUNNING, task_id],
+    )
+    .map_err(to_error)?;
+    append_event(&tx, &task_id, EVENT_START, ts, None)?;
+    validate_task_timeline(&tx, &task_id)?;
     tx.commit().map_err(to_error)?;
+
+    maybe_create_task_switch_suggestion(conn, previous_focus_task, &task_id, ts)?;
+
     Ok(())
 }
 
-pub fn remove_tag_from_task(
+/// Pauses a running task. `at_ts` backdates the pause; it must not precede the task's last
+/// focus event or lie in the future. `reason` is free text from the user; the reasons the
+/// app writes itself (breaks, subtask inserts, sleep) are reserved so a manual pause is
+/// never mistaken for one of them.
+pub fn pause_task(
     conn: &mut Connection,
     task_id: String,
-    tag_name: String,
+    reason: Option<String>,
+    at_ts: Option<i64>,
 ) -> AppResult<()> {
-    ensure_task_exists(conn, &task_id)?;
-    let clean_tag = sanitize_tag(&tag_name)?;
-    let ts = now_ts();
+    let payload = sanitize_pause_reason(reason)?.map(|reason| json!({ "reason": reason }));
+    pause_task_with_payload(conn, task_id, payload, at_ts)
+}
 
-    let tx = conn.transaction().map_err(to_error)?;
-    let maybe_tag_id: Option<String> = tx
-        .query_row(
-            "SELECT id FROM tags WHERE lower(name) = lower(?1) LIMIT 1",
-            params![clean_tag],
-            |row| row.get(0),
-        )
-        .optional()
-        .map_err(to_error)?;
+fn pause_task_with_payload(
+    conn: &mut Connection,
+    task_id: String,
+    payload: Option<serde_json::Value>,
+    at_ts: Option<i64>,
+) -> AppResult<()> {
+    let task = get_task_state(conn, &task_id)?;
 
-    if let Some(tag_id) = maybe_tag_id {
-        let deleted = tx
-            .execute(
-                "DELETE FROM task_tags WHERE task_id = ?1 AND tag_id = ?2",
-                params![task_id, tag_id],
-            )
-            .map_err(to_error)?;
+    if task.status == STATUS_PAUSED {
+        return Ok(());
+    }
 
-        if deleted > 0 {
-            append_event(
-                &tx,
-                &task_id,
-                EVENT_TAG_REMOVE,
-                ts,
-                Some(json!({
-                    "tag": clean_tag
-                })),
-            )?;
-        }
+    if task.status != STATUS_RUNNING {
+        return Err(conflict_error("only a running task can be paused"));
     }
 
+    let ts = resolve_session_end_ts(conn, &task_id, at_ts)?;
+    let tx = begin_transaction(conn)?;
+    tx.execute(
+        "UPDATE tasks SET status = ?1 WHERE id = ?2",
+        params![STATUS_PAUSED, task_id],
+    )
+    .map_err(to_error)?;
+    append_event(&tx, &task_id, EVENT_PAUSE, ts, payload)?;
+    validate_task_timeline(&tx, &task_id)?;
     tx.commit().map_err(to_error)?;
+
     Ok(())
 }
 
-pub fn respond_rest_suggestion(
-    conn: &mut Connection,
-    suggestion_id: i64,
-    accept: bool,
-) -> AppResult<()> {
-    if suggestion_id <= 0 {
-        return Err(validation_error("suggestion_id must be positive"));
-    }
+pub fn pause_running_task(conn: &mut Connection) -> AppResult<Option<String>> {
+    let Some(task_id) = find_running_task(conn)? else {
+        return Ok(None);
+    };
+    pause_task(conn, task_id.clone(), None, None)?;
+    Ok(Some(task_id))
+}
 
-    let status = if accept {
-        REST_STATUS_ACCEPTED
-    } else {
-        REST_STATUS_IGNORED
+/// Pauses the running task for a break, tagging the pause so `end_break` can pick it up.
+/// Returns the paused task, if any was running.
+pub fn start_break(conn: &mut Connection) -> AppResult<Option<String>> {
+    let Some(task_id) = find_running_task(conn)? else {
+        return Ok(None);
     };
-    let ts = now_ts();
+    pause_task_with_payload(
+        conn,
+        task_id.clone(),
+        Some(json!({ "reason": PAUSE_REASON_BREAK })),
+        None,
+    )?;
+    Ok(Some(task_id))
+}
 
-    {
-        let tx = conn.transaction().map_err(to_error)?;
-        let updated = tx
-            .execute(
-                "UPDATE rest_suggestions
-             SET status = ?1, responded_at = ?2
-             WHERE id = ?3 AND status = ?4",
-                params![status, ts, suggestion_id, REST_STATUS_PENDING],
-            )
-            .map_err(to_error)?;
+/// Resumes the task paused by `start_break`, unless the timeline moved on during the break
+/// (a manual pause, resume or another task). Returns the resumed task, if any.
+pub fn end_break(conn: &mut Connection) -> AppResult<Option<String>> {
+    let tx = begin_transaction(conn)?;
+    let resumed_task_id = maybe_auto_resume_after_break(&tx, now_ts())?;
+    tx.commit().map_err(to_error)?;
+    Ok(resumed_task_id)
+}
 
-        if updated > 0 {
-            tx.execute(
-                "UPDATE notifications
-                 SET status = ?1, responded_at = ?2
-                 WHERE rest_suggestion_id = ?3 AND status = ?4",
-                params![status, ts, suggestion_id, REST_STATUS_PENDING],
-            )
-            .map_err(to_error)?;
+/// Logs a quick interruption against the running task without pausing it; the optional
+/// note goes into the event payload. Tracked time is unaffected. Returns the task id.
+pub fn log_interruption(conn: &mut Connection, note: Option<String>) -> AppResult<String> {
+    let Some(task_id) = find_running_task(conn)? else {
+        return Err(conflict_error(
+            "no running task to log an interruption against",
+        ));
+    };
+    let clean_note = note.as_deref().map(str::trim).unwrap_or_default();
+    if clean_note.chars().count() > MAX_SESSION_NOTE_CHARS {
+        return Err(validation_error(format!(
+            "interruption note cannot exceed {MAX_SESSION_NOTE_CHARS} characters"
+        )));
+    }
 
-            tx.commit().map_err(to_error)?;
-            return Ok(());
-        }
+    let tx = begin_transaction(conn)?;
+    append_event(
+        &tx,
+        &task_id,
+        EVENT_INTERRUPTION,
+        now_ts(),
+        (!clean_note.is_empty()).then(|| json!({ "note": clean_note })),
+    )?;
+    tx.commit().map_err(to_error)?;
+    Ok(task_id)
+}
+
+pub fn resume_task(conn: &mut Connection, task_id: String) -> AppResult<()> {
+    let previous_focus_task = latest_focus_task(conn)?;
+    let task = get_task_state(conn, &task_id)?;
+
+    if task.status == STATUS_RUNNING {
+        return Ok(());
     }
 
-    let existing: Option<String> = conn
-        .query_row(
-            "SELECT status FROM rest_suggestions WHERE id = ?1 LIMIT 1",
-            params![suggestion_id],
-            |row| row.get(0),
-        )
-        .optional()
-        .map_err(to_error)?;
+    if task.status != STATUS_PAUSED {
+        return Err(conflict_error("only a paused task can be resumed"));
+    }
 
-    match existing {
-        Some(_) => Ok(()),
-        None => Err(not_found_error(format!(
-            "rest suggestion {suggestion_id} not found"
-        ))),
+    if let Some(active_task_id) = find_running_task(conn)? {
+        if active_task_id != task_id {
+            return Err(task_running_error("resume", active_task_id));
+        }
     }
-}
 
-pub fn get_overview(conn: &Connection, range: Option<String>) -> AppResult<OverviewResponse> {
-    let now = now_ts();
-    let (window_start, resolved_range) = resolve_window(range, now)?;
+    let ts = now_ts();
+    let tx = begin_transaction(conn)?;
+    tx.execute(
+        "UPDATE tasks SET status = ?1 WHERE id = ?2",
+        params![STATUS_RUNNING, task_id],
+    )
+    .map_err(to_error)?;
+    append_event(&tx, &task_id, EVENT_RESUME, ts, None)?;
+    tx.commit().map_err(to_error)?;
 
-    let tasks = load_tasks(conn)?;
-    let last_activated_by_task = load_last_activated_at(conn)?;
-    let tags_by_task = load_tags(conn)?;
-    let exclusive_seconds = replay_exclusive_seconds(conn, window_start, now)?;
-    let inclusive_seconds = derive_inclusive_seconds(&tasks, &exclusive_seconds);
-    let active_task_id = find_running_task(conn)?;
-    let last_used_task_id = latest_used_task(conn)?;
-    let rest_suggestion = load_latest_pending_rest_suggestion(conn)?;
-    let notifications = load_pending_notifications(conn)?;
+    maybe_create_task_switch_suggestion(conn, previous_focus_task, &task_id, ts)?;
 
-    let records = tasks
-        .into_iter()
-        .map(|task| TaskRecord {
-            id: task.id.clone(),
-            parent_id: task.parent_id.clone(),
-            title: task.title,
-            status: task.status,
-            created_at: task.created_at,
-            last_activated_at: last_activated_by_task.get(&task.id).copied(),
-            tags: tags_by_task.get(&task.id).cloned().unwrap_or_default(),
-            inclusive_seconds: *inclusive_seconds.get(&task.id).unwrap_or(&0),
-            exclusive_seconds: *exclusive_seconds.get(&task.id).unwrap_or(&0),
-        })
-        .collect::<Vec<_>>();
+    Ok(())
+}
 
-    Ok(OverviewResponse {
-        range: resolved_range,
-        generated_at: now,
-        active_task_id,
-        last_used_task_id,
-        rest_suggestion,
-        notifications,
-        tasks: records,
-    })
+/// Starts an idle or stopped task, pauses a running one and resumes a paused one. Returns
+/// the task's status afterwards.
+pub fn toggle_task(conn: &mut Connection, task_id: String) -> AppResult<String> {
+    let task = get_task_state(conn, &task_id)?;
+    match task.status.as_str() {
+        STATUS_RUNNING => pause_task(conn, task_id.clone(), None, None)?,
+        STATUS_PAUSED => resume_task(conn, task_id.clone())?,
+        _ => start_task(conn, task_id.clone(), None)?,
+    }
+    Ok(get_task_state(conn, &task_id)?.status)
 }
 
-pub fn get_focus_summary(
-    conn: &Connection,
-    range: Option<String>,
-) -> AppResult<FocusSummaryResponse> {
-    let now = now_ts();
-    let window = resolve_summary_window(conn, range, now)?;
-    let tasks = load_tasks_for_reporting(conn)?;
-    let task_lookup = tasks
-        .into_iter()
-        .map(|task| (task.id.clone(), task))
-        .collect::<HashMap<_, _>>();
-    let intervals = collect_focus_intervals(conn, Some(window.range_start), window.range_end)?;
+/// Stops a running or paused task. `at_ts` backdates the stop (and the parent auto-resume
+/// and rest suggestion it triggers); it must not precede the task's last focus event or
+/// lie in the future.
+pub fn stop_task(conn: &mut Connection, task_id: String, at_ts: Option<i64>) -> AppResult<()> {
+    let task = get_task_state(conn, &task_id)?;
 
-    let mut seconds_by_day: HashMap<i64, HashMap<String, i64>> = HashMap::new();
-    let mut segments_by_day: HashMap<i64, Vec<FocusTimelineSegment>> = HashMap::new();
-    for interval in intervals {
-        let mut cursor = interval.start_ts;
-        while cursor < interval.end_ts {
-            let day_start = local_day_start_ts(cursor);
-            let next_day_start = shift_local_day_start(day_start, 1);
-            let segment_end = interval.end_ts.min(next_day_start);
-            let duration_seconds = segment_end - cursor;
-            let day_bucket = seconds_by_day.entry(day_start).or_default();
-            *day_bucket.entry(interval.task_id.clone()).or_insert(0) += duration_seconds;
-            let task = task_lookup.get(&interval.task_id);
-            segments_by_day
-                .entry(day_start)
-                .or_default()
-                .push(FocusTimelineSegment {
-                    task_id: interval.task_id.clone(),
-                    parent_id: task.and_then(|item| item.parent_id.clone()),
-                    title: task
-                        .map(|item| item.title.clone())
-                        .unwrap_or_else(|| format!("Task {}", interval.task_id)),
-                    start_ts: cursor,
-                    end_ts: segment_end,
-                    start_offset_seconds: cursor - day_start,
-                    end_offset_seconds: segment_end - day_start,
-                    duration_seconds,
-                });
-            cursor = segment_end;
-        }
+    if task.status == STATUS_STOPPED {
+        return Ok(());
     }
 
-    for adjustment in collect_focus_adjustments(conn, Some(window.range_start), window.range_end)? {
-        let day_start = local_day_start_ts(adjustment.ts);
-        let day_bucket = seconds_by_day.entry(day_start).or_default();
-        *day_bucket.entry(adjustment.task_id).or_insert(0) += adjustment.delta_seconds;
+    if task.status == STATUS_IDLE {
+        return Err(conflict_error("cannot stop an idle task"));
     }
 
-    let days = window
-        .day_starts
-        .into_iter()
-        .rev()
-        .map(|day_start| {
-            let day_end = shift_local_day_start(day_start, 1).min(window.range_end);
-            let mut task_rows = seconds_by_day.remove(&day_start).unwrap_or_default();
-            task_rows.retain(|_, exclusive_seconds| *exclusive_seconds > 0);
-            let total_focus_seconds = task_rows.values().copied().sum::<i64>();
-            let mut tasks = task_rows
-                .drain()
-                .map(|(task_id, exclusive_seconds)| {
-                    let task = task_lookup.get(&task_id);
-                    let share_ratio = if total_focus_seconds > 0 {
-                        exclusive_seconds as f64 / total_focus_seconds as f64
-                    } else {
-                        0.0
-                    };
-                    DayTaskBreakdown {
-                        task_id: task_id.clone(),
-                        parent_id: task.and_then(|item| item.parent_id.clone()),
-                        title: task
-                            .map(|item| item.title.clone())
-                            .unwrap_or_else(|| format!("Task {task_id}")),
-                        exclusive_seconds,
-                        share_ratio,
-                    }
-                })
-                .collect::<Vec<_>>();
-            tasks.sort_by(|left, right| {
-                right
-                    .exclusive_seconds
-                    .cmp(&left.exclusive_seconds)
-                    .then_with(|| left.title.cmp(&right.title))
-            });
-            let mut timeline_segments = segments_by_day.remove(&day_start).unwrap_or_default();
-            timeline_segments.sort_by(|left, right| {
-                left.start_ts
-                    .cmp(&right.start_ts)
-                    .then_with(|| left.end_ts.cmp(&right.end_ts))
-                    .then_with(|| left.title.cmp(&right.title))
-            });
+    let ts = resolve_session_end_ts(conn, &task_id, at_ts)?;
+    let tx = begin_transaction(conn)?;
+    let should_trigger_subtask_rest = write_stop(&tx, &task_id, task.parent_id.as_deref(), ts)?;
+    tx.commit().map_err(to_error)?;
 
-            FocusSummaryDay {
-                date_key: local_date_key(day_start),
-                day_start_ts: day_start,
-                day_end_ts: day_end,
-                total_focus_seconds,
-                tasks,
-                timeline_segments,
-            }
-        })
-        .collect::<Vec<_>>();
+    if should_trigger_subtask_rest {
+        create_rest_suggestion(conn, REST_TRIGGER_SUBTASK_END, Some(task_id.as_str()), ts)?;
+    }
 
-    Ok(FocusSummaryResponse {
-        range: window.range,
-        generated_at: now,
-        days,
-    })
+    Ok(())
+}
+
+/// Marks a running or paused task stopped at `ts` and auto-resumes its parent. Returns
+/// whether the caller should raise a subtask-end rest suggestion once `tx` commits.
+fn write_stop(
+    tx: &Transaction<'_>,
+    task_id: &str,
+    parent_id: Option<&str>,
+    ts: i64,
+) -> AppResult<bool> {
+    tx.execute(
+        "UPDATE tasks SET status = ?1 WHERE id = ?2",
+        params![STATUS_STOPPED, task_id],
+    )
+    .map_err(to_error)?;
+    append_event(tx, task_id, EVENT_STOP, ts, None)?;
+    validate_task_timeline(tx, task_id)?;
+
+    match parent_id {
+        Some(parent_id) => maybe_auto_resume_parent(tx, parent_id, task_id, ts),
+        None => Ok(false),
+    }
+}
+
+/// Stops the running task at the most recent local `workday_end` boundary, backdating the
+/// stop event to the boundary itself. Focus that started after the boundary is left
+/// alone, so work resumed later in the evening is not interrupted a second time.
+pub fn enforce_workday_end(conn: &mut Connection) -> AppResult<Option<WorkdayEndStop>> {
+    let Some(workday_end) = read_setting(conn, SETTING_WORKDAY_END)?
+        .and_then(|raw| NaiveTime::parse_from_str(raw.trim(), WORKDAY_END_FORMAT).ok())
+    else {
+        return Ok(None);
+    };
+
+    let now = now_ts();
+    let today_start = local_day_start_ts(now);
+    let mut boundary = local_time_on_day(today_start, workday_end);
+    if boundary > now {
+        boundary = local_time_on_day(shift_local_day_start(today_start, -1), workday_end);
+    }
+
+    let Some(task_id) = find_running_task(conn)? else {
+        return Ok(None);
+    };
+    let focus_since: Option<i64> = conn
+        .query_row(
+            "SELECT MAX(ts) FROM time_events
+             WHERE task_id = ?1 AND event_type IN ('start', 'resume')",
+            params![task_id],
+            |row| row.get(0),
+        )
+        .map_err(to_error)?;
+    if focus_since.is_none_or(|since| since >= boundary) {
+        return Ok(None);
+    }
+
+    let tx = begin_transaction(conn)?;
+    tx.execute(
+        "UPDATE tasks SET status = ?1 WHERE id = ?2",
+        params![STATUS_STOPPED, task_id],
+    )
+    .map_err(to_error)?;
+    append_event(
+        &tx,
+        &task_id,
+        EVENT_STOP,
+        boundary,
+        Some(json!({ "reason": STOP_REASON_WORKDAY_END })),
+    )?;
+    validate_task_timeline(&tx, &task_id)?;
+    let title: String = tx
+        .query_row(
+            "SELECT title FROM tasks WHERE id = ?1",
+            params![task_id],
+            |row| row.get(0),
+        )
+        .map_err(to_error)?;
+    tx.commit().map_err(to_error)?;
+
+    Ok(Some(WorkdayEndStop {
+        task_id,
+        title,
+        stopped_at: boundary,
+    }))
+}
+
+/// Removes a machine suspend from the running task's session by pausing it at
+/// `slept_from` and resuming it at `slept_until`. Paused tasks need nothing: only running
+/// intervals count. Sessions that began after `slept_from` are left alone.
+///
+/// This runs from the background loop rather than from `resume_task`: a task left running
+/// across a suspend never goes through the resume path, and the loop's next tick is the
+/// first code to run after wake-up. The sleep-split pause does not end the session for
+/// `completed_session_durations`, so it never lands in the deviation baseline.
+pub fn clip_running_across_sleep(
+    conn: &mut Connection,
+    slept_from: i64,
+    slept_until: i64,
+) -> AppResult<Option<SleepClip>> {
+    if slept_from >= slept_until || slept_until > now_ts() {
+        return Ok(None);
+    }
+    let Some(task_id) = find_running_task(conn)? else {
+        return Ok(None);
+    };
+    let focus_since: Option<i64> = conn
+        .query_row(
+            "SELECT MAX(ts) FROM time_events
+             WHERE task_id = ?1 AND event_type IN ('start', 'resume')",
+            params![task_id],
+            |row| row.get(0),
+        )
+        .map_err(to_error)?;
+    if focus_since.is_none_or(|since| since >= slept_from) {
+        return Ok(None);
+    }
+
+    let tx = begin_transaction(conn)?;
+    append_event(
+        &tx,
+        &task_id,
+        EVENT_PAUSE,
+        slept_from,
+        Some(json!({ "reason": PAUSE_REASON_SLEEP })),
+    )?;
+    append_event(
+        &tx,
+        &task_id,
+        EVENT_RESUME,
+        slept_until,
+        Some(json!({ "reason": RESUME_REASON_WAKE })),
+    )?;
+    validate_task_timeline(&tx, &task_id)?;
+    tx.commit().map_err(to_error)?;
+
+    Ok(Some(SleepClip {
+        task_id,
+        slept_from,
+        slept_until,
+    }))
+}
+
+/// Stops tasks that have sat paused for at least `auto_stop_paused_after` seconds. The
+/// stop is backdated to the pause so no time is added. Parents paused by
+/// `insert_subtask_and_start` are skipped since they are waiting on their child.
+pub fn auto_stop_stale_paused_tasks(conn: &mut Connection) -> AppResult<Vec<PausedAutoStop>> {
+    let threshold = read_setting_i64(conn, SETTING_AUTO_STOP_PAUSED_AFTER, 0)?;
+    if threshold <= 0 {
+        return Ok(Vec::new());
+    }
+
+    let now = now_ts();
+    let mut stmt = conn
+        .prepare(
+            "SELECT t.id, t.title, e.ts, e.payload
+             FROM tasks t
+             JOIN time_events e ON e.id = (
+                 SELECT id FROM time_events
+                 WHERE task_id = t.id AND event_type IN ('start', 'pause', 'resume', 'stop')
+                 ORDER BY ts DESC, id DESC
+                 LIMIT 1
+             )
+             WHERE t.status = ?1 AND t.archived_at IS NULL AND e.event_type = ?2
+               AND e.ts <= ?3
+             ORDER BY e.ts ASC",
+        )
+        .map_err(to_error)?;
+    let candidates = stmt
+        .query_map(
+            params![STATUS_PAUSED, EVENT_PAUSE, now - threshold],
+            |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, i64>(2)?,
+                    row.get::<_, Option<String>>(3)?,
+                ))
+            },
+        )
+        .map_err(to_error)?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(to_error)?;
+    drop(stmt);
+
+    let mut stopped = Vec::new();
+    let tx = begin_transaction(conn)?;
+    for (task_id, title, paused_at, payload) in candidates {
+        let (_, reason) = parse_event_payload(payload.as_deref());
+        if reason.as_deref() == Some(PAUSE_REASON_INSERT_SUBTASK) {
+            continue;
+        }
+        tx.execute(
+            "UPDATE tasks SET status = ?1 WHERE id = ?2",
+            params![STATUS_STOPPED, task_id],
+        )
+        .map_err(to_error)?;
+        append_event(
+            &tx,
+            &task_id,
+            EVENT_STOP,
+            paused_at,
+            Some(json!({ "reason": STOP_REASON_PAUSED_TIMEOUT })),
+        )?;
+        validate_task_timeline(&tx, &task_id)?;
+        stopped.push(PausedAutoStop {
+            task_id,
+            title,
+            stopped_at: paused_at,
+        });
+    }
+    tx.commit().map_err(to_error)?;
+
+    Ok(stopped)
+}
+
+/// Reports today's tracked total against the `daily_max_seconds` cap. A cap of 0 turns
+/// the check off, so `exceeded` stays false however long the day runs.
+pub fn get_overwork_status(conn: &Connection) -> AppResult<OverworkStatus> {
+    let now = now_ts();
+    let day_start_ts = local_day_start_ts(now);
+    let daily_max_seconds = read_setting_i64(conn, SETTING_DAILY_MAX_SECONDS, 0)?.max(0);
+    let total_seconds = replay_exclusive_seconds(conn, Some(day_start_ts), now)?
+        .values()
+        .sum::<i64>();
+
+    Ok(OverworkStatus {
+        date_key: local_date_key(now),
+        day_start_ts,
+        total_seconds,
+        daily_max_seconds,
+        exceeded: daily_max_seconds > 0 && total_seconds > daily_max_seconds,
+        running_task_id: find_running_task(conn)?,
+    })
+}
+
+pub fn complete_task(conn: &mut Connection, task_id: String) -> AppResult<()> {
+    let task = get_task_state(conn, &task_id)?;
+    let ts = now_ts();
+    let mut should_trigger_subtask_rest = false;
+    let tx = begin_transaction(conn)?;
+    if task.status == STATUS_RUNNING || task.status == STATUS_PAUSED {
+        should_trigger_subtask_rest = write_stop(&tx, &task_id, task.parent_id.as_deref(), ts)?;
+    }
+    tx.execute(
+        "UPDATE tasks SET completed_at = ?1 WHERE id = ?2 AND archived_at IS NULL AND completed_at IS NULL",
+        params![ts, task_id],
+    )
+    .map_err(to_error)?;
+    tx.commit().map_err(to_error)?;
+
+    if should_trigger_subtask_rest {
+        create_rest_suggestion(conn, REST_TRIGGER_SUBTASK_END, Some(task_id.as_str()), ts)?;
+    }
+
+    Ok(())
+}
+
+pub fn reopen_task(conn: &mut Connection, task_id: String) -> AppResult<()> {
+    ensure_task_exists(conn, &task_id)?;
+    conn.execute(
+        "UPDATE tasks SET completed_at = NULL WHERE id = ?1 AND archived_at IS NULL",
+        params![task_id],
+    )
+    .map_err(to_error)?;
+    Ok(())
+}
+
+pub fn set_task_due(conn: &Connection, task_id: String, due_at: Option<i64>) -> AppResult<()> {
+    ensure_task_exists(conn, &task_id)?;
+    if due_at.is_some_and(|ts| ts <= 0) {
+        return Err(validation_error("due_at must be a positive unix timestamp"));
+    }
+
+    conn.execute(
+        "UPDATE tasks SET due_at = ?1 WHERE id = ?2 AND archived_at IS NULL",
+        params![due_at, task_id],
+    )
+    .map_err(to_error)?;
+    Ok(())
+}
+
+/// Links a task to its ticket or doc. An empty `url` clears the link.
+pub fn set_task_reference(conn: &Connection, task_id: String, url: String) -> AppResult<()> {
+    ensure_task_exists(conn, &task_id)?;
+    let reference_url = sanitize_reference_url(&url)?;
+
+    conn.execute(
+        "UPDATE tasks SET reference_url = ?1 WHERE id = ?2 AND archived_at IS NULL",
+        params![reference_url, task_id],
+    )
+    .map_err(to_error)?;
+    Ok(())
+}
+
+pub fn adjust_task_focus(
+    conn: &mut Connection,
+    task_id: String,
+    delta_seconds: i64,
+) -> AppResult<()> {
+    ensure_task_exists(conn, &task_id)?;
+    if delta_seconds == 0 {
+        return Err(validation_error("delta_seconds cannot be zero"));
+    }
+
+    let now = now_ts();
+    let total_focus_seconds = task_total_focus_seconds(conn, &task_id, now)?;
+    if total_focus_seconds + delta_seconds < 0 {
+        return Err(validation_error(
+            "adjustment would make task focus time negative",
+        ));
+    }
+
+    let tx = begin_transaction(conn)?;
+    append_event(
+        &tx,
+        &task_id,
+        EVENT_ADJUST,
+        now,
+        Some(json!({
+            "delta_seconds": delta_seconds
+        })),
+    )?;
+    tx.commit().map_err(to_error)?;
+
+    Ok(())
+}
+
+pub fn insert_subtask_and_start(
+    conn: &mut Connection,
+    parent_task_id: String,
+    title: String,
+) -> AppResult<String> {
+    let clean_title = sanitize_title(&title)?;
+    let parent = get_task_state(conn, &parent_task_id)?;
+
+    if parent.status != STATUS_RUNNING {
+        return Err(conflict_error(
+            "insert_subtask_and_start requires the parent task to be running",
+        ));
+    }
+
+    if let Some(active_task_id) = find_running_task(conn)? {
+        if active_task_id != parent_task_id {
+            return Err(AppError::task_running(
+                format!("cannot insert subtask while task {active_task_id} is running"),
+                active_task_id,
+            ));
+        }
+    } else {
+        return Err(conflict_error("no running task found for subtask insertion"));
+    }
+
+    // A root task sits at depth 0, so the new child lands one level below the parent.
+    let max_depth = read_setting_i64(
+        conn,
+        SETTING_MAX_SUBTASK_INSERT_DEPTH,
+        DEFAULT_MAX_SUBTASK_INSERT_DEPTH,
+    )?;
+    let child_depth = load_ancestor_chain(conn, &parent_task_id)?.len() as i64;
+    if child_depth > max_depth {
+        return Err(validation_error(format!(
+            "cannot insert a subtask deeper than {max_depth} levels; raise {SETTING_MAX_SUBTASK_INSERT_DEPTH} to allow it"
+        )));
+    }
+
+    let child_task_id = Uuid::new_v4().to_string();
+    let ts = now_ts();
+    let tx = begin_transaction(conn)?;
+
+    tx.execute(
+        "UPDATE tasks SET status = ?1 WHERE id = ?2",
+        params![STATUS_PAUSED, parent_task_id],
+    )
+    .map_err(to_error)?;
+    append_event(
+        &tx,
+        &parent_task_id,
+        EVENT_PAUSE,
+        ts,
+        Some(json!({
+            "reason": PAUSE_REASON_INSERT_SUBTASK,
+            "child_id": child_task_id
+        })),
+    )?;
+
+    tx.execute(
+        "INSERT INTO tasks (id, parent_id, title, status, created_at) VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![
+            child_task_id,
+            parent_task_id,
+            clean_title,
+            STATUS_RUNNING,
+            ts
+        ],
+    )
+    .map_err(to_error)?;
+    append_event(
+        &tx,
+        &child_task_id,
+        EVENT_CREATE,
+        ts,
+        Some(json!({ "parent_id": parent_task_id })),
+    )?;
+    append_event(
+        &tx,
+        &child_task_id,
+        EVENT_START,
+        ts,
+        Some(json!({
+            "reason": "insert_subtask",
+            "parent_id": parent_task_id
+        })),
+    )?;
+
+    tx.commit().map_err(to_error)?;
+
+    create_rest_suggestion(
+        conn,
+        REST_TRIGGER_TASK_SWITCH,
+        Some(parent_task_id.as_str()),
+        ts,
+    )?;
+
+    Ok(child_task_id)
+}
+
+pub fn add_tag_to_task(conn: &mut Connection, task_id: String, tag_name: String) -> AppResult<()> {
+    ensure_task_exists(conn, &task_id)?;
+    let clean_tag = sanitize_tag(&tag_name)?;
+    let ts = now_ts();
+
+    let tx = begin_transaction(conn)?;
+    attach_tag(&tx, &task_id, &clean_tag, ts)?;
+    tx.commit().map_err(to_error)?;
+    Ok(())
+}
+
+/// Tags the task and returns the overview for `range` filtered to that tag, so the caller
+/// lands on the tag's view in one round-trip. The range is checked before tagging.
+pub fn tag_and_view(
+    conn: &mut Connection,
+    task_id: String,
+    tag_name: String,
+    range: Option<String>,
+) -> AppResult<OverviewResponse> {
+    resolve_window(range.clone(), now_ts())?;
+    add_tag_to_task(conn, task_id, tag_name.clone())?;
+    get_overview(
+        conn,
+        range,
+        OverviewOptions {
+            tag: Some(tag_name),
+            ..OverviewOptions::default()
+        },
+    )
+}
+
+fn attach_tag(tx: &Transaction<'_>, task_id: &str, clean_tag: &str, ts: i64) -> AppResult<()> {
+    let tag_id = find_or_create_tag_id(tx, clean_tag)?;
+    let inserted = tx
+        .execute(
+            "INSERT OR IGNORE INTO task_tags (task_id, tag_id, created_at) VALUES (?1, ?2, ?3)",
+            params![task_id, tag_id, ts],
+        )
+        .map_err(to_error)?;
+
+    if inserted > 0 {
+        append_event(
+            tx,
+            task_id,
+            EVENT_TAG_ADD,
+            ts,
+            Some(json!({
+                "tag": clean_tag
+            })),
+        )?;
+    }
+
+    Ok(())
+}
+
+fn find_or_create_tag_id(tx: &Transaction<'_>, clean_tag: &str) -> AppResult<String> {
+    let maybe_tag_id: Option<String> = tx
+        .query_row(
+            "SELECT id FROM tags WHERE lower(name) = lower(?1) LIMIT 1",
+            params![clean_tag],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(to_error)?;
+    if let Some(existing_id) = maybe_tag_id {
+        return Ok(existing_id);
+    }
+
+    let created_tag_id = Uuid::new_v4().to_string();
+    let (tag_key, tag_value) = split_structured_tag(clean_tag).unzip();
+    tx.execute(
+        "INSERT INTO tags (id, name, tag_key, tag_value) VALUES (?1, ?2, ?3, ?4)",
+        params![created_tag_id, clean_tag, tag_key, tag_value],
+    )
+    .map_err(to_error)?;
+    Ok(created_tag_id)
+}
+
+/// Imports a Toggl-style CSV export. The header row must name a `description` column,
+/// either `start`/`end` or Toggl's split `start date`/`start time` and `end date`/`end time`
+/// columns, and optionally a comma-separated `tags` column. Timestamps may be RFC 3339 or
+/// local `YYYY-MM-DD HH:MM[:SS]`. Tasks are matched by title, malformed or overlapping
+/// rows are skipped and reported, and the rest is written in one transaction.
+pub fn import_csv_sessions(conn: &mut Connection, csv: String) -> AppResult<CsvImportReport> {
+    let mut records = parse_csv_records(&csv).into_iter();
+    let Some((_, header)) = records.next() else {
+        return Err(validation_error("csv is empty"));
+    };
+    let columns = resolve_csv_columns(&header)?;
+
+    let now = now_ts();
+    let mut occupied = collect_focus_intervals(conn, None, now)?
+        .into_iter()
+        .map(|interval| (interval.start_ts, interval.end_ts))
+        .collect::<Vec<_>>();
+
+    let tx = begin_transaction(conn)?;
+    let mut task_ids_by_title: HashMap<String, String> = HashMap::new();
+    let mut tasks_created = 0;
+    let mut sessions_imported = 0;
+    let mut skipped_rows = Vec::new();
+
+    for (line, fields) in records {
+        if fields.iter().all(|field| field.trim().is_empty()) {
+            continue;
+        }
+
+        let entry = match parse_csv_entry(&columns, &fields, now) {
+            Ok(entry) => entry,
+            Err(message) => {
+                skipped_rows.push(CsvImportRowError { line, message });
+                continue;
+            }
+        };
+        if occupied
+            .iter()
+            .any(|(start, end)| entry.start_ts < *end && *start < entry.end_ts)
+        {
+            skipped_rows.push(CsvImportRowError {
+                line,
+                message: "entry overlaps already tracked time".to_string(),
+            });
+            continue;
+        }
+
+        let task_id = match task_ids_by_title.get(&entry.title) {
+            Some(task_id) => task_id.clone(),
+            None => {
+                let (task_id, created) =
+                    find_or_create_import_task(&tx, &entry.title, entry.start_ts)?;
+                if created {
+                    tasks_created += 1;
+                }
+                task_ids_by_title.insert(entry.title.clone(), task_id.clone());
+                task_id
+            }
+        };
+
+        if !session_fits_timeline(&tx, &task_id, entry.start_ts, entry.end_ts)? {
+            skipped_rows.push(CsvImportRowError {
+                line,
+                message: "entry falls inside an open or paused session of this task".to_string(),
+            });
+            continue;
+        }
+
+        let payload = json!({ "reason": IMPORT_REASON_CSV });
+        append_event(
+            &tx,
+            &task_id,
+            EVENT_START,
+            entry.start_ts,
+            Some(payload.clone()),
+        )?;
+        append_event(&tx, &task_id, EVENT_STOP, entry.end_ts, Some(payload))?;
+        tx.execute(
+            "UPDATE tasks SET status = ?1 WHERE id = ?2 AND status = ?3",
+            params![STATUS_STOPPED, task_id, STATUS_IDLE],
+        )
+        .map_err(to_error)?;
+        for tag in &entry.tags {
+            attach_tag(&tx, &task_id, tag, now)?;
+        }
+
+        occupied.push((entry.start_ts, entry.end_ts));
+        sessions_imported += 1;
+    }
+
+    for task_id in task_ids_by_title.values() {
+        validate_task_timeline(&tx, task_id)?;
+    }
+    tx.commit().map_err(to_error)?;
+
+    Ok(CsvImportReport {
+        tasks_created,
+        sessions_imported,
+        skipped_rows,
+    })
+}
+
+pub fn remove_tag_from_task(
+    conn: &mut Connection,
+    task_id: String,
+    tag_name: String,
+) -> AppResult<()> {
+    ensure_task_exists(conn, &task_id)?;
+    let clean_tag = sanitize_tag(&tag_name)?;
+    let ts = now_ts();
+
+    let tx = begin_transaction(conn)?;
+    let maybe_tag_id: Option<String> = tx
+        .query_row(
+            "SELECT id FROM tags WHERE lower(name) = lower(?1) LIMIT 1",
+            params![clean_tag],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(to_error)?;
+
+    if let Some(tag_id) = maybe_tag_id {
+        let deleted = tx
+            .execute(
+                "DELETE FROM task_tags WHERE task_id = ?1 AND tag_id = ?2",
+                params![task_id, tag_id],
+            )
+            .map_err(to_error)?;
+
+        if deleted > 0 {
+            append_event(
+                &tx,
+                &task_id,
+                EVENT_TAG_REMOVE,
+                ts,
+                Some(json!({
+                    "tag": clean_tag
+                })),
+            )?;
+        }
+    }
+
+    tx.commit().map_err(to_error)?;
+    Ok(())
+}
+
+pub fn list_untagged_tasks(conn: &Connection) -> AppResult<Vec<TaskRecord>> {
+    let untagged_ids = {
+        let mut stmt = conn
+            .prepare(
+                "SELECT t.id
+                 FROM tasks t
+                 WHERE t.archived_at IS NULL
+                   AND NOT EXISTS (SELECT 1 FROM task_tags tt WHERE tt.task_id = t.id)",
+            )
+            .map_err(to_error)?;
+        let rows = stmt
+            .query_map([], |row| row.get::<_, String>(0))
+            .map_err(to_error)?;
+        rows.collect::<Result<HashSet<_>, _>>().map_err(to_error)?
+    };
+
+    Ok(load_task_records(conn, None, now_ts(), TimeBasis::Active)?
+        .into_iter()
+        .filter(|record| untagged_ids.contains(&record.id))
+        .collect())
+}
+
+/// Suggests tags that most often appear on other tasks alongside this task's tags,
+/// ranked by how many (shared tag, task) pairs they co-occur in.
+pub fn suggest_tags(
+    conn: &Connection,
+    task_id: String,
+    limit: Option<i64>,
+) -> AppResult<Vec<TagSuggestion>> {
+    ensure_task_exists(conn, &task_id)?;
+    let limit = limit.unwrap_or(DEFAULT_TAG_SUGGESTION_LIMIT);
+    if limit <= 0 {
+        return Err(validation_error("limit must be positive"));
+    }
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT tg.name, COUNT(*) AS co_occurrences
+             FROM task_tags mine
+             INNER JOIN task_tags peer ON peer.tag_id = mine.tag_id AND peer.task_id <> mine.task_id
+             INNER JOIN task_tags other ON other.task_id = peer.task_id
+             INNER JOIN tags tg ON tg.id = other.tag_id
+             WHERE mine.task_id = ?1
+               AND other.tag_id NOT IN (SELECT tag_id FROM task_tags WHERE task_id = ?1)
+             GROUP BY tg.id
+             ORDER BY co_occurrences DESC, tg.name ASC
+             LIMIT ?2",
+        )
+        .map_err(to_error)?;
+
+    let rows = stmt
+        .query_map(params![task_id, limit], |row| {
+            Ok(TagSuggestion {
+                name: row.get(0)?,
+                co_occurrences: row.get(1)?,
+            })
+        })
+        .map_err(to_error)?;
+
+    rows.collect::<Result<Vec<_>, _>>().map_err(to_error)
+}
+
+/// Sets or clears (`None`) a tag's chip color. Colors are `#rgb` or `#rrggbb` and are
+/// stored lowercased.
+pub fn set_tag_color(conn: &Connection, tag_name: String, color: Option<String>) -> AppResult<()> {
+    let clean_tag = sanitize_tag(&tag_name)?;
+    let clean_color = color.as_deref().map(sanitize_color).transpose()?;
+    let updated = conn
+        .execute(
+            "UPDATE tags SET color = ?1 WHERE lower(name) = lower(?2)",
+            params![clean_color, clean_tag],
+        )
+        .map_err(to_error)?;
+    if updated == 0 {
+        return Err(not_found_error(format!("tag '{clean_tag}' not found")));
+    }
+    Ok(())
+}
+
+/// Tags carried by at least one non-archived task, most used first. `limit` defaults to 10.
+pub fn get_top_tags(conn: &Connection, limit: Option<i64>) -> AppResult<Vec<TagRecord>> {
+    let limit = limit.unwrap_or(10);
+    if limit <= 0 {
+        return Err(validation_error("limit must be positive"));
+    }
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT tg.name, tg.tag_key, tg.tag_value, tg.color, COUNT(t.id) AS task_count
+             FROM tags tg
+             INNER JOIN task_tags tt ON tt.tag_id = tg.id
+             INNER JOIN tasks t ON t.id = tt.task_id AND t.archived_at IS NULL
+             GROUP BY tg.id
+             ORDER BY task_count DESC, tg.name ASC
+             LIMIT ?1",
+        )
+        .map_err(to_error)?;
+
+    let rows = stmt
+        .query_map(params![limit], |row| {
+            Ok(TagRecord {
+                name: row.get(0)?,
+                key: row.get(1)?,
+                value: row.get(2)?,
+                color: row.get(3)?,
+                task_count: row.get(4)?,
+            })
+        })
+        .map_err(to_error)?;
+
+    rows.collect::<Result<Vec<_>, _>>().map_err(to_error)
+}
+
+pub fn get_tags_by_key(conn: &Connection, key: String) -> AppResult<Vec<TagRecord>> {
+    let clean_key = key.trim().to_lowercase();
+    if clean_key.is_empty() {
+        return Err(validation_error("tag key cannot be empty"));
+    }
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT tg.name, tg.tag_key, tg.tag_value, tg.color, COUNT(t.id)
+             FROM tags tg
+             LEFT JOIN task_tags tt ON tt.tag_id = tg.id
+             LEFT JOIN tasks t ON t.id = tt.task_id AND t.archived_at IS NULL
+             WHERE tg.tag_key = ?1
+             GROUP BY tg.id
+             ORDER BY tg.tag_value ASC",
+        )
+        .map_err(to_error)?;
+
+    let rows = stmt
+        .query_map(params![clean_key], |row| {
+            Ok(TagRecord {
+                name: row.get(0)?,
+                key: row.get(1)?,
+                value: row.get(2)?,
+                color: row.get(3)?,
+                task_count: row.get(4)?,
+            })
+        })
+        .map_err(to_error)?;
+
+    rows.collect::<Result<Vec<_>, _>>().map_err(to_error)
+}
+
+pub fn respond_rest_suggestion(
+    conn: &mut Connection,
+    suggestion_id: i64,
+    accept: bool,
+) -> AppResult<()> {
+    let status = if accept {
+        REST_STATUS_ACCEPTED
+    } else {
+        REST_STATUS_IGNORED
+    };
+    record_rest_suggestion_response(conn, suggestion_id, status, None)
+}
+
+/// Answers every pending suggestion at once, e.g. a backlog that piled up before expiry
+/// ran. Accepted ones take their suggested minutes. Returns how many were updated.
+pub fn respond_all_pending_rest_suggestions(
+    conn: &mut Connection,
+    accept: bool,
+) -> AppResult<usize> {
+    let status = if accept {
+        REST_STATUS_ACCEPTED
+    } else {
+        REST_STATUS_IGNORED
+    };
+    let ts = now_ts();
+
+    let tx = begin_transaction(conn)?;
+    tx.execute(
+        "UPDATE notifications
+         SET status = ?1, responded_at = ?2
+         WHERE status = ?3
+           AND rest_suggestion_id IN (SELECT id FROM rest_suggestions WHERE status = ?3)",
+        params![status, ts, REST_STATUS_PENDING],
+    )
+    .map_err(to_error)?;
+    let updated = tx
+        .execute(
+            "UPDATE rest_suggestions
+             SET status = ?1,
+                 responded_at = ?2,
+                 accepted_minutes = CASE WHEN ?1 = ?4 THEN suggested_minutes ELSE NULL END
+             WHERE status = ?3",
+            params![status, ts, REST_STATUS_PENDING, REST_STATUS_ACCEPTED],
+        )
+        .map_err(to_error)?;
+    tx.commit().map_err(to_error)?;
+
+    Ok(updated)
+}
+
+pub fn accept_rest_suggestion_with_minutes(
+    conn: &mut Connection,
+    suggestion_id: i64,
+    minutes: i64,
+) -> AppResult<()> {
+    if minutes <= 0 {
+        return Err(validation_error("minutes must be positive"));
+    }
+    if minutes > MAX_REST_MINUTES {
+        return Err(validation_error(format!(
+            "minutes cannot exceed {MAX_REST_MINUTES}"
+        )));
+    }
+    record_rest_suggestion_response(conn, suggestion_id, REST_STATUS_ACCEPTED, Some(minutes))
+}
+
+fn record_rest_suggestion_response(
+    conn: &mut Connection,
+    suggestion_id: i64,
+    status: &str,
+    accepted_minutes: Option<i64>,
+) -> AppResult<()> {
+    if suggestion_id <= 0 {
+        return Err(validation_error("suggestion_id must be positive"));
+    }
+
+    let ts = now_ts();
+
+    {
+        let tx = begin_transaction(conn)?;
+        let updated = tx
+            .execute(
+                "UPDATE rest_suggestions
+             SET status = ?1,
+                 responded_at = ?2,
+                 accepted_minutes = CASE
+                     WHEN ?1 = ?5 THEN COALESCE(?6, suggested_minutes)
+                     ELSE NULL
+                 END
+             WHERE id = ?3 AND status = ?4",
+                params![
+                    status,
+                    ts,
+                    suggestion_id,
+                    REST_STATUS_PENDING,
+                    REST_STATUS_ACCEPTED,
+                    accepted_minutes
+                ],
+            )
+            .map_err(to_error)?;
+
+        if updated > 0 {
+            tx.execute(
+                "UPDATE notifications
+                 SET status = ?1, responded_at = ?2
+                 WHERE rest_suggestion_id = ?3 AND status = ?4",
+                params![status, ts, suggestion_id, REST_STATUS_PENDING],
+            )
+            .map_err(to_error)?;
+
+            tx.commit().map_err(to_error)?;
+            return Ok(());
+        }
+    }
+
+    let existing: Option<String> = conn
+        .query_row(
+            "SELECT status FROM rest_suggestions WHERE id = ?1 LIMIT 1",
+            params![suggestion_id],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(to_error)?;
+
+    match existing {
+        Some(_) => Ok(()),
+        None => Err(not_found_error(format!(
+            "rest suggestion {suggestion_id} not found"
+        ))),
+    }
+}
+
+pub fn expire_stale_rest_suggestions(conn: &mut Connection) -> AppResult<usize> {
+    let ts = now_ts();
+    let Some(cutoff) = rest_suggestion_expiry_cutoff(conn, ts)? else {
+        return Ok(0);
+    };
+
+    let tx = begin_transaction(conn)?;
+    tx.execute(
+        "UPDATE notifications
+         SET status = ?1, responded_at = ?2
+         WHERE status = ?3
+           AND rest_suggestion_id IN (
+               SELECT id FROM rest_suggestions WHERE status = ?3 AND created_at < ?4
+           )",
+        params![REST_STATUS_IGNORED, ts, REST_STATUS_PENDING, cutoff],
+    )
+    .map_err(to_error)?;
+    let expired = tx
+        .execute(
+            "UPDATE rest_suggestions
+             SET status = ?1, responded_at = ?2, response_reason = ?3
+             WHERE status = ?4 AND created_at < ?5",
+            params![
+                REST_STATUS_IGNORED,
+                ts,
+                REST_RESPONSE_REASON_EXPIRED,
+                REST_STATUS_PENDING,
+                cutoff
+            ],
+        )
+        .map_err(to_error)?;
+    tx.commit().map_err(to_error)?;
+
+    Ok(expired)
+}
+
+/// Measures the inputs of the rest rules for the running task right now, without
+/// evaluating the rules or storing anything. All zero when no task is running.
+pub fn get_rest_signals(conn: &Connection) -> AppResult<RestSignals> {
+    let now = now_ts();
+    match find_running_task(conn)? {
+        Some(task_id) => measure_rest_signals(conn, task_id, now),
+        None => Ok(RestSignals {
+            measured_at: now,
+            ..RestSignals::default()
+        }),
+    }
+}
+
+fn measure_rest_signals(conn: &Connection, task_id: String, now: i64) -> AppResult<RestSignals> {
+    let focus_seconds = current_focus_seconds(conn, &task_id, now)?;
+    Ok(RestSignals {
+        switch_count_30m: count_task_switches(conn, now - SWITCH_WINDOW_SECONDS, now)?,
+        deviation_ratio: compute_deviation_ratio(conn, &task_id, focus_seconds, now)?,
+        task_id: Some(task_id),
+        measured_at: now,
+        focus_seconds,
+    })
+}
+
+/// Evaluates the rest rules against the running task's current session as if it ended now.
+/// Nothing is stored; returns an all-zero preview when no task is running.
+pub fn preview_current_rest(conn: &Connection) -> AppResult<RestPreview> {
+    let Some(task_id) = find_running_task(conn)? else {
+        return Ok(RestPreview::default());
+    };
+
+    let signals = measure_rest_signals(conn, task_id, now_ts())?;
+    let (suggested_minutes, reasons) = evaluate_rest_rules(
+        signals.focus_seconds,
+        signals.switch_count_30m,
+        signals.deviation_ratio,
+        read_rest_minute_tiers(conn)?,
+    );
+
+    Ok(RestPreview {
+        task_id: signals.task_id,
+        focus_seconds: signals.focus_seconds,
+        switch_count_30m: signals.switch_count_30m,
+        deviation_ratio: signals.deviation_ratio,
+        suggested_minutes,
+        reasons,
+    })
+}
+
+/// Replays the task switches and subtask ends in the window and counts the suggestions
+/// `config` would have produced, per minute tier. Nothing is written.
+pub fn simulate_rest_rules(
+    conn: &Connection,
+    config: RestRulesConfig,
+    range: Option<String>,
+) -> AppResult<RestSimulationResponse> {
+    let now = now_ts();
+    let window = resolve_summary_window(conn, range, now)?;
+    let minute_tiers = match config.minute_tiers.as_deref() {
+        Some(raw) => parse_rest_minute_tiers(raw).ok_or_else(|| {
+            validation_error(format!(
+                "minute_tiers must be three non-decreasing minute values between 0 and {MAX_REST_MINUTES}, like 3,8,15"
+            ))
+        })?,
+        None => read_rest_minute_tiers(conn)?,
+    };
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT task_id, event_type, ts, payload
+             FROM time_events
+             WHERE event_type IN ('start', 'resume') AND ts <= ?1
+             ORDER BY ts ASC, id ASC",
+        )
+        .map_err(to_error)?;
+    let rows = stmt
+        .query_map(params![window.range_end], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, i64>(2)?,
+                row.get::<_, Option<String>>(3)?,
+            ))
+        })
+        .map_err(to_error)?;
+
+    // Mirrors the live triggers: an auto-resumed parent marks a subtask end, any other
+    // focus change away from the previous task is a task switch.
+    let mut triggers: Vec<(&str, String, i64)> = Vec::new();
+    let mut previous_focus_task: Option<String> = None;
+    for row in rows {
+        let (task_id, event_type, ts, payload) = row.map_err(to_error)?;
+        let finished_child_id = if event_type == EVENT_RESUME {
+            auto_resumed_child_id(payload.as_deref())
+        } else {
+            None
+        };
+
+        let trigger = match (finished_child_id, previous_focus_task.take()) {
+            (Some(child_id), _) => Some((REST_TRIGGER_SUBTASK_END, child_id)),
+            (None, Some(previous)) if previous != task_id => {
+                Some((REST_TRIGGER_TASK_SWITCH, previous))
+            }
+            _ => None,
+        };
+        if let Some((trigger_type, source_task_id)) = trigger {
+            if ts >= window.range_start {
+                triggers.push((trigger_type, source_task_id, ts));
+            }
+        }
+        previous_focus_task = Some(task_id);
+    }
+
+    let mut task_switch_count = 0;
+    let mut subtask_end_count = 0;
+    let mut prompts_by_minutes: HashMap<i64, i64> = HashMap::new();
+    for (trigger_type, source_task_id, ts) in &triggers {
+        if *trigger_type == REST_TRIGGER_SUBTASK_END {
+            subtask_end_count += 1;
+        } else {
+            task_switch_count += 1;
+        }
+        let focus_seconds = latest_closed_session_duration(conn, source_task_id, *ts)?.unwrap_or(0);
+        let switch_count_30m = count_task_switches(conn, ts - SWITCH_WINDOW_SECONDS, *ts)?;
+        let deviation_ratio = compute_deviation_ratio(conn, source_task_id, focus_seconds, *ts)?;
+        let (suggested_minutes, _) = evaluate_rest_rules(
+            focus_seconds,
+            switch_count_30m,
+            deviation_ratio,
+            minute_tiers,
+        );
+        if suggested_minutes > 0 {
+            *prompts_by_minutes.entry(suggested_minutes).or_insert(0) += 1;
+        }
+    }
+
+    let mut tier_minutes = minute_tiers
+        .iter()
+        .copied()
+        .filter(|minutes| *minutes > 0)
+        .collect::<Vec<_>>();
+    tier_minutes.dedup();
+
+    Ok(RestSimulationResponse {
+        range: window.range,
+        generated_at: now,
+        minute_tiers: minute_tiers.to_vec(),
+        trigger_count: triggers.len() as i64,
+        task_switch_count,
+        subtask_end_count,
+        prompt_count: prompts_by_minutes.values().sum(),
+        tiers: tier_minutes
+            .into_iter()
+            .map(|minutes| RestTierCount {
+                minutes,
+                count: prompts_by_minutes.get(&minutes).copied().unwrap_or(0),
+            })
+            .collect(),
+    })
+}
+
+pub fn get_settings(conn: &Connection) -> AppResult<Vec<SettingRecord>> {
+    let mut records = Vec::with_capacity(SETTING_KEYS.len());
+    for key in SETTING_KEYS {
+        let stored = read_setting(conn, key)?;
+        let is_default = stored.is_none();
+        records.push(SettingRecord {
+            key: key.to_string(),
+            value: stored.unwrap_or_else(|| setting_default(key)),
+            is_default,
+        });
+    }
+    Ok(records)
+}
+
+pub fn update_setting(conn: &mut Connection, key: String, value: String) -> AppResult<()> {
+    let key = key.trim();
+    if !SETTING_KEYS.contains(&key) {
+        return Err(validation_error(format!("unknown setting '{key}'")));
+    }
+
+    if value.trim().is_empty() {
+        conn.execute("DELETE FROM settings WHERE key = ?1", params![key])
+            .map_err(to_error)?;
+        return Ok(());
+    }
+
+    let normalized = normalize_setting_value(key, &value)?;
+    conn.execute(
+        "INSERT INTO settings (key, value, updated_at) VALUES (?1, ?2, ?3)
+         ON CONFLICT(key) DO UPDATE SET value = excluded.value, updated_at = excluded.updated_at",
+        params![key, normalized, now_ts()],
+    )
+    .map_err(to_error)?;
+    // Cached baselines only count sessions that clear the minimum length.
+    if key == SETTING_MIN_SESSION_SECONDS {
+        rebuild_baselines(conn)?;
+    }
+    Ok(())
+}
+
+/// Recomputes the cached deviation baseline of every task from its event history.
+/// Returns how many tasks have at least one completed session.
+pub fn rebuild_baselines(conn: &mut Connection) -> AppResult<usize> {
+    let tx = begin_transaction(conn)?;
+    tx.execute("DELETE FROM task_baselines", [])
+        .map_err(to_error)?;
+    let task_ids = {
+        let mut stmt = tx
+            .prepare("SELECT id FROM tasks ORDER BY id ASC")
+            .map_err(to_error)?;
+        let rows = stmt
+            .query_map([], |row| row.get::<_, String>(0))
+            .map_err(to_error)?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(to_error)?;
+        rows
+    };
+
+    let mut cached = 0;
+    for task_id in &task_ids {
+        if refresh_task_baseline(&tx, task_id)? {
+            cached += 1;
+        }
+    }
+    tx.commit().map_err(to_error)?;
+    Ok(cached)
+}
+
+/// Stores the all-time inclusive and exclusive totals of every live task with tracked
+/// time under one `taken_at`, so growth can be charted without replaying old events.
+pub fn snapshot_overview(conn: &mut Connection) -> AppResult<OverviewSnapshotSummary> {
+    let taken_at = now_ts();
+    let records = load_task_records(conn, None, taken_at, TimeBasis::Active)?
+        .into_iter()
+        .filter(|record| record.inclusive_seconds > 0)
+        .collect::<Vec<_>>();
+
+    let tx = begin_transaction(conn)?;
+    for record in &records {
+        tx.execute(
+            "INSERT INTO overview_snapshots
+                 (task_id, taken_at, inclusive_seconds, exclusive_seconds)
+             VALUES (?1, ?2, ?3, ?4)",
+            params![
+                record.id,
+                taken_at,
+                record.inclusive_seconds,
+                record.exclusive_seconds
+            ],
+        )
+        .map_err(to_error)?;
+    }
+    tx.commit().map_err(to_error)?;
+
+    Ok(OverviewSnapshotSummary {
+        taken_at,
+        task_count: records.len(),
+    })
+}
+
+/// Returns the stored snapshots of `task_id`, oldest first.
+pub fn get_snapshots(conn: &Connection, task_id: String) -> AppResult<Vec<OverviewSnapshot>> {
+    ensure_task_exists(conn, &task_id)?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT taken_at, inclusive_seconds, exclusive_seconds
+             FROM overview_snapshots
+             WHERE task_id = ?1
+             ORDER BY taken_at ASC, id ASC",
+        )
+        .map_err(to_error)?;
+    let rows = stmt
+        .query_map(params![task_id], |row| {
+            Ok(OverviewSnapshot {
+                taken_at: row.get(0)?,
+                inclusive_seconds: row.get(1)?,
+                exclusive_seconds: row.get(2)?,
+            })
+        })
+        .map_err(to_error)?;
+
+    rows.collect::<Result<Vec<_>, _>>().map_err(to_error)
+}
+
+pub fn coalesce_task_sessions(
+    conn: &mut Connection,
+    task_id: String,
+    max_gap_seconds: i64,
+) -> AppResult<usize> {
+    ensure_task_exists(conn, &task_id)?;
+    if max_gap_seconds <= 0 {
+        return Err(validation_error("max_gap_seconds must be positive"));
+    }
+
+    let tx = begin_transaction(conn)?;
+    let events = {
+        let mut stmt = tx
+            .prepare(
+                "SELECT id, event_type, ts, payload
+                 FROM time_events
+                 WHERE task_id = ?1
+                   AND event_type IN ('start', 'resume', 'pause', 'stop')
+                 ORDER BY ts ASC, id ASC",
+            )
+            .map_err(to_error)?;
+        let rows = stmt
+            .query_map(params![task_id], |row| {
+                Ok((
+                    row.get::<_, i64>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, i64>(2)?,
+                    row.get::<_, Option<String>>(3)?,
+                ))
+            })
+            .map_err(to_error)?;
+        rows.collect::<Result<Vec<_>, _>>().map_err(to_error)?
+    };
+
+    let mut merged = 0usize;
+    for pair in events.windows(2) {
+        let (pause_id, pause_type, pause_ts, pause_payload) = &pair[0];
+        let (resume_id, resume_type, resume_ts, _) = &pair[1];
+        // Pauses with a payload carry structural meaning (e.g. subtask insertion) and are kept.
+        if pause_type != EVENT_PAUSE || resume_type != EVENT_RESUME || pause_payload.is_some() {
+            continue;
+        }
+
+        let gap_seconds = resume_ts - pause_ts;
+        if gap_seconds >= max_gap_seconds {
+            continue;
+        }
+        if other_task_focused_between(&tx, &task_id, *pause_ts, *resume_ts)? {
+            continue;
+        }
+
+        tx.execute(
+            "DELETE FROM time_events WHERE id IN (?1, ?2)",
+            params![pause_id, resume_id],
+        )
+        .map_err(to_error)?;
+        // The joined session now spans the gap, so offset it to keep tracked totals unchanged.
+        if gap_seconds > 0 {
+            append_event(
+                &tx,
+                &task_id,
+                EVENT_ADJUST,
+                *pause_ts,
+                Some(json!({
+                    "delta_seconds": -gap_seconds,
+                    "reason": "coalesce"
+                })),
+            )?;
+        }
+        merged += 1;
+    }
+
+    refresh_task_baseline(&tx, &task_id)?;
+    validate_task_timeline(&tx, &task_id)?;
+    tx.commit().map_err(to_error)?;
+    Ok(merged)
+}
+
+pub fn get_overview(
+    conn: &Connection,
+    range: Option<String>,
+    options: OverviewOptions,
+) -> AppResult<OverviewResponse> {
+    let now = now_ts();
+    let (window_start, resolved_range) = resolve_window(range, now)?;
+    let window_end = match options.as_of {
+        Some(as_of) if as_of > now => {
+            return Err(validation_error("as_of cannot be in the future"));
+        }
+        Some(as_of) if window_start.is_some_and(|start| as_of < start) => {
+            return Err(validation_error("as_of cannot be before the window start"));
+        }
+        Some(as_of) => as_of,
+        None => now,
+    };
+    let sort = options.sort.as_deref().unwrap_or("created_asc");
+    if !OVERVIEW_SORT_KEYS.contains(&sort) {
+        return Err(validation_error(format!(
+            "unsupported sort '{sort}', expected one of: {}",
+            OVERVIEW_SORT_KEYS.join(", ")
+        )));
+    }
+    let time_basis = TimeBasis::parse(options.time_basis.as_deref())?;
+    let precision = Precision::parse(options.precision.as_deref())?;
+    let tagged_task_ids = match options.tag.as_deref() {
+        Some(raw_tag) => Some(load_task_ids_with_tag(conn, &sanitize_tag(raw_tag)?)?),
+        None => None,
+    };
+
+    let active_task_id = find_running_task(conn)?;
+    let last_used_task_id = latest_used_task(conn)?;
+    let rest_suggestion = load_latest_pending_rest_suggestion(conn)?;
+    let notifications = load_pending_notifications(conn)?;
+
+    let mut all_records = load_task_records(conn, window_start, window_end, time_basis)?;
+    if precision == Precision::Millis {
+        let window_end_millis = if options.as_of.is_some() {
+            window_end * 1000
+        } else {
+            now_ts_millis()
+        };
+        let exclusive_millis = replay_totals(
+            conn,
+            window_start.map(|start| start * 1000),
+            window_end_millis,
+            time_basis,
+            precision,
+        )?;
+        let inclusive_millis = derive_inclusive_seconds(&load_tasks(conn)?, &exclusive_millis);
+        for record in &mut all_records {
+            record.exclusive_millis = Some(*exclusive_millis.get(&record.id).unwrap_or(&0));
+            record.inclusive_millis = Some(*inclusive_millis.get(&record.id).unwrap_or(&0));
+        }
+    }
+    let parent_by_task = all_records
+        .iter()
+        .map(|record| (record.id.clone(), record.parent_id.clone()))
+        .collect::<HashMap<_, _>>();
+    let collapsed_ids = if options.collapse_completed {
+        collect_collapsible_completed(&all_records)
+    } else {
+        HashSet::new()
+    };
+    let mut records = all_records
+        .into_iter()
+        .filter(|record| {
+            tagged_task_ids
+                .as_ref()
+                .is_none_or(|task_ids| task_ids.contains(&record.id))
+        })
+        .filter(|record| !(options.exclude_completed && record.completed_at.is_some()))
+        .collect::<Vec<_>>();
+
+    let mut context_task_ids = Vec::new();
+    if options.only_active_in_window {
+        let active_ids = records
+            .iter()
+            .filter(|record| record.exclusive_seconds != 0)
+            .map(|record| record.id.clone())
+            .collect::<HashSet<_>>();
+        let mut keep_ids = active_ids.clone();
+        for task_id in &active_ids {
+            let mut current = parent_by_task.get(task_id).cloned().flatten();
+            while let Some(parent_id) = current {
+                if !keep_ids.insert(parent_id.clone()) {
+                    break;
+                }
+                current = parent_by_task.get(&parent_id).cloned().flatten();
+            }
+        }
+        records.retain(|record| keep_ids.contains(&record.id));
+        context_task_ids = records
+            .iter()
+            .filter(|record| !active_ids.contains(&record.id))
+            .map(|record| record.id.clone())
+            .collect();
+    }
+    // Collapsing runs last so a collapsed subtask still keeps its ancestors under
+    // `only_active_in_window`; their inclusive totals already carry its time.
+    if !collapsed_ids.is_empty() {
+        records.retain(|record| !collapsed_ids.contains(&record.id));
+        context_task_ids.retain(|task_id| !collapsed_ids.contains(task_id));
+    }
+    let records = sort_task_records(records, sort);
+
+    Ok(OverviewResponse {
+        schema_version: TASK_RECORD_SCHEMA_VERSION,
+        range: resolved_range,
+        time_basis: time_basis.as_str().to_string(),
+        precision: precision.as_str().to_string(),
+        generated_at: now,
+        active_task_id,
+        last_used_task_id,
+        rest_suggestion,
+        notifications,
+        tasks: records,
+        context_task_ids,
+    })
+}
+
+/// Same records and windowing as `get_overview`, nested under their parents.
+pub fn get_task_tree_nested(
+    conn: &Connection,
+    range: Option<String>,
+) -> AppResult<TaskTreeResponse> {
+    let now = now_ts();
+    let (window_start, resolved_range) = resolve_window(range, now)?;
+    let records = load_task_records(conn, window_start, now, TimeBasis::Active)?;
+    let task_count = records.len();
+
+    let known_ids = records
+        .iter()
+        .map(|record| record.id.clone())
+        .collect::<HashSet<_>>();
+    let mut root_records = Vec::new();
+    let mut children_by_parent: HashMap<String, Vec<TaskRecord>> = HashMap::new();
+    for record in records {
+        match record.parent_id.clone() {
+            Some(parent_id) if known_ids.contains(&parent_id) => {
+                children_by_parent
+                    .entry(parent_id)
+                    .or_default()
+                    .push(record);
+            }
+            _ => root_records.push(record),
+        }
+    }
+
+    let mut visited = HashSet::new();
+    let roots = root_records
+        .into_iter()
+        .map(|record| build_tree_node(record, &mut children_by_parent, &mut visited))
+        .collect::<AppResult<Vec<_>>>()?;
+    if visited.len() != task_count {
+        return Err(conflict_error(
+            "detected cycle while building task tree; some tasks are unreachable from a root",
+        ));
+    }
+
+    Ok(TaskTreeResponse {
+        schema_version: TASK_RECORD_SCHEMA_VERSION,
+        range: resolved_range,
+        generated_at: now,
+        roots,
+    })
+}
+
+/// Builds the running task's record from its own events and its subtree only, avoiding
+/// the full replay behind `get_overview`.
+pub fn get_running_task(conn: &Connection) -> AppResult<Option<TaskRecord>> {
+    let Some(task_id) = find_running_task(conn)? else {
+        return Ok(None);
+    };
+
+    let now = now_ts();
+    let task = conn
+        .query_row(
+            "SELECT id, parent_id, title, status, created_at, completed_at, due_at, reference_url,
+                    sort_order
+             FROM tasks
+             WHERE id = ?1",
+            params![task_id],
+            |row| {
+                Ok(TaskRow {
+                    id: row.get(0)?,
+                    parent_id: row.get(1)?,
+                    title: row.get(2)?,
+                    status: row.get(3)?,
+                    created_at: row.get(4)?,
+                    completed_at: row.get(5)?,
+                    due_at: row.get(6)?,
+                    reference_url: row.get(7)?,
+                    sort_order: row.get(8)?,
+                })
+            },
+        )
+        .map_err(to_error)?;
+    let last_activated_at: Option<i64> = conn
+        .query_row(
+            "SELECT MAX(ts) FROM time_events WHERE task_id = ?1 AND event_type IN (?2, ?3)",
+            params![task_id, EVENT_START, EVENT_RESUME],
+            |row| row.get(0),
+        )
+        .map_err(to_error)?;
+
+    let exclusive_seconds = task_total_focus_seconds(conn, &task_id, now)?;
+    let mut inclusive_seconds = exclusive_seconds;
+    let mut has_tracked_descendants = false;
+    for subtree_id in collect_subtree_ids(conn, &task_id)? {
+        if subtree_id == task_id {
+            continue;
+        }
+        let seconds = task_total_focus_seconds(conn, &subtree_id, now)?;
+        inclusive_seconds += seconds;
+        has_tracked_descendants |= seconds != 0;
+    }
+
+    Ok(Some(TaskRecord {
+        tags: load_task_tag_refs(conn, &task_id)?,
+        id: task.id,
+        parent_id: task.parent_id,
+        title: task.title,
+        status: task.status,
+        created_at: task.created_at,
+        completed_at: task.completed_at,
+        due_at: task.due_at,
+        reference_url: task.reference_url,
+        sort_order: task.sort_order,
+        last_activated_at,
+        inclusive_seconds,
+        exclusive_seconds,
+        inclusive_millis: None,
+        exclusive_millis: None,
+        has_tracked_descendants,
+    }))
+}
+
+/// Counts non-archived tasks per status, with archived tasks counted separately.
+pub fn get_status_counts(conn: &Connection) -> AppResult<StatusCounts> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT CASE WHEN archived_at IS NULL THEN status ELSE 'archived' END AS bucket,
+                    COUNT(*)
+             FROM tasks
+             GROUP BY bucket",
+        )
+        .map_err(to_error)?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?))
+        })
+        .map_err(to_error)?;
+
+    let mut counts = StatusCounts::default();
+    for row in rows {
+        let (bucket, count) = row.map_err(to_error)?;
+        match bucket.as_str() {
+            STATUS_IDLE => counts.idle = count,
+            STATUS_RUNNING => counts.running = count,
+            STATUS_PAUSED => counts.paused = count,
+            STATUS_STOPPED => counts.stopped = count,
+            _ => counts.archived = count,
+        }
+    }
+
+    Ok(counts)
+}
+
+/// Top-level tasks with their subtree time in `range`, the union of tags across the
+/// subtree and their child counts. Projects without tracked time are dropped unless
+/// `include_zero` is set.
+pub fn get_projects(
+    conn: &Connection,
+    range: Option<String>,
+    include_zero: bool,
+) -> AppResult<ProjectsResponse> {
+    let now = now_ts();
+    let (window_start, resolved_range) = resolve_window(range, now)?;
+    let tasks = load_tasks(conn)?;
+    let tags_by_task = load_tags(conn)?;
+    let exclusive_seconds = replay_seconds(conn, window_start, now, TimeBasis::Active)?;
+    let inclusive_seconds = derive_inclusive_seconds(&tasks, &exclusive_seconds);
+
+    let mut children_by_parent: HashMap<&str, Vec<&str>> = HashMap::new();
+    for task in &tasks {
+        if let Some(parent_id) = &task.parent_id {
+            children_by_parent
+                .entry(parent_id.as_str())
+                .or_default()
+                .push(task.id.as_str());
+        }
+    }
+
+    let mut projects = Vec::new();
+    for task in tasks.iter().filter(|task| task.parent_id.is_none()) {
+        let seconds = *inclusive_seconds.get(&task.id).unwrap_or(&0);
+        if !include_zero && seconds <= 0 {
+            continue;
+        }
+
+        let mut tags: Vec<TagRef> = Vec::new();
+        let mut visited = HashSet::new();
+        let mut stack = vec![task.id.as_str()];
+        while let Some(id) = stack.pop() {
+            if !visited.insert(id) {
+                continue;
+            }
+            for tag in tags_by_task.get(id).into_iter().flatten() {
+                if !tags.iter().any(|existing| existing.name == tag.name) {
+                    tags.push(tag.clone());
+                }
+            }
+            stack.extend(children_by_parent.get(id).into_iter().flatten());
+        }
+        tags.sort_by(|left, right| left.name.cmp(&right.name));
+
+        projects.push(ProjectRecord {
+            id: task.id.clone(),
+            title: task.title.clone(),
+            status: task.status.clone(),
+            inclusive_seconds: seconds,
+            tags,
+            child_count: children_by_parent.get(task.id.as_str()).map_or(0, Vec::len),
+            descendant_count: visited.len() - 1,
+        });
+    }
+    projects.sort_by(|left, right| {
+        right
+            .inclusive_seconds
+            .cmp(&left.inclusive_seconds)
+            .then_with(|| left.title.cmp(&right.title))
+    });
+
+    Ok(ProjectsResponse {
+        range: resolved_range,
+        generated_at: now,
+        projects,
+    })
+}
+
+/// Tasks whose own time in `range` is below `ratio` (0..=1) of their subtree time, i.e.
+/// parents that mostly act as containers. Leaf tasks and parents whose children tracked
+/// nothing have inclusive == exclusive and are never returned. Largest subtrees first.
+pub fn get_container_tasks(
+    conn: &Connection,
+    range: Option<String>,
+    ratio: f64,
+) -> AppResult<Vec<TaskRecord>> {
+    if !(ratio > 0.0 && ratio <= 1.0) {
+        return Err(validation_error(
+            "ratio must be greater than 0 and at most 1",
+        ));
+    }
+
+    let now = now_ts();
+    let (window_start, _) = resolve_window(range, now)?;
+    let mut containers = load_task_records(conn, window_start, now, TimeBasis::Active)?
+        .into_iter()
+        .filter(|record| record.inclusive_seconds > record.exclusive_seconds)
+        .filter(|record| {
+            (record.exclusive_seconds as f64) < ratio * record.inclusive_seconds as f64
+        })
+        .collect::<Vec<_>>();
+    containers.sort_by(|left, right| {
+        right
+            .inclusive_seconds
+            .cmp(&left.inclusive_seconds)
+            .then_with(|| left.title.cmp(&right.title))
+    });
+    Ok(containers)
+}
+
+/// Sums exclusive seconds per exact tag set, so each task's time lands in one bucket only.
+/// Untagged tasks form the combination with no tags. Tasks without time are counted only
+/// when `include_zero` is set. Sorted by time, largest first.
+pub fn get_time_by_tag_set(
+    conn: &Connection,
+    range: Option<String>,
+    include_zero: bool,
+) -> AppResult<TagSetTotalsResponse> {
+    let now = now_ts();
+    let (window_start, resolved_range) = resolve_window(range, now)?;
+
+    let mut totals_by_set: HashMap<Vec<String>, (i64, i64)> = HashMap::new();
+    for record in load_task_records(conn, window_start, now, TimeBasis::Active)? {
+        if !include_zero && record.exclusive_seconds == 0 {
+            continue;
+        }
+        let mut tags = record
+            .tags
+            .into_iter()
+            .map(|tag| tag.name)
+            .collect::<Vec<_>>();
+        tags.sort();
+        let entry = totals_by_set.entry(tags).or_insert((0, 0));
+        entry.0 += record.exclusive_seconds;
+        entry.1 += 1;
+    }
+
+    let mut combinations = totals_by_set
+        .into_iter()
+        .map(|(tags, (seconds, task_count))| TagSetTotal {
+            tags,
+            seconds,
+            task_count,
+        })
+        .collect::<Vec<_>>();
+    combinations.sort_by(|left, right| {
+        right
+            .seconds
+            .cmp(&left.seconds)
+            .then_with(|| left.tags.cmp(&right.tags))
+    });
+
+    Ok(TagSetTotalsResponse {
+        range: resolved_range,
+        generated_at: now,
+        total_seconds: combinations.iter().map(|combo| combo.seconds).sum(),
+        combinations,
+    })
+}
+
+/// Splits tracked time in the window across top-level tasks, with subtasks rolled into
+/// their root. `untracked_seconds` is the rest of the window and is `None` for `all`.
+/// Roots without time are listed at 0% only when `include_zero` is set.
+pub fn get_time_breakdown(
+    conn: &Connection,
+    range: Option<String>,
+    include_zero: bool,
+) -> AppResult<TimeBreakdownResponse> {
+    let now = now_ts();
+    let (window_start, resolved_range) = resolve_window(range, now)?;
+    let records = load_task_records(conn, window_start, now, TimeBasis::Active)?;
+    let present = records
+        .iter()
+        .map(|record| record.id.clone())
+        .collect::<HashSet<_>>();
+
+    let mut entries = records
+        .into_iter()
+        .filter(|record| {
+            record
+                .parent_id
+                .as_ref()
+                .is_none_or(|parent_id| !present.contains(parent_id))
+        })
+        .filter(|record| include_zero || record.inclusive_seconds > 0)
+        .map(|record| TimeBreakdownEntry {
+            task_id: record.id,
+            title: record.title,
+            inclusive_seconds: record.inclusive_seconds,
+            percentage: 0.0,
+        })
+        .collect::<Vec<_>>();
+    let total_seconds = entries
+        .iter()
+        .map(|entry| entry.inclusive_seconds)
+        .sum::<i64>();
+    if total_seconds > 0 {
+        for entry in &mut entries {
+            entry.percentage = entry.inclusive_seconds as f64 * 100.0 / total_seconds as f64;
+        }
+    }
+    entries.sort_by(|left, right| {
+        right
+            .inclusive_seconds
+            .cmp(&left.inclusive_seconds)
+            .then_with(|| left.title.cmp(&right.title))
+    });
+
+    Ok(TimeBreakdownResponse {
+        range: resolved_range,
+        generated_at: now,
+        total_seconds,
+        untracked_seconds: window_start.map(|start| (now - start - total_seconds).max(0)),
+        entries,
+    })
+}
+
+/// Flags tasks whose inclusive seconds exceed the wall-clock length of the window, which
+/// can only happen when sessions inside the subtree overlapped. For `all` the window runs
+/// from the earliest focus event.
+pub fn get_inclusive_overlap_warnings(
+    conn: &Connection,
+    range: Option<String>,
+) -> AppResult<Vec<InclusiveOverlapWarning>> {
+    let now = now_ts();
+    let (window_start, _) = resolve_window(range, now)?;
+    let span_start = match window_start {
+        Some(start) => start,
+        None => earliest_focus_event_ts(conn)?.unwrap_or(now),
+    };
+    let window_seconds = (now - span_start).max(0);
+
+    let mut warnings = load_task_records(conn, window_start, now, TimeBasis::Active)?
+        .into_iter()
+        .filter(|record| record.inclusive_seconds > window_seconds)
+        .map(|record| InclusiveOverlapWarning {
+            task_id: record.id,
+            title: record.title,
+            inclusive_seconds: record.inclusive_seconds,
+            window_seconds,
+            excess_seconds: record.inclusive_seconds - window_seconds,
+        })
+        .collect::<Vec<_>>();
+    warnings.sort_by_key(|warning| std::cmp::Reverse(warning.excess_seconds));
+
+    Ok(warnings)
+}
+
+/// Lists focus sessions overlapping the window whose start and end fall on different
+/// local days, i.e. the ones daily reports split at midnight. Oldest first.
+pub fn get_midnight_crossing_sessions(
+    conn: &Connection,
+    range: Option<String>,
+) -> AppResult<Vec<MidnightCrossingSession>> {
+    let now = now_ts();
+    let (window_start, _) = resolve_window(range, now)?;
+    let title_by_task = load_tasks_for_reporting(conn)?
+        .into_iter()
+        .map(|task| (task.id, task.title))
+        .collect::<HashMap<_, _>>();
+
+    let mut sessions = collect_focus_intervals(conn, window_start, now)?
+        .into_iter()
+        .filter_map(|interval| {
+            let start_date = local_date_key(interval.unclipped_start_ts);
+            let end_date = local_date_key(interval.unclipped_end_ts);
+            if start_date == end_date {
+                return None;
+            }
+            Some(MidnightCrossingSession {
+                title: title_by_task
+                    .get(&interval.task_id)
+                    .cloned()
+                    .unwrap_or_default(),
+                task_id: interval.task_id,
+                start_ts: interval.unclipped_start_ts,
+                end_ts: interval.unclipped_end_ts,
+                start_date,
+                end_date,
+            })
+        })
+        .collect::<Vec<_>>();
+    sessions.sort_by_key(|session| (session.start_ts, session.end_ts));
+
+    Ok(sessions)
+}
+
+pub fn get_latest_event_id(conn: &Connection) -> AppResult<Option<i64>> {
+    conn.query_row("SELECT MAX(id) FROM time_events", [], |row| row.get(0))
+        .map_err(to_error)
+}
+
+/// Returns the all-time records of tasks touched by events after `last_event_id`, plus
+/// their ancestors since inclusive time rolls up. Tasks that were archived or deleted in
+/// the meantime are listed in `removed_task_ids`; hard deletes come from their tombstones
+/// and may be listed again if they raced the caller's last read. Changes that write no
+/// event (renames, due dates) are not picked up. Falls back to `full_overview` when
+/// `last_event_id` is 0, ahead of the log, or older than the earliest surviving event.
+pub fn get_overview_since(conn: &Connection, last_event_id: i64) -> AppResult<OverviewDelta> {
+    let (earliest_event_id, latest_event_id): (Option<i64>, Option<i64>) = conn
+        .query_row("SELECT MIN(id), MAX(id) FROM time_events", [], |row| {
+            Ok((row.get(0)?, row.get(1)?))
+        })
+        .map_err(to_error)?;
+    let stale = last_event_id <= 0
+        || latest_event_id.is_none_or(|latest| last_event_id > latest)
+        || earliest_event_id.is_some_and(|earliest| last_event_id < earliest - 1);
+    if stale {
+        return Ok(OverviewDelta {
+            latest_event_id,
+            full_overview: Some(get_overview(conn, None, OverviewOptions::default())?),
+            changed_tasks: Vec::new(),
+            removed_task_ids: Vec::new(),
+        });
+    }
+
+    let mut stmt = conn
+        .prepare("SELECT DISTINCT task_id FROM time_events WHERE id > ?1")
+        .map_err(to_error)?;
+    let touched_ids = stmt
+        .query_map(params![last_event_id], |row| row.get::<_, String>(0))
+        .map_err(to_error)?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(to_error)?;
+
+    let parent_by_task = load_tasks(conn)?
+        .into_iter()
+        .map(|task| (task.id, task.parent_id))
+        .collect::<HashMap<_, _>>();
+    let mut affected_ids = HashSet::new();
+    let mut removed_task_ids = Vec::new();
+    for task_id in touched_ids {
+        if !parent_by_task.contains_key(&task_id) {
+            removed_task_ids.push(task_id);
+            continue;
+        }
+        let mut current = Some(task_id);
+        while let Some(id) = current {
+            if !affected_ids.insert(id.clone()) {
+                break;
+            }
+            current = parent_by_task.get(&id).cloned().flatten();
+        }
+    }
+    let mut stmt = conn
+        .prepare("SELECT task_id FROM deleted_tasks WHERE last_event_id >= ?1")
+        .map_err(to_error)?;
+    let deleted_ids = stmt
+        .query_map(params![last_event_id], |row| row.get::<_, String>(0))
+        .map_err(to_error)?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(to_error)?;
+    removed_task_ids.extend(
+        deleted_ids
+            .into_iter()
+            .filter(|task_id| !parent_by_task.contains_key(task_id)),
+    );
+    removed_task_ids.sort();
+    removed_task_ids.dedup();
+
+    let changed_tasks = load_task_records(conn, None, now_ts(), TimeBasis::Active)?
+        .into_iter()
+        .filter(|record| affected_ids.contains(&record.id))
+        .collect();
+
+    Ok(OverviewDelta {
+        latest_event_id,
+        full_overview: None,
+        changed_tasks,
+        removed_task_ids,
+    })
+}
+
+/// Returns one page of a task's events, newest page first: the `limit` events (default
+/// 100, capped at 1000) before the `(before_ts, before_id)` cursor, in timeline order.
+/// Pass the page's `next_before_ts`/`next_before_id` back to fetch the previous page.
+pub fn get_task_events(
+    conn: &Connection,
+    task_id: String,
+    limit: Option<i64>,
+    before_ts: Option<i64>,
+    before_id: Option<i64>,
+) -> AppResult<TaskEventsPage> {
+    load_archived_at(conn, &task_id)?;
+    let limit = limit.unwrap_or(TASK_EVENTS_DEFAULT_LIMIT);
+    if limit <= 0 {
+        return Err(validation_error("limit must be positive"));
+    }
+    let limit = limit.min(TASK_EVENTS_MAX_LIMIT);
+    if before_id.is_some_and(|id| id <= 0) {
+        return Err(validation_error("before_id must be positive"));
+    }
+    let cursor = match (before_ts, before_id) {
+        (Some(ts), Some(id)) => Some((ts, id)),
+        (None, None) => None,
+        _ => {
+            return Err(validation_error(
+                "before_ts and before_id must be provided together",
+            ))
+        }
+    };
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, task_id, event_type, ts, payload
+             FROM time_events
+             WHERE task_id = ?1 AND (?2 IS NULL OR (ts, id) < (?2, ?3))
+             ORDER BY ts DESC, id DESC
+             LIMIT ?4",
+        )
+        .map_err(to_error)?;
+
+    let rows = stmt
+        .query_map(
+            params![
+                task_id,
+                cursor.map(|(ts, _)| ts),
+                cursor.map(|(_, id)| id),
+                limit + 1
+            ],
+            |row| {
+                Ok((
+                    row.get::<_, i64>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, String>(2)?,
+                    row.get::<_, i64>(3)?,
+                    row.get::<_, Option<String>>(4)?,
+                ))
+            },
+        )
+        .map_err(to_error)?;
+
+    let mut events = Vec::new();
+    for row in rows {
+        let (id, task_id, event_type, ts, raw_payload) = row.map_err(to_error)?;
+        let (payload, reason) = parse_event_payload(raw_payload.as_deref());
+        events.push(TaskEventRecord {
+            id,
+            task_id,
+            event_type,
+            ts,
+            reason,
+            payload,
+        });
+    }
+
+    let has_more = events.len() as i64 > limit;
+    events.truncate(limit as usize);
+    events.reverse();
+    let first = events.first().filter(|_| has_more);
+    Ok(TaskEventsPage {
+        next_before_ts: first.map(|event| event.ts),
+        next_before_id: first.map(|event| event.id),
+        has_more,
+        events,
+    })
+}
+
+/// Returns one page of events across all tasks with `from_ts <= ts < to_ts`, oldest first.
+/// Pages are keyed on `(ts, id)`: pass the previous page's `next_after_ts`/`next_after_id`
+/// to continue.
+pub fn get_events_in_window(
+    conn: &Connection,
+    from_ts: i64,
+    to_ts: i64,
+    limit: Option<i64>,
+    after_ts: Option<i64>,
+    after_id: Option<i64>,
+) -> AppResult<TimeEventsPage> {
+    if from_ts >= to_ts {
+        return Err(validation_error("from_ts must be earlier than to_ts"));
+    }
+    let limit = limit.unwrap_or(TASK_EVENTS_DEFAULT_LIMIT);
+    if limit <= 0 {
+        return Err(validation_error("limit must be positive"));
+    }
+    let limit = limit.min(TASK_EVENTS_MAX_LIMIT);
+    let cursor = match (after_ts, after_id) {
+        (Some(ts), Some(id)) => Some((ts, id)),
+        (None, None) => None,
+        _ => {
+            return Err(validation_error(
+                "after_ts and after_id must be provided together",
+            ))
+        }
+    };
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT e.id, e.task_id, t.title, e.event_type, e.ts, e.payload
+             FROM time_events e
+             JOIN tasks t ON t.id = e.task_id
+             WHERE e.ts >= ?1 AND e.ts < ?2
+               AND (?3 IS NULL OR (e.ts, e.id) > (?3, ?4))
+             ORDER BY e.ts ASC, e.id ASC
+             LIMIT ?5",
+        )
+        .map_err(to_error)?;
+
+    let rows = stmt
+        .query_map(
+            params![
+                from_ts,
+                to_ts,
+                cursor.map(|(ts, _)| ts),
+                cursor.map(|(_, id)| id),
+                limit + 1
+            ],
+            |row| {
+                Ok((
+                    row.get::<_, i64>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, String>(2)?,
+                    row.get::<_, String>(3)?,
+                    row.get::<_, i64>(4)?,
+                    row.get::<_, Option<String>>(5)?,
+                ))
+            },
+        )
+        .map_err(to_error)?;
+
+    let mut events = Vec::new();
+    for row in rows {
+        let (id, task_id, task_title, event_type, ts, raw_payload) = row.map_err(to_error)?;
+        let (payload, reason) = parse_event_payload(raw_payload.as_deref());
+        events.push(TimeEventRecord {
+            id,
+            task_id,
+            task_title,
+            event_type,
+            ts,
+            reason,
+            payload,
+        });
+    }
+
+    let has_more = events.len() as i64 > limit;
+    events.truncate(limit as usize);
+    let last = events.last().filter(|_| has_more);
+    Ok(TimeEventsPage {
+        next_after_ts: last.map(|event| event.ts),
+        next_after_id: last.map(|event| event.id),
+        has_more,
+        events,
+    })
+}
+
+fn parse_event_payload(raw: Option<&str>) -> (Option<serde_json::Value>, Option<String>) {
+    let payload = raw.and_then(|raw| serde_json::from_str::<serde_json::Value>(raw).ok());
+    let reason = payload
+        .as_ref()
+        .and_then(|value| value.get("reason"))
+        .and_then(|value| value.as_str())
+        .map(str::to_string);
+    (payload, reason)
+}
+
+pub fn get_pending_rest_suggestion(conn: &Connection) -> AppResult<Option<RestSuggestionRecord>> {
+    load_latest_pending_rest_suggestion(conn)
+}
+
+pub fn get_focus_summary(
+    conn: &Connection,
+    range: Option<String>,
+) -> AppResult<FocusSummaryResponse> {
+    let now = now_ts();
+    let window = resolve_summary_window(conn, range, now)?;
+    let tasks = load_tasks_for_reporting(conn)?;
+    let task_lookup = tasks
+        .into_iter()
+        .map(|task| (task.id.clone(), task))
+        .collect::<HashMap<_, _>>();
+    let intervals = collect_focus_intervals(conn, Some(window.range_start), window.range_end)?;
+
+    let mut seconds_by_day: HashMap<i64, HashMap<String, i64>> = HashMap::new();
+    let mut segments_by_day: HashMap<i64, Vec<FocusTimelineSegment>> = HashMap::new();
+    for interval in intervals {
+        let mut cursor = interval.start_ts;
+        while cursor < interval.end_ts {
+            let day_start = local_day_start_ts(cursor);
+            let next_day_start = shift_local_day_start(day_start, 1);
+            let segment_end = interval.end_ts.min(next_day_start);
+            let duration_seconds = segment_end - cursor;
+            let day_bucket = seconds_by_day.entry(day_start).or_default();
+            *day_bucket.entry(interval.task_id.clone()).or_insert(0) += duration_seconds;
+            let task = task_lookup.get(&interval.task_id);
+            segments_by_day
+                .entry(day_start)
+                .or_default()
+                .push(FocusTimelineSegment {
+                    task_id: interval.task_id.clone(),
+                    parent_id: task.and_then(|item| item.parent_id.clone()),
+                    title: task
+                        .map(|item| item.title.clone())
+                        .unwrap_or_else(|| format!("Task {}", interval.task_id)),
+                    start_ts: cursor,
+                    end_ts: segment_end,
+                    start_offset_seconds: cursor - day_start,
+                    end_offset_seconds: segment_end - day_start,
+                    duration_seconds,
+                });
+            cursor = segment_end;
+        }
+    }
+
+    for adjustment in collect_focus_adjustments(conn, Some(window.range_start), window.range_end)? {
+        let day_start = local_day_start_ts(adjustment.ts);
+        let day_bucket = seconds_by_day.entry(day_start).or_default();
+        *day_bucket.entry(adjustment.task_id).or_insert(0) += adjustment.delta_seconds;
+    }
+
+    let days = window
+        .day_starts
+        .into_iter()
+        .rev()
+        .map(|day_start| {
+            let day_end = shift_local_day_start(day_start, 1).min(window.range_end);
+            let mut task_rows = seconds_by_day.remove(&day_start).unwrap_or_default();
+            task_rows.retain(|_, exclusive_seconds| *exclusive_seconds > 0);
+            let total_focus_seconds = task_rows.values().copied().sum::<i64>();
+            let mut tasks = task_rows
+                .drain()
+                .map(|(task_id, exclusive_seconds)| {
+                    let task = task_lookup.get(&task_id);
+                    let share_ratio = if total_focus_seconds > 0 {
+                        exclusive_seconds as f64 / total_focus_seconds as f64
+                    } else {
+                        0.0
+                    };
+                    DayTaskBreakdown {
+                        task_id: task_id.clone(),
+                        parent_id: task.and_then(|item| item.parent_id.clone()),
+                        title: task
+                            .map(|item| item.title.clone())
+                            .unwrap_or_else(|| format!("Task {task_id}")),
+                        exclusive_seconds,
+                        share_ratio,
+                    }
+                })
+                .collect::<Vec<_>>();
+            tasks.sort_by(|left, right| {
+                right
+                    .exclusive_seconds
+                    .cmp(&left.exclusive_seconds)
+                    .then_with(|| left.title.cmp(&right.title))
+            });
+            let mut timeline_segments = segments_by_day.remove(&day_start).unwrap_or_default();
+            timeline_segments.sort_by(|left, right| {
+                left.start_ts
+                    .cmp(&right.start_ts)
+                    .then_with(|| left.end_ts.cmp(&right.end_ts))
+                    .then_with(|| left.title.cmp(&right.title))
+            });
+
+            FocusSummaryDay {
+                date_key: local_date_key(day_start),
+                day_start_ts: day_start,
+                day_end_ts: day_end,
+                total_focus_seconds,
+                tasks,
+                timeline_segments,
+            }
+        })
+        .collect::<Vec<_>>();
+
+    Ok(FocusSummaryResponse {
+        range: window.range,
+        generated_at: now,
+        days,
+    })
+}
+
+/// Seconds per local day and tag, with sessions split at midnight. A task's time counts in
+/// full towards each of its tags; untagged tasks are left out. Only day/tag pairs with time
+/// are returned (no zero-fill), ordered by day then tag name.
+pub fn get_tag_daily_totals(
+    conn: &Connection,
+    range: Option<String>,
+) -> AppResult<TagDailyTotalsResponse> {
+    let now = now_ts();
+    let window = resolve_summary_window(conn, range, now)?;
+    let tags_by_task = load_tags(conn)?;
+
+    let mut seconds_by_day_task: HashMap<(i64, String), i64> = HashMap::new();
+    for interval in collect_focus_intervals(conn, Some(window.range_start), window.range_end)? {
+        let mut cursor = interval.start_ts;
+        while cursor < interval.end_ts {
+            let day_start = local_day_start_ts(cursor);
+            let segment_end = interval.end_ts.min(shift_local_day_start(day_start, 1));
+            *seconds_by_day_task
+                .entry((day_start, interval.task_id.clone()))
+                .or_insert(0) += segment_end - cursor;
+            cursor = segment_end;
+        }
+    }
+    for adjustment in collect_focus_adjustments(conn, Some(window.range_start), window.range_end)? {
+        let day_start = local_day_start_ts(adjustment.ts);
+        *seconds_by_day_task
+            .entry((day_start, adjustment.task_id))
+            .or_insert(0) += adjustment.delta_seconds;
+    }
+
+    let mut seconds_by_day_tag: HashMap<(i64, String), i64> = HashMap::new();
+    for ((day_start, task_id), seconds) in seconds_by_day_task {
+        for tag in tags_by_task.get(&task_id).into_iter().flatten() {
+            *seconds_by_day_tag
+                .entry((day_start, tag.name.clone()))
+                .or_insert(0) += seconds;
+        }
+    }
+
+    let mut rows = seconds_by_day_tag
+        .into_iter()
+        .filter(|(_, seconds)| *seconds > 0)
+        .map(|((day_start, tag_name), seconds)| TagDailyTotal {
+            date_key: local_date_key(day_start),
+            day_start_ts: day_start,
+            tag_name,
+            seconds,
+        })
+        .collect::<Vec<_>>();
+    rows.sort_by(|left, right| {
+        left.day_start_ts
+            .cmp(&right.day_start_ts)
+            .then_with(|| left.tag_name.cmp(&right.tag_name))
+    });
+
+    Ok(TagDailyTotalsResponse {
+        range: window.range,
+        generated_at: now,
+        rows,
+    })
+}
+
+/// Daily totals for `range`, oldest first, each with the mean of the trailing
+/// `window_days` days including itself. Near the start of the range the mean covers only
+/// the days available, as reported by `sample_days`.
+pub fn get_rolling_average(
+    conn: &Connection,
+    range: Option<String>,
+    window_days: i64,
+) -> AppResult<RollingAverageResponse> {
+    if !(1..=MAX_ROLLING_WINDOW_DAYS).contains(&window_days) {
+        return Err(validation_error(format!(
+            "window_days must be between 1 and {MAX_ROLLING_WINDOW_DAYS}"
+        )));
+    }
+
+    let focus = get_focus_summary(conn, range)?;
+    let totals = focus
+        .days
+        .iter()
+        .rev()
+        .map(|day| {
+            (
+                day.date_key.clone(),
+                day.day_start_ts,
+                day.total_focus_seconds,
+            )
+        })
+        .collect::<Vec<_>>();
+
+    let mut days = Vec::with_capacity(totals.len());
+    let mut running_sum = 0;
+    for (index, (date_key, day_start_ts, total_seconds)) in totals.iter().enumerate() {
+        running_sum += total_seconds;
+        if index as i64 >= window_days {
+            running_sum -= totals[index - window_days as usize].2;
+        }
+        let sample_days = (index as i64 + 1).min(window_days);
+        days.push(RollingAverageDay {
+            date_key: date_key.clone(),
+            day_start_ts: *day_start_ts,
+            total_seconds: *total_seconds,
+            average_seconds: running_sum / sample_days,
+            sample_days,
+        });
+    }
+
+    Ok(RollingAverageResponse {
+        range: focus.range,
+        generated_at: focus.generated_at,
+        window_days,
+        days,
+    })
+}
+
+pub fn get_fragmentation(
+    conn: &Connection,
+    range: Option<String>,
+) -> AppResult<FragmentationResponse> {
+    let now = now_ts();
+    let window = resolve_summary_window(conn, range, now)?;
+
+    let mut blocks_by_day: HashMap<i64, Vec<i64>> = HashMap::new();
+    for session in collect_closed_sessions(conn, window.range_start, window.range_end)? {
+        blocks_by_day
+            .entry(local_day_start_ts(session.start_ts))
+            .or_default()
+            .push(session.end_ts - session.start_ts);
+    }
+
+    let mut days = Vec::with_capacity(window.day_starts.len());
+    for day_start in window.day_starts.into_iter().rev() {
+        let day_end = shift_local_day_start(day_start, 1).min(window.range_end);
+        let switch_count = count_task_switches(conn, day_start, day_end)?;
+        let interruption_count = count_interruptions(conn, day_start, day_end)?;
+        let blocks = blocks_by_day.remove(&day_start).unwrap_or_default();
+        let focus_block_count = blocks.len() as i64;
+        let average_block_seconds = if focus_block_count > 0 {
+            blocks.iter().sum::<i64>() / focus_block_count
+        } else {
+            0
+        };
+
+        days.push(FragmentationDay {
+            date_key: local_date_key(day_start),
+            day_start_ts: day_start,
+            day_end_ts: day_end,
+            switch_count,
+            interruption_count,
+            focus_block_count,
+            average_block_seconds,
+        });
+    }
+
+    Ok(FragmentationResponse {
+        range: window.range,
+        generated_at: now,
+        days,
+    })
+}
+
+/// Summarises the last seven local days for journaling: total focus, the top three tasks,
+/// rest suggestion responses and the day with the most task switches. `text` is the same
+/// data as a short paragraph.
+pub fn get_weekly_summary(conn: &Connection) -> AppResult<WeeklySummary> {
+    let range = Some("7d".to_string());
+    let focus = get_focus_summary(conn, range.clone())?;
+    let fragmentation = get_fragmentation(conn, range)?;
+    let range_start = focus
+        .days
+        .iter()
+        .map(|day| day.day_start_ts)
+        .min()
+        .unwrap_or(focus.generated_at);
+
+    let mut top_tasks = rank_focus_tasks(&focus);
+    let total_focus_seconds = top_tasks
+        .iter()
+        .map(|task| task.exclusive_seconds)
+        .sum::<i64>();
+    top_tasks.truncate(3);
+
+    let (breaks_accepted, breaks_ignored) =
+        count_rest_outcomes(conn, range_start, focus.generated_at)?;
+
+    let most_fragmented_day = fragmentation
+        .days
+        .into_iter()
+        .filter(|day| day.switch_count > 0)
+        .max_by(|left, right| {
+            left.switch_count
+                .cmp(&right.switch_count)
+                .then_with(|| left.focus_block_count.cmp(&right.focus_block_count))
+                .then_with(|| right.day_start_ts.cmp(&left.day_start_ts))
+        });
+
+    let mut text = format!("This week: {} focused.", format_hours(total_focus_seconds));
+    if !top_tasks.is_empty() {
+        let listed = top_tasks
+            .iter()
+            .map(|task| format!("{} ({})", task.title, format_hours(task.exclusive_seconds)))
+            .collect::<Vec<_>>()
+            .join(", ");
+        text.push_str(&format!(" Top tasks: {listed}."));
+    }
+    text.push_str(&format!(
+        " Breaks: {breaks_accepted} accepted, {breaks_ignored} ignored."
+    ));
+    if let Some(day) = &most_fragmented_day {
+        text.push_str(&format!(
+            " Most fragmented day: {} ({} switches).",
+            day.date_key, day.switch_count
+        ));
+    }
+
+    Ok(WeeklySummary {
+        range_start,
+        range_end: focus.generated_at,
+        total_focus_seconds,
+        top_tasks,
+        breaks_accepted,
+        breaks_ignored,
+        most_fragmented_day,
+        text,
+    })
+}
+
+/// Returns the handful of numbers shown on the shareable stats card: focus total, the
+/// single top task, the current streak and how many rest suggestions were accepted.
+/// The streak counts consecutive local days with a start or resume, back from today (or
+/// from yesterday when nothing has been started yet today), regardless of `range`.
+pub fn get_summary_card_data(
+    conn: &Connection,
+    range: Option<String>,
+) -> AppResult<SummaryCardData> {
+    let focus = get_focus_summary(conn, range)?;
+    let range_start = focus
+        .days
+        .iter()
+        .map(|day| day.day_start_ts)
+        .min()
+        .unwrap_or(focus.generated_at);
+
+    let ranked = rank_focus_tasks(&focus);
+    let total_focus_seconds = ranked
+        .iter()
+        .map(|task| task.exclusive_seconds)
+        .sum::<i64>();
+    let top_task = ranked.into_iter().next();
+    let (breaks_taken, _) = count_rest_outcomes(conn, range_start, focus.generated_at)?;
+
+    Ok(SummaryCardData {
+        range: focus.range,
+        range_start,
+        range_end: focus.generated_at,
+        total_focus_seconds,
+        total_hours: (total_focus_seconds as f64 / 360.0).round() / 10.0,
+        top_task,
+        streak_days: count_activity_streak_days(conn, focus.generated_at)?,
+        breaks_taken,
+    })
+}
+
+/// Focus per task summed over every day of `focus`, most focused first and then by title.
+fn rank_focus_tasks(focus: &FocusSummaryResponse) -> Vec<WeeklyTopTask> {
+    let mut seconds_by_task: HashMap<String, WeeklyTopTask> = HashMap::new();
+    for task in focus.days.iter().flat_map(|day| day.tasks.iter()) {
+        seconds_by_task
+            .entry(task.task_id.clone())
+            .or_insert_with(|| WeeklyTopTask {
+                task_id: task.task_id.clone(),
+                title: task.title.clone(),
+                exclusive_seconds: 0,
+            })
+            .exclusive_seconds += task.exclusive_seconds;
+    }
+    let mut ranked = seconds_by_task.into_values().collect::<Vec<_>>();
+    ranked.sort_by(|left, right| {
+        right
+            .exclusive_seconds
+            .cmp(&left.exclusive_seconds)
+            .then_with(|| left.title.cmp(&right.title))
+    });
+    ranked
+}
+
+/// Compares the break minutes taken with the minutes suggested by the rest suggestions
+/// answered in the window. Accepted suggestions count the minutes chosen when accepting.
+pub fn get_break_adherence(conn: &Connection, range: Option<String>) -> AppResult<BreakAdherence> {
+    let now = now_ts();
+    let window = resolve_summary_window(conn, range, now)?;
+    let (suggestion_count, accepted_count, suggested_minutes, accepted_minutes) = conn
+        .query_row(
+            "SELECT
+                COUNT(*),
+                COALESCE(SUM(CASE WHEN status = ?1 THEN 1 ELSE 0 END), 0),
+                COALESCE(SUM(suggested_minutes), 0),
+                COALESCE(SUM(CASE WHEN status = ?1 THEN accepted_minutes ELSE 0 END), 0)
+             FROM rest_suggestions
+             WHERE status IN (?1, ?2)
+               AND suggested_minutes > 0
+               AND created_at >= ?3 AND created_at <= ?4",
+            params![
+                REST_STATUS_ACCEPTED,
+                REST_STATUS_IGNORED,
+                window.range_start,
+                window.range_end
+            ],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+        )
+        .map_err(to_error)?;
+
+    Ok(BreakAdherence {
+        range: window.range,
+        range_start: window.range_start,
+        range_end: window.range_end,
+        generated_at: now,
+        suggestion_count,
+        accepted_count,
+        suggested_minutes,
+        accepted_minutes,
+        adherence_ratio: (suggested_minutes > 0)
+            .then(|| accepted_minutes as f64 / suggested_minutes as f64),
+    })
+}
+
+fn count_rest_outcomes(
+    conn: &Connection,
+    range_start: i64,
+    range_end: i64,
+) -> AppResult<(i64, i64)> {
+    conn.query_row(
+        "SELECT
+            COALESCE(SUM(CASE WHEN status = ?1 THEN 1 ELSE 0 END), 0),
+            COALESCE(SUM(CASE WHEN status = ?2 THEN 1 ELSE 0 END), 0)
+         FROM rest_suggestions
+         WHERE created_at >= ?3 AND created_at <= ?4",
+        params![
+            REST_STATUS_ACCEPTED,
+            REST_STATUS_IGNORED,
+            range_start,
+            range_end
+        ],
+        |row| Ok((row.get::<_, i64>(0)?, row.get::<_, i64>(1)?)),
+    )
+    .map_err(to_error)
+}
+
+fn count_activity_streak_days(conn: &Connection, now: i64) -> AppResult<i64> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT ts
+             FROM time_events
+             WHERE event_type IN (?1, ?2) AND ts <= ?3",
+        )
+        .map_err(to_error)?;
+    let rows = stmt
+        .query_map(params![EVENT_START, EVENT_RESUME, now], |row| {
+            row.get::<_, i64>(0)
+        })
+        .map_err(to_error)?;
+
+    let mut active_days = HashSet::new();
+    for row in rows {
+        active_days.insert(local_day_start_ts(row.map_err(to_error)?));
+    }
+
+    let today_start = local_day_start_ts(now);
+    let mut day_start = if active_days.contains(&today_start) {
+        today_start
+    } else {
+        shift_local_day_start(today_start, -1)
+    };
+    let mut streak = 0;
+    while active_days.contains(&day_start) {
+        streak += 1;
+        day_start = shift_local_day_start(day_start, -1);
+    }
+    Ok(streak)
+}
+
+/// Reports, per local day with activity, the first start/resume and the last pause/stop.
+/// While a task is running, today is flagged `still_working` and is included even when
+/// the session began before midnight.
+pub fn get_work_bounds(conn: &Connection, range: Option<String>) -> AppResult<WorkBoundsResponse> {
+    let now = now_ts();
+    let window = resolve_summary_window(conn, range, now)?;
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT event_type, ts
+             FROM time_events
+             WHERE event_type IN ('start', 'resume', 'pause', 'stop')
+               AND ts >= ?1 AND ts <= ?2
+             ORDER BY ts ASC, id ASC",
+        )
+        .map_err(to_error)?;
+    let rows = stmt
+        .query_map(params![window.range_start, window.range_end], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?))
+        })
+        .map_err(to_error)?;
+
+    let mut first_start_by_day: HashMap<i64, i64> = HashMap::new();
+    let mut last_end_by_day: HashMap<i64, i64> = HashMap::new();
+    for row in rows {
+        let (event_type, ts) = row.map_err(to_error)?;
+        let day_start = local_day_start_ts(ts);
+        match event_type.as_str() {
+            EVENT_START | EVENT_RESUME => {
+                first_start_by_day.entry(day_start).or_insert(ts);
+            }
+            _ => {
+                last_end_by_day.insert(day_start, ts);
+            }
+        }
+    }
+
+    let today_start = local_day_start_ts(now);
+    let still_working = find_running_task(conn)?.is_some() && today_start >= window.range_start;
+    if still_working {
+        first_start_by_day.entry(today_start).or_insert(today_start);
+    }
+
+    let mut days = Vec::new();
+    for day_start in window.day_starts.into_iter().rev() {
+        let Some(first_start_ts) = first_start_by_day.remove(&day_start) else {
+            continue;
+        };
+        days.push(WorkBoundsDay {
+            date_key: local_date_key(day_start),
+            day_start_ts: day_start,
+            first_start_ts,
+            last_end_ts: last_end_by_day.remove(&day_start),
+            still_working: still_working && day_start == today_start,
+        });
+    }
+
+    Ok(WorkBoundsResponse {
+        range: window.range,
+        generated_at: now,
+        days,
+    })
+}
+
+/// Per local day with focus, tracked seconds over the span from the first to the last
+/// focused moment of the day. Sessions crossing midnight are split at the day boundary and
+/// an open session counts up to now. Near 1.0 means few untracked gaps; manual
+/// adjustments count as tracked time and can push it above 1.0. Newest day first.
+pub fn get_focus_density(
+    conn: &Connection,
+    range: Option<String>,
+) -> AppResult<FocusDensityResponse> {
+    let summary = get_focus_summary(conn, range)?;
+
+    let days = summary
+        .days
+        .into_iter()
+        .filter_map(|day| {
+            let first_start_ts = day
+                .timeline_segments
+                .iter()
+                .map(|segment| segment.start_ts)
+                .min()?;
+            let last_end_ts = day
+                .timeline_segments
+                .iter()
+                .map(|segment| segment.end_ts)
+                .max()?;
+            let elapsed_seconds = last_end_ts - first_start_ts;
+            let tracked_seconds = day.total_focus_seconds;
+            Some(FocusDensityDay {
+                date_key: day.date_key,
+                day_start_ts: day.day_start_ts,
+                tracked_seconds,
+                elapsed_seconds,
+                density: (elapsed_seconds > 0)
+                    .then(|| tracked_seconds as f64 / elapsed_seconds as f64),
+            })
+        })
+        .collect();
+
+    Ok(FocusDensityResponse {
+        range: summary.range,
+        generated_at: summary.generated_at,
+        days,
+    })
+}
+
+/// Returns the stretches of `date` (`YYYY-MM-DD`, local) between the first and last
+/// tracked moment where no task was running.
+pub fn get_untracked_gaps(conn: &Connection, date: String) -> AppResult<UntrackedGapsResponse> {
+    let day_start = parse_local_date_start(&date)?;
+    let gaps = collect_untracked_gaps(conn, day_start)?;
+
+    Ok(UntrackedGapsResponse {
+        date_key: local_date_key(day_start),
+        day_start_ts: day_start,
+        total_seconds: gaps.iter().map(|gap| gap.seconds).sum(),
+        gaps,
+    })
+}
+
+/// Returns the largest gap from `get_untracked_gaps` for `date`, the earliest on a tie,
+/// or `None` when the day has no gaps or no tracked time.
+pub fn get_longest_untracked_gap(
+    conn: &Connection,
+    date: String,
+) -> AppResult<Option<UntrackedGap>> {
+    let day_start = parse_local_date_start(&date)?;
+    Ok(collect_untracked_gaps(conn, day_start)?
+        .into_iter()
+        .reduce(|longest, gap| {
+            if gap.seconds > longest.seconds {
+                gap
+            } else {
+                longest
+            }
+        }))
+}
+
+fn collect_untracked_gaps(conn: &Connection, day_start: i64) -> AppResult<Vec<UntrackedGap>> {
+    let window_end = shift_local_day_start(day_start, 1).min(now_ts());
+
+    let mut intervals = collect_focus_intervals(conn, Some(day_start), window_end)?;
+    intervals.sort_by_key(|interval| (interval.start_ts, interval.end_ts));
+
+    let mut gaps = Vec::new();
+    let mut covered_until: Option<i64> = None;
+    for interval in intervals {
+        match covered_until {
+            Some(end) if interval.start_ts > end => {
+                gaps.push(UntrackedGap {
+                    start_ts: end,
+                    end_ts: interval.start_ts,
+                    seconds: interval.start_ts - end,
+                });
+                covered_until = Some(interval.end_ts);
+            }
+            Some(end) => covered_until = Some(end.max(interval.end_ts)),
+            None => covered_until = Some(interval.end_ts),
+        }
+    }
+
+    Ok(gaps)
+}
+
+/// Billable time for `client`, one invoice line per task. Tasks without time in the
+/// window get a line only when `include_zero` is set.
+pub fn get_billable_report(
+    conn: &Connection,
+    range: Option<String>,
+    client: String,
+    include_zero: bool,
+) -> AppResult<BillableReport> {
+    let client = client.trim().to_string();
+    if client.is_empty() {
+        return Err(validation_error("client cannot be empty"));
+    }
+
+    let now = now_ts();
+    let (window_start, resolved_range) = resolve_window(range, now)?;
+
+    let structured_ids = load_task_ids_with_tag(conn, &format!("{TAG_KEY_CLIENT}:{client}"))?;
+    let (client_task_ids, matched_by) = if structured_ids.is_empty() {
+        (load_task_ids_with_tag(conn, &client)?, "plain")
+    } else {
+        (structured_ids, "structured")
+    };
+    let mut billable_ids = load_task_ids_with_tag(conn, TAG_BILLABLE_TRUE)?;
+    billable_ids.extend(load_task_ids_with_tag(conn, TAG_BILLABLE)?);
+
+    let exclusive_seconds = replay_exclusive_seconds(conn, window_start, now)?;
+    let mut tasks = load_tasks_for_reporting(conn)?
+        .into_iter()
+        .filter(|task| client_task_ids.contains(&task.id) && billable_ids.contains(&task.id))
+        .filter_map(|task| {
+            let seconds = *exclusive_seconds.get(&task.id).unwrap_or(&0);
+            if !include_zero && seconds <= 0 {
+                return None;
+            }
+            Some(BillableTaskLine {
+                invoice_line: format_invoice_line(&task.title, seconds),
+                task_id: task.id,
+                title: task.title,
+                seconds,
+            })
+        })
+        .collect::<Vec<_>>();
+    tasks.sort_by(|left, right| {
+        right
+            .seconds
+            .cmp(&left.seconds)
+            .then_with(|| left.title.cmp(&right.title))
+    });
+
+    let total_seconds = tasks.iter().map(|line| line.seconds).sum::<i64>();
+    let invoice_line = format_invoice_line(&format!("{client} ({resolved_range})"), total_seconds);
+
+    Ok(BillableReport {
+        range: resolved_range,
+        client,
+        generated_at: now,
+        matched_by: matched_by.to_string(),
+        tasks,
+        total_seconds,
+        invoice_line,
+    })
+}
+
+/// Returns windowed totals for the requested tasks only. Inclusive totals cover each
+/// task's live descendants; archived ids are reported in `skipped_archived_ids` instead
+/// of failing the whole request.
+pub fn get_totals_for(
+    conn: &Connection,
+    task_ids: Vec<String>,
+    range: Option<String>,
+) -> AppResult<TaskTotalsResponse> {
+    let now = now_ts();
+    let (window_start, resolved_range) = resolve_window(range, now)?;
+
+    let mut live_ids = Vec::new();
+    let mut skipped_archived_ids = Vec::new();
+    for task_id in task_ids {
+        let archived_at: Option<i64> = conn
+            .query_row(
+                "SELECT archived_at FROM tasks WHERE id = ?1 LIMIT 1",
+                params![task_id],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(to_error)?
+            .ok_or_else(|| not_found_error(format!("task {task_id} not found")))?;
+        if archived_at.is_some() {
+            if !skipped_archived_ids.contains(&task_id) {
+                skipped_archived_ids.push(task_id);
+            }
+        } else if !live_ids.contains(&task_id) {
+            live_ids.push(task_id);
+        }
+    }
+
+    let exclusive_seconds = if live_ids.is_empty() {
+        HashMap::new()
+    } else {
+        replay_exclusive_seconds(conn, window_start, now)?
+    };
+    let mut totals = HashMap::new();
+    for task_id in live_ids {
+        let inclusive_seconds = collect_subtree_ids(conn, &task_id)?
+            .iter()
+            .map(|id| exclusive_seconds.get(id).copied().unwrap_or(0))
+            .sum();
+        totals.insert(
+            task_id.clone(),
+            TaskTotals {
+                inclusive_seconds,
+                exclusive_seconds: exclusive_seconds.get(&task_id).copied().unwrap_or(0),
+            },
+        );
+    }
+
+    Ok(TaskTotalsResponse {
+        range: resolved_range,
+        generated_at: now,
+        totals,
+        skipped_archived_ids,
+    })
+}
+
+/// Inclusive seconds for `task_id` with each subtree rooted at `excluded_ids` counted as
+/// zero. `excluded_seconds` is what those subtrees would have added.
+pub fn get_inclusive_excluding(
+    conn: &Connection,
+    task_id: String,
+    excluded_ids: Vec<String>,
+    range: Option<String>,
+) -> AppResult<InclusiveExcludingResponse> {
+    ensure_task_exists(conn, &task_id)?;
+    let now = now_ts();
+    let (window_start, resolved_range) = resolve_window(range, now)?;
+
+    let subtree_ids = collect_subtree_ids(conn, &task_id)?;
+    let mut pruned = HashSet::new();
+    for excluded_id in excluded_ids {
+        if excluded_id == task_id {
+            return Err(validation_error("cannot exclude the task itself"));
+        }
+        if !subtree_ids.contains(&excluded_id) {
+            return Err(validation_error(format!(
+                "task {excluded_id} is not a descendant of task {task_id}"
+            )));
+        }
+        pruned.insert(excluded_id);
+    }
+
+    let tasks = load_tasks(conn)?;
+    let exclusive = replay_exclusive_seconds(conn, window_start, now)?;
+    let mut children_by_parent: HashMap<String, Vec<String>> = HashMap::new();
+    for task in &tasks {
+        if let Some(parent_id) = &task.parent_id {
+            children_by_parent
+                .entry(parent_id.clone())
+                .or_default()
+                .push(task.id.clone());
+        }
+    }
+    let full_seconds = compute_inclusive(
+        &task_id,
+        &children_by_parent,
+        &exclusive,
+        &mut HashMap::new(),
+        &mut HashSet::new(),
+    );
+    for children in children_by_parent.values_mut() {
+        children.retain(|child_id| !pruned.contains(child_id));
+    }
+    let inclusive_seconds = compute_inclusive(
+        &task_id,
+        &children_by_parent,
+        &exclusive,
+        &mut HashMap::new(),
+        &mut HashSet::new(),
+    );
+
+    Ok(InclusiveExcludingResponse {
+        task_id,
+        range: resolved_range,
+        generated_at: now,
+        inclusive_seconds,
+        excluded_seconds: full_seconds - inclusive_seconds,
+    })
+}
+
+pub fn export_task_subtree(
+    conn: &Connection,
+    task_id: String,
+    include_archived: bool,
+    include_totals: bool,
+) -> AppResult<TaskSubtreeExport> {
+    if include_archived {
+        load_archived_at(conn, &task_id)?;
+    } else {
+        ensure_task_exists(conn, &task_id)?;
+    }
+
+    let now = now_ts();
+    let exclusive_seconds = if include_totals {
+        Some(replay_exclusive_seconds(conn, None, now)?)
+    } else {
+        None
+    };
+    let mut visited = HashSet::new();
+    let root = build_exported_node(
+        conn,
+        &task_id,
+        include_archived,
+        exclusive_seconds.as_ref(),
+        &mut visited,
+    )?;
+
+    Ok(TaskSubtreeExport {
+        format_version: SUBTREE_EXPORT_FORMAT_VERSION,
+        exported_at: now,
+        root,
+    })
+}
+
+/// Read-only self-check comparing the global event replay with each task's own ledger
+/// (the per-task replay used for adjustment validation). Returns the tasks whose totals
+/// disagree; an empty list means the event log is internally consistent.
+pub fn verify_totals(conn: &Connection) -> AppResult<Vec<TotalsDrift>> {
+    let now = now_ts();
+    let replayed = replay_exclusive_seconds(conn, None, now)?;
+
+    let mut drifts = Vec::new();
+    for task in load_tasks_for_reporting(conn)? {
+        let replayed_seconds = replayed.get(&task.id).copied().unwrap_or(0);
+        let stored_seconds = task_total_focus_seconds(conn, &task.id, now)?;
+        if replayed_seconds != stored_seconds {
+            drifts.push(TotalsDrift {
+                task_id: task.id,
+                title: task.title,
+                replayed_seconds,
+                stored_seconds,
+                difference_seconds: replayed_seconds - stored_seconds,
+            });
+        }
+    }
+
+    Ok(drifts)
+}
+
+/// Diagnostic dump of the intervals and adjustments the global replay credits to
+/// `task_id` in the overview window, before and after clipping to it.
+pub fn debug_intervals(
+    conn: &Connection,
+    task_id: String,
+    range: Option<String>,
+) -> AppResult<DebugIntervalsResponse> {
+    ensure_task_exists(conn, &task_id)?;
+    let now = now_ts();
+    let (window_start, resolved_range) = resolve_window(range, now)?;
+
+    let mut intervals = collect_focus_intervals(conn, window_start, now)?
+        .into_iter()
+        .filter(|interval| interval.task_id == task_id)
+        .map(|interval| DebugInterval {
+            start_ts: interval.unclipped_start_ts,
+            end_ts: interval.unclipped_end_ts,
+            clipped_start_ts: interval.start_ts,
+            clipped_end_ts: interval.end_ts,
+            seconds: interval.end_ts - interval.start_ts,
+        })
+        .collect::<Vec<_>>();
+    intervals.sort_by_key(|interval| (interval.start_ts, interval.end_ts));
+    let adjustments = collect_focus_adjustments(conn, window_start, now)?
+        .into_iter()
+        .filter(|adjustment| adjustment.task_id == task_id)
+        .map(|adjustment| DebugAdjustment {
+            ts: adjustment.ts,
+            delta_seconds: adjustment.delta_seconds,
+        })
+        .collect::<Vec<_>>();
+    let total_seconds = intervals
+        .iter()
+        .map(|interval| interval.seconds)
+        .sum::<i64>()
+        + adjustments
+            .iter()
+            .map(|adjustment| adjustment.delta_seconds)
+            .sum::<i64>();
+
+    Ok(DebugIntervalsResponse {
+        task_id,
+        range: resolved_range,
+        window_start,
+        window_end: now,
+        intervals,
+        adjustments,
+        total_seconds,
+    })
+}
+
+/// Read-only check that replays every task's `tag_add`/`tag_remove` events (archived tasks
+/// included) and compares the resulting tags with its `task_tags` rows. Names are compared
+/// after `sanitize_tag` normalization, case-insensitively.
+/// Returns the tasks that disagree; an empty list means the two are in sync.
+pub fn verify_tag_consistency(conn: &Connection) -> AppResult<Vec<TagDiscrepancy>> {
+    let mut logged_by_task = replay_logged_tags(conn)?;
+    let mut attached_by_task: HashMap<String, BTreeMap<String, String>> = HashMap::new();
+    {
+        let mut stmt = conn
+            .prepare(
+                "SELECT tt.task_id, tg.name
+                 FROM task_tags tt
+                 INNER JOIN tags tg ON tg.id = tt.tag_id",
+            )
+            .map_err(to_error)?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+            })
+            .map_err(to_error)?;
+        for row in rows {
+            let (task_id, name) = row.map_err(to_error)?;
+            let key = tag_identity(&name).map_or_else(|| name.to_lowercase(), |(key, _)| key);
+            attached_by_task
+                .entry(task_id)
+                .or_default()
+                .insert(key, name);
+        }
+    }
+
+    let mut stmt = conn
+        .prepare("SELECT id, title FROM tasks ORDER BY created_at ASC, id ASC")
+        .map_err(to_error)?;
+    let tasks = stmt
+        .query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })
+        .map_err(to_error)?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(to_error)?;
+
+    let mut discrepancies = Vec::new();
+    for (task_id, title) in tasks {
+        let logged = logged_by_task.remove(&task_id).unwrap_or_default();
+        let attached = attached_by_task.remove(&task_id).unwrap_or_default();
+        let missing_rows = logged
+            .iter()
+            .filter(|(key, _)| !attached.contains_key(*key))
+            .map(|(_, name)| name.clone())
+            .collect::<Vec<_>>();
+        let unlogged_rows = attached
+            .iter()
+            .filter(|(key, _)| !logged.contains_key(*key))
+            .map(|(_, name)| name.clone())
+            .collect::<Vec<_>>();
+        if !missing_rows.is_empty() || !unlogged_rows.is_empty() {
+            discrepancies.push(TagDiscrepancy {
+                task_id,
+                title,
+                missing_rows,
+                unlogged_rows,
+            });
+        }
+    }
+
+    Ok(discrepancies)
+}
+
+/// Rewrites `task_tags` to match the event log for every task `verify_tag_consistency`
+/// flags, in one transaction, and returns what was fixed. Logged names go through
+/// `sanitize_tag` before a tag is looked up or created. No events are written.
+pub fn reconcile_tags(conn: &mut Connection) -> AppResult<Vec<TagDiscrepancy>> {
+    let discrepancies = verify_tag_consistency(conn)?;
+    if discrepancies.is_empty() {
+        return Ok(discrepancies);
+    }
+
+    let ts = now_ts();
+    let tx = begin_transaction(conn)?;
+    for discrepancy in &discrepancies {
+        for name in &discrepancy.missing_rows {
+            let clean_tag = sanitize_tag(name)?;
+            let tag_id = find_or_create_tag_id(&tx, &clean_tag)?;
+            tx.execute(
+                "INSERT OR IGNORE INTO task_tags (task_id, tag_id, created_at) VALUES (?1, ?2, ?3)",
+                params![discrepancy.task_id, tag_id, ts],
+            )
+            .map_err(to_error)?;
+        }
+        for name in &discrepancy.unlogged_rows {
+            tx.execute(
+                "DELETE FROM task_tags
+                 WHERE task_id = ?1
+                   AND tag_id IN (SELECT id FROM tags WHERE lower(name) = lower(?2))",
+                params![discrepancy.task_id, name],
+            )
+            .map_err(to_error)?;
+        }
+    }
+    tx.commit().map_err(to_error)?;
+
+    Ok(discrepancies)
+}
+
+/// Tags each task is left with after replaying its tag events, as sanitized names keyed by
+/// `tag_identity`. Events logged before a normalization change still match their tag.
+fn replay_logged_tags(conn: &Connection) -> AppResult<HashMap<String, BTreeMap<String, String>>> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT task_id, event_type, payload
+             FROM time_events
+             WHERE event_type IN (?1, ?2)
+             ORDER BY ts ASC, id ASC",
+        )
+        .map_err(to_error)?;
+    let rows = stmt
+        .query_map(params![EVENT_TAG_ADD, EVENT_TAG_REMOVE], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, Option<String>>(2)?,
+            ))
+        })
+        .map_err(to_error)?;
+
+    let mut tags_by_task: HashMap<String, BTreeMap<String, String>> = HashMap::new();
+    for row in rows {
+        let (task_id, event_type, payload) = row.map_err(to_error)?;
+        let Some((key, name)) = parse_event_payload(payload.as_deref())
+            .0
+            .and_then(|value| tag_identity(value.get("tag")?.as_str()?))
+        else {
+            continue;
+        };
+        let tags = tags_by_task.entry(task_id).or_default();
+        if event_type == EVENT_TAG_ADD {
+            tags.insert(key, name);
+        } else {
+            tags.remove(&key);
+        }
+    }
+
+    Ok(tags_by_task)
+}
+
+/// Walks every task's parent chain (archived tasks included) and returns each parent
+/// cycle once, as task ids in parent order starting from the smallest id.
+pub fn find_cycles(conn: &Connection) -> AppResult<Vec<Vec<String>>> {
+    let mut stmt = conn
+        .prepare("SELECT id, parent_id FROM tasks")
+        .map_err(to_error)?;
+    let parent_by_task = stmt
+        .query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, Option<String>>(1)?))
+        })
+        .map_err(to_error)?
+        .collect::<Result<HashMap<_, _>, _>>()
+        .map_err(to_error)?;
+
+    let mut task_ids = parent_by_task.keys().cloned().collect::<Vec<_>>();
+    task_ids.sort();
+
+    let mut settled: HashSet<String> = HashSet::new();
+    let mut cycles = Vec::new();
+    for start_id in task_ids {
+        let mut path: Vec<String> = Vec::new();
+        let mut current = Some(start_id);
+        while let Some(task_id) = current {
+            if settled.contains(&task_id) {
+                break;
+            }
+            if let Some(position) = path.iter().position(|visited| *visited == task_id) {
+                let mut cycle = path[position..].to_vec();
+                let min_position = cycle
+                    .iter()
+                    .enumerate()
+                    .min_by(|left, right| left.1.cmp(right.1))
+                    .map(|(index, _)| index)
+                    .unwrap_or(0);
+                cycle.rotate_left(min_position);
+                cycles.push(cycle);
+                break;
+            }
+            current = parent_by_task.get(&task_id).cloned().flatten();
+            path.push(task_id);
+        }
+        settled.extend(path);
+    }
+
+    Ok(cycles)
+}
+
+/// Recovers from a corrupted tree by moving `task_id` to the root, provided it is part of
+/// a parent cycle.
+pub fn break_cycle(conn: &mut Connection, task_id: String) -> AppResult<()> {
+    let parent_id: Option<String> = conn
+        .query_row(
+            "SELECT parent_id FROM tasks WHERE id = ?1",
+            params![task_id],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(to_error)?
+        .ok_or_else(|| not_found_error(format!("task {task_id} not found")))?;
+    let in_cycle = find_cycles(conn)?
+        .iter()
+        .any(|cycle| cycle.contains(&task_id));
+    if !in_cycle {
+        return Err(conflict_error(format!(
+            "task {task_id} is not part of a parent cycle"
+        )));
+    }
+
+    let tx = begin_transaction(conn)?;
+    tx.execute(
+        "UPDATE tasks SET parent_id = NULL WHERE id = ?1",
+        params![task_id],
+    )
+    .map_err(to_error)?;
+    append_event(
+        &tx,
+        &task_id,
+        EVENT_REPARENT,
+        now_ts(),
+        Some(json!({
+            "old_parent_id": parent_id,
+            "new_parent_id": null,
+            "reason": "break_cycle"
+        })),
+    )?;
+    tx.commit().map_err(to_error)?;
+    Ok(())
+}
+
+pub fn get_overdue_tasks(conn: &Connection) -> AppResult<Vec<OverdueTaskRecord>> {
+    load_overdue_tasks(conn, None, now_ts())
+}
+
+/// Returns incomplete tasks whose due date passed in `(after_ts, until_ts]`, so a
+/// periodic checker can report each deadline once as it goes by.
+pub fn get_tasks_due_between(
+    conn: &Connection,
+    after_ts: i64,
+    until_ts: i64,
+) -> AppResult<Vec<OverdueTaskRecord>> {
+    load_overdue_tasks(conn, Some(after_ts), until_ts)
+}
+
+/// Groups non-archived tasks whose titles match after trimming, collapsing whitespace and
+/// lowercasing, or differ by at most `threshold` character edits. Similar titles chain,
+/// so a group can hold two titles further apart than the threshold. Only the first 200
+/// characters of each title are compared. Groups come in creation order of their oldest
+/// task; the scan stops early once the comparison or group cap is hit.
+pub fn find_duplicate_tasks(
+    conn: &Connection,
+    threshold: Option<i64>,
+) -> AppResult<DuplicateTasksResponse> {
+    let threshold = threshold.unwrap_or(DEFAULT_DUPLICATE_TITLE_DISTANCE);
+    if !(0..=MAX_DUPLICATE_TITLE_DISTANCE).contains(&threshold) {
+        return Err(validation_error(format!(
+            "threshold must be between 0 and {MAX_DUPLICATE_TITLE_DISTANCE}"
+        )));
+    }
+
+    let tasks = load_tasks(conn)?;
+    let normalized = tasks
+        .iter()
+        .map(|task| normalize_task_title(&task.title))
+        .collect::<Vec<_>>();
+    let chars = normalized
+        .iter()
+        .map(|title| {
+            title
+                .chars()
+                .take(MAX_DUPLICATE_TITLE_CHARS)
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    let max_distance = threshold as usize;
+
+    let mut roots = (0..tasks.len()).collect::<Vec<_>>();
+    let mut comparisons = 0;
+    let mut truncated = false;
+    'scan: for left in 0..tasks.len() {
+        for right in (left + 1)..tasks.len() {
+            if chars[left].len().abs_diff(chars[right].len()) > max_distance {
+                continue;
+            }
+            if comparisons == MAX_DUPLICATE_COMPARISONS {
+                truncated = true;
+                break 'scan;
+            }
+            comparisons += 1;
+            if within_edit_distance(&chars[left], &chars[right], max_distance) {
+                let left_root = find_root(&mut roots, left);
+                let right_root = find_root(&mut roots, right);
+                roots[left_root.max(right_root)] = left_root.min(right_root);
+            }
+        }
+    }
+
+    let mut members_by_root: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+    for index in 0..tasks.len() {
+        let root = find_root(&mut roots, index);
+        members_by_root.entry(root).or_default().push(index);
+    }
+    let mut groups = members_by_root
+        .into_iter()
+        .filter(|(_, members)| members.len() > 1)
+        .map(|(root, members)| DuplicateTaskGroup {
+            normalized_title: normalized[root].clone(),
+            tasks: members
+                .into_iter()
+                .map(|index| DuplicateTaskCandidate {
+                    task_id: tasks[index].id.clone(),
+                    title: tasks[index].title.clone(),
+                    parent_id: tasks[index].parent_id.clone(),
+                    created_at: tasks[index].created_at,
+                })
+                .collect(),
+        })
+        .collect::<Vec<_>>();
+    if groups.len() > MAX_DUPLICATE_GROUPS {
+        groups.truncate(MAX_DUPLICATE_GROUPS);
+        truncated = true;
+    }
+
+    Ok(DuplicateTasksResponse {
+        threshold,
+        truncated,
+        groups,
+    })
+}
+
+fn normalize_task_title(title: &str) -> String {
+    title
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+/// Levenshtein distance check limited to the diagonal band `|i - j| <= max_distance`.
+/// Cells outside the band and values above the limit are clamped to `max_distance + 1`,
+/// and the scan gives up once a whole row is over, so a pair costs
+/// O(len * max_distance) at most.
+fn within_edit_distance(left: &[char], right: &[char], max_distance: usize) -> bool {
+    if left.len().abs_diff(right.len()) > max_distance {
+        return false;
+    }
+    let over = max_distance + 1;
+    let mut previous = (0..=right.len()).map(|j| j.min(over)).collect::<Vec<_>>();
+    let mut current = vec![over; right.len() + 1];
+    for (i, left_char) in left.iter().enumerate() {
+        let row = i + 1;
+        let from = row.saturating_sub(max_distance).max(1);
+        let to = (row + max_distance).min(right.len());
+        current[0] = row.min(over);
+        if from > 1 {
+            current[from - 1] = over;
+        }
+        let mut row_min = current[0];
+        for j in from..=to {
+            let substitution = previous[j - 1] + usize::from(*left_char != right[j - 1]);
+            let value = substitution
+                .min(previous[j] + 1)
+                .min(current[j - 1] + 1)
+                .min(over);
+            current[j] = value;
+            row_min = row_min.min(value);
+        }
+        if to < right.len() {
+            current[to + 1] = over;
+        }
+        if row_min > max_distance {
+            return false;
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+    previous[right.len()] <= max_distance
+}
+
+fn find_root(roots: &mut [usize], index: usize) -> usize {
+    let mut root = index;
+    while roots[root] != root {
+        root = roots[root];
+    }
+    let mut current = index;
+    while roots[current] != root {
+        let next = roots[current];
+        roots[current] = root;
+        current = next;
+    }
+    root
+}
+
+/// Returns incomplete tasks whose latest timing event is older than `min_idle_days`
+/// (0 to 3650). Tasks that were never worked on are measured from their creation time.
+pub fn get_stale_tasks(conn: &Connection, min_idle_days: i64) -> AppResult<Vec<StaleTaskRecord>> {
+    if !(0..=MAX_STALE_IDLE_DAYS).contains(&min_idle_days) {
+        return Err(validation_error(format!(
+            "min_idle_days must be between 0 and {MAX_STALE_IDLE_DAYS}"
+        )));
+    }
+
+    let now = now_ts();
+    let cutoff = now - min_idle_days * 86_400;
+    let mut stmt = conn
+        .prepare(
+            "SELECT t.id, t.title, t.created_at, MAX(e.ts) AS last_worked_at
+             FROM tasks t
+             LEFT JOIN time_events e
+               ON e.task_id = t.id
+              AND e.event_type IN ('start', 'pause', 'resume', 'stop', 'adjust')
+             WHERE t.archived_at IS NULL
+               AND t.completed_at IS NULL
+             GROUP BY t.id
+             HAVING COALESCE(MAX(e.ts), t.created_at) <= ?1
+             ORDER BY COALESCE(MAX(e.ts), t.created_at) ASC, t.created_at ASC",
+        )
+        .map_err(to_error)?;
+
+    let rows = stmt
+        .query_map(params![cutoff], |row| {
+            let created_at: i64 = row.get(2)?;
+            let last_worked_at: Option<i64> = row.get(3)?;
+            Ok(StaleTaskRecord {
+                task_id: row.get(0)?,
+                title: row.get(1)?,
+                last_worked_at,
+                idle_seconds: now - last_worked_at.unwrap_or(created_at),
+            })
+        })
+        .map_err(to_error)?;
+
+    rows.collect::<Result<Vec<_>, _>>().map_err(to_error)
+}
+
+/// Lists running and paused tasks, running first, with all-time exclusive seconds that
+/// include the open interval. `state_since` is the ts of the task's latest clock event.
+pub fn get_in_progress_tasks(conn: &Connection) -> AppResult<Vec<InProgressTaskRecord>> {
+    let now = now_ts();
+    let mut stmt = conn
+        .prepare(
+            "SELECT t.id, t.parent_id, t.title, t.status, MAX(e.ts)
+             FROM tasks t
+             LEFT JOIN time_events e
+               ON e.task_id = t.id
+              AND e.event_type IN ('start', 'pause', 'resume', 'stop')
+             WHERE t.archived_at IS NULL
+               AND t.status IN (?1, ?2)
+             GROUP BY t.id
+             ORDER BY t.status = ?1 DESC, MAX(e.ts) DESC, t.created_at ASC",
+        )
+        .map_err(to_error)?;
+
+    let rows = stmt
+        .query_map(params![STATUS_RUNNING, STATUS_PAUSED], |row| {
+            Ok(InProgressTaskRecord {
+                task_id: row.get(0)?,
+                parent_id: row.get(1)?,
+                title: row.get(2)?,
+                status: row.get(3)?,
+                exclusive_seconds: 0,
+                state_since: row.get(4)?,
+            })
+        })
+        .map_err(to_error)?;
+
+    let mut records = rows.collect::<Result<Vec<_>, _>>().map_err(to_error)?;
+    for record in &mut records {
+        record.exclusive_seconds = task_total_focus_seconds(conn, &record.task_id, now)?;
+    }
+    Ok(records)
+}
+
+/// Counts tasks created and completed inside the window. Every task is counted on its own,
+/// so subtasks contribute separately rather than being rolled into their parent. Tasks
+/// completed in the window count toward the median even if they were created before it.
+pub fn get_completion_rate(
+    conn: &Connection,
+    range: Option<String>,
+) -> AppResult<CompletionRateResponse> {
+    let now = now_ts();
+    let (window_start, resolved_range) = resolve_window(range, now)?;
+    let in_window = |ts: i64| ts <= now && window_start.is_none_or(|start| ts >= start);
+
+    let tasks = load_tasks_for_reporting(conn)?;
+    let created_count = tasks
+        .iter()
+        .filter(|task| in_window(task.created_at))
+        .count() as i64;
+    let durations = tasks
+        .iter()
+        .filter_map(|task| {
+            task.completed_at
+                .filter(|completed_at| in_window(*completed_at))
+                .map(|completed_at| (completed_at - task.created_at).max(0))
+        })
+        .collect::<Vec<_>>();
+
+    Ok(CompletionRateResponse {
+        range: resolved_range,
+        generated_at: now,
+        created_count,
+        completed_count: durations.len() as i64,
+        median_seconds_to_complete: if durations.is_empty() {
+            None
+        } else {
+            Some(median_i64(&durations))
+        },
+    })
+}
+
+fn insert_idle_task(
+    tx: &Transaction<'_>,
+    parent_id: Option<&str>,
+    title: &str,
+    created_at: i64,
+) -> AppResult<String> {
+    let task_id = Uuid::new_v4().to_string();
+    tx.execute(
+        "INSERT INTO tasks (id, parent_id, title, status, created_at) VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![task_id, parent_id, title, STATUS_IDLE, created_at],
+    )
+    .map_err(to_error)?;
+    append_event(
+        tx,
+        &task_id,
+        EVENT_CREATE,
+        created_at,
+        Some(json!({ "parent_id": parent_id })),
+    )?;
+    Ok(task_id)
 }
 
 fn ensure_task_exists(conn: &Connection, task_id: &str) -> AppResult<()> {
     get_task_state(conn, task_id).map(|_| ())
 }
 
-fn get_task_state(conn: &Connection, task_id: &str) -> AppResult<TaskState> {
-    conn.query_row(
-        "SELECT parent_id, status FROM tasks WHERE id = ?1 AND archived_at IS NULL LIMIT 1",
-        params![task_id],
-        |row| {
-            Ok(TaskState {
-                parent_id: row.get(0)?,
-                status: row.get(1)?,
-            })
-        },
-    )
-    .optional()
-    .map_err(to_error)?
-    .ok_or_else(|| not_found_error(format!("task {task_id} not found or archived")))
+fn get_task_state(conn: &Connection, task_id: &str) -> AppResult<TaskState> {
+    conn.query_row(
+        "SELECT parent_id, status FROM tasks WHERE id = ?1 AND archived_at IS NULL LIMIT 1",
+        params![task_id],
+        |row| {
+            Ok(TaskState {
+                parent_id: row.get(0)?,
+                status: row.get(1)?,
+            })
+        },
+    )
+    .optional()
+    .map_err(to_error)?
+    .ok_or_else(|| not_found_error(format!("task {task_id} not found or archived")))
+}
+
+fn latest_focus_task(conn: &Connection) -> AppResult<Option<String>> {
+    conn.query_row(
+        "SELECT task_id
+         FROM time_events
+         WHERE event_type IN ('start', 'resume')
+         ORDER BY ts DESC, id DESC
+         LIMIT 1",
+        [],
+        |row| row.get(0),
+    )
+    .optional()
+    .map_err(to_error)
+}
+
+fn latest_used_task(conn: &Connection) -> AppResult<Option<String>> {
+    conn.query_row(
+        "SELECT e.task_id
+         FROM time_events e
+         INNER JOIN tasks t ON t.id = e.task_id
+         WHERE e.event_type IN ('start', 'resume', 'pause', 'stop', 'adjust')
+           AND t.archived_at IS NULL
+         ORDER BY e.ts DESC, e.id DESC
+         LIMIT 1",
+        [],
+        |row| row.get(0),
+    )
+    .optional()
+    .map_err(to_error)
+}
+
+fn find_running_task(conn: &Connection) -> AppResult<Option<String>> {
+    conn.query_row(
+        "SELECT id FROM tasks WHERE status = ?1 AND archived_at IS NULL LIMIT 1",
+        params![STATUS_RUNNING],
+        |row| row.get(0),
+    )
+    .optional()
+    .map_err(to_error)
+}
+
+/// Walks a task's focus events in replay order and rejects sequences that would make
+/// `replay_exclusive_seconds` miscount: a session opened while another is still open
+/// (overlap), or a pause/resume/stop with nothing to close, which is how a stop backdated
+/// before its start (a negative interval) shows up. Editing commands call this before
+/// committing.
+fn validate_task_timeline(conn: &Connection, task_id: &str) -> AppResult<()> {
+    check_timeline_events(task_id, &load_timeline_events(conn, task_id)?)
+}
+
+/// Whether a closed `start_ts..end_ts` session could be added to the task without breaking
+/// its state machine, e.g. by landing inside a paused gap.
+fn session_fits_timeline(
+    conn: &Connection,
+    task_id: &str,
+    start_ts: i64,
+    end_ts: i64,
+) -> AppResult<bool> {
+    let mut events = load_timeline_events(conn, task_id)?;
+    // New rows sort after existing ones with the same timestamp.
+    let start_at = events.partition_point(|(_, ts)| *ts <= start_ts);
+    events.insert(start_at, (EVENT_START.to_string(), start_ts));
+    let stop_at = events.partition_point(|(_, ts)| *ts <= end_ts);
+    events.insert(stop_at, (EVENT_STOP.to_string(), end_ts));
+    Ok(check_timeline_events(task_id, &events).is_ok())
+}
+
+fn load_timeline_events(conn: &Connection, task_id: &str) -> AppResult<Vec<(String, i64)>> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT event_type, ts
+             FROM time_events
+             WHERE task_id = ?1 AND event_type IN ('start', 'resume', 'pause', 'stop')
+             ORDER BY ts ASC, id ASC",
+        )
+        .map_err(to_error)?;
+    let rows = stmt
+        .query_map(params![task_id], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?))
+        })
+        .map_err(to_error)?;
+
+    rows.collect::<Result<Vec<_>, _>>().map_err(to_error)
+}
+
+fn check_timeline_events(task_id: &str, events: &[(String, i64)]) -> AppResult<()> {
+    let mut state = STATUS_IDLE;
+    for (event_type, ts) in events {
+        state = match (state, event_type.as_str()) {
+            (STATUS_IDLE | STATUS_STOPPED, EVENT_START) => STATUS_RUNNING,
+            (STATUS_RUNNING, EVENT_PAUSE) => STATUS_PAUSED,
+            (STATUS_PAUSED, EVENT_RESUME) => STATUS_RUNNING,
+            (STATUS_RUNNING | STATUS_PAUSED, EVENT_STOP) => STATUS_STOPPED,
+            (STATUS_RUNNING | STATUS_PAUSED, EVENT_START) | (STATUS_RUNNING, EVENT_RESUME) => {
+                return Err(conflict_error(format!(
+                    "task {task_id} timeline has overlapping sessions at {ts}"
+                )));
+            }
+            _ => {
+                return Err(conflict_error(format!(
+                    "task {task_id} timeline has a {event_type} at {ts} with no open session"
+                )));
+            }
+        };
+    }
+
+    Ok(())
+}
+
+/// True when one task is an ancestor of the other (or they are the same task).
+fn in_same_branch(conn: &Connection, left_id: &str, right_id: &str) -> AppResult<bool> {
+    Ok(collect_subtree_ids(conn, left_id)?
+        .iter()
+        .any(|id| id == right_id)
+        || collect_subtree_ids(conn, right_id)?
+            .iter()
+            .any(|id| id == left_id))
+}
+
+fn resolve_event_ts(conn: &Connection, task_id: &str, at_ts: Option<i64>) -> AppResult<i64> {
+    let now = now_ts();
+    let Some(at_ts) = at_ts else {
+        return Ok(now);
+    };
+    if at_ts > now {
+        return Err(validation_error("at_ts cannot be in the future"));
+    }
+
+    let (task_last_ts, other_focus_ts): (Option<i64>, Option<i64>) = conn
+        .query_row(
+            "SELECT
+                (SELECT MAX(ts) FROM time_events WHERE task_id = ?1),
+                (SELECT MAX(ts) FROM time_events
+                 WHERE task_id <> ?1
+                   AND event_type IN ('start', 'resume', 'pause', 'stop'))",
+            params![task_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .map_err(to_error)?;
+    if task_last_ts.is_some_and(|last_ts| at_ts < last_ts) {
+        return Err(validation_error(
+            "at_ts cannot be before the task's last event",
+        ));
+    }
+    if other_focus_ts.is_some_and(|last_ts| at_ts < last_ts) {
+        return Err(conflict_error(
+            "at_ts overlaps focus already recorded for another task",
+        ));
+    }
+
+    Ok(at_ts)
+}
+
+fn resolve_session_end_ts(conn: &Connection, task_id: &str, at_ts: Option<i64>) -> AppResult<i64> {
+    let now = now_ts();
+    let Some(at_ts) = at_ts else {
+        return Ok(now);
+    };
+    if at_ts > now {
+        return Err(validation_error("at_ts cannot be in the future"));
+    }
+
+    let last_focus_ts: Option<i64> = conn
+        .query_row(
+            "SELECT MAX(ts) FROM time_events
+             WHERE task_id = ?1 AND event_type IN ('start', 'resume', 'pause', 'stop')",
+            params![task_id],
+            |row| row.get(0),
+        )
+        .map_err(to_error)?;
+    if last_focus_ts.is_some_and(|last_ts| at_ts < last_ts) {
+        return Err(validation_error(
+            "at_ts cannot be before the task's last start or resume",
+        ));
+    }
+
+    Ok(at_ts)
+}
+
+/// A task's sessions in replay order, each paired with the id of the event that opened it.
+fn load_task_sessions(
+    conn: &Connection,
+    task_id: &str,
+    now: i64,
+) -> AppResult<Vec<(i64, TaskSession)>> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, event_type, ts, payload
+             FROM time_events
+             WHERE task_id = ?1 AND event_type IN ('start', 'resume', 'pause', 'stop')
+             ORDER BY ts ASC, id ASC",
+        )
+        .map_err(to_error)?;
+    let rows = stmt
+        .query_map(params![task_id], |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, i64>(2)?,
+                row.get::<_, Option<String>>(3)?,
+            ))
+        })
+        .map_err(to_error)?;
+
+    let mut sessions: Vec<(i64, TaskSession)> = Vec::new();
+    let mut open = false;
+    for row in rows {
+        let (event_id, event_type, ts, payload) = row.map_err(to_error)?;
+        match event_type.as_str() {
+            EVENT_START | EVENT_RESUME if !open => {
+                let note = payload
+                    .as_deref()
+                    .and_then(|raw| serde_json::from_str::<serde_json::Value>(raw).ok())
+                    .and_then(|value| value.get("note")?.as_str().map(str::to_owned));
+                sessions.push((
+                    event_id,
+                    TaskSession {
+                        start_ts: ts,
+                        end_ts: None,
+                        duration_seconds: 0,
+                        note,
+                    },
+                ));
+                open = true;
+            }
+            EVENT_PAUSE | EVENT_STOP if open => {
+                if let Some((_, session)) = sessions.last_mut() {
+                    session.end_ts = Some(ts);
+                    session.duration_seconds = ts - session.start_ts;
+                }
+                open = false;
+            }
+            _ => {}
+        }
+    }
+    if open {
+        if let Some((_, session)) = sessions.last_mut() {
+            session.duration_seconds = (now - session.start_ts).max(0);
+        }
+    }
+
+    Ok(sessions)
 }
 
-fn latest_focus_task(conn: &Connection) -> AppResult<Option<String>> {
-    conn.query_row(
-        "SELECT task_id
-         FROM time_events
-         WHERE event_type IN ('start', 'resume')
-         ORDER BY ts DESC, id DESC
-         LIMIT 1",
-        [],
-        |row| row.get(0),
-    )
-    .optional()
-    .map_err(to_error)
+fn other_task_focused_between(
+    conn: &Connection,
+    task_id: &str,
+    from_ts: i64,
+    to_ts: i64,
+) -> AppResult<bool> {
+    let found: Option<i64> = conn
+        .query_row(
+            "SELECT id
+             FROM time_events
+             WHERE task_id <> ?1
+               AND event_type IN ('start', 'resume')
+               AND ts >= ?2 AND ts <= ?3
+             LIMIT 1",
+            params![task_id, from_ts, to_ts],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(to_error)?;
+    Ok(found.is_some())
 }
 
-fn latest_used_task(conn: &Connection) -> AppResult<Option<String>> {
-    conn.query_row(
-        "SELECT e.task_id
-         FROM time_events e
-         INNER JOIN tasks t ON t.id = e.task_id
-         WHERE e.event_type IN ('start', 'resume', 'pause', 'stop', 'adjust')
-           AND t.archived_at IS NULL
-         ORDER BY e.ts DESC, e.id DESC
-         LIMIT 1",
-        [],
-        |row| row.get(0),
-    )
-    .optional()
-    .map_err(to_error)
+/// Opens an immediate write transaction so lock contention surfaces here, before any work
+/// is done, and retries with exponential backoff while the database is busy or locked.
+fn begin_transaction(conn: &mut Connection) -> AppResult<Transaction<'_>> {
+    // `&mut` already rules out nesting; the shared reborrow lets the loop retry.
+    let conn: &Connection = conn;
+    let mut attempt = 0;
+    loop {
+        match Transaction::new_unchecked(conn, TransactionBehavior::Immediate) {
+            Ok(tx) => return Ok(tx),
+            Err(error) if is_busy_error(&error) => {
+                attempt += 1;
+                if attempt >= BUSY_RETRY_ATTEMPTS {
+                    return Err(AppError::busy(error.to_string()));
+                }
+                thread::sleep(Duration::from_millis(
+                    BUSY_RETRY_BASE_DELAY_MS << (attempt - 1),
+                ));
+            }
+            Err(error) => return Err(to_error(error)),
+        }
+    }
 }
 
-fn find_running_task(conn: &Connection) -> AppResult<Option<String>> {
-    conn.query_row(
-        "SELECT id FROM tasks WHERE status = ?1 AND archived_at IS NULL LIMIT 1",
-        params![STATUS_RUNNING],
-        |row| row.get(0),
+fn is_busy_error(error: &rusqlite::Error) -> bool {
+    matches!(
+        error.sqlite_error_code(),
+        Some(ErrorCode::DatabaseBusy | ErrorCode::DatabaseLocked)
     )
-    .optional()
-    .map_err(to_error)
 }
 
 fn append_event(
@@ -824,11 +5069,18 @@ fn append_event(
     payload: Option<serde_json::Value>,
 ) -> AppResult<()> {
     let payload_string = payload.map(|value| value.to_string());
+    // Only live events get sub-second detail; backdated ones fall back to `ts * 1000`.
+    let now_ms = now_ts_millis();
+    let ts_ms = (now_ms.div_euclid(1000) == ts).then_some(now_ms);
     tx.execute(
-        "INSERT INTO time_events (task_id, event_type, ts, payload) VALUES (?1, ?2, ?3, ?4)",
-        params![task_id, event_type, ts, payload_string],
+        "INSERT INTO time_events (task_id, event_type, ts, ts_ms, payload)
+         VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![task_id, event_type, ts, ts_ms, payload_string],
     )
     .map_err(to_error)?;
+    if matches!(event_type, EVENT_PAUSE | EVENT_STOP | EVENT_ADJUST) {
+        refresh_task_baseline(tx, task_id)?;
+    }
     Ok(())
 }
 
@@ -913,9 +5165,249 @@ fn maybe_auto_resume_parent(
     Ok(true)
 }
 
+fn maybe_auto_resume_after_break(tx: &Transaction<'_>, ts: i64) -> AppResult<Option<String>> {
+    if find_running_task(tx)?.is_some() {
+        return Ok(None);
+    }
+
+    let latest_focus_event: Option<(String, String, Option<String>)> = tx
+        .query_row(
+            "SELECT task_id, event_type, payload
+             FROM time_events
+             WHERE event_type IN ('start', 'pause', 'resume', 'stop')
+             ORDER BY ts DESC, id DESC
+             LIMIT 1",
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )
+        .optional()
+        .map_err(to_error)?;
+
+    let Some((task_id, event_type, payload)) = latest_focus_event else {
+        return Ok(None);
+    };
+    if event_type != EVENT_PAUSE {
+        return Ok(None);
+    }
+
+    let reason = payload
+        .and_then(|payload| serde_json::from_str::<serde_json::Value>(&payload).ok())
+        .and_then(|value| {
+            value
+                .get("reason")
+                .and_then(|raw| raw.as_str())
+                .map(str::to_owned)
+        });
+    if reason.as_deref() != Some(PAUSE_REASON_BREAK) {
+        return Ok(None);
+    }
+
+    let updated = tx
+        .execute(
+            "UPDATE tasks SET status = ?1 WHERE id = ?2 AND status = ?3 AND archived_at IS NULL",
+            params![STATUS_RUNNING, task_id, STATUS_PAUSED],
+        )
+        .map_err(to_error)?;
+    if updated == 0 {
+        return Ok(None);
+    }
+    append_event(
+        tx,
+        &task_id,
+        EVENT_RESUME,
+        ts,
+        Some(json!({ "reason": RESUME_REASON_BREAK_ENDED })),
+    )?;
+
+    Ok(Some(task_id))
+}
+
+fn load_task_records(
+    conn: &Connection,
+    window_start: Option<i64>,
+    now: i64,
+    basis: TimeBasis,
+) -> AppResult<Vec<TaskRecord>> {
+    let tasks = load_tasks(conn)?;
+    let last_activated_by_task = load_last_activated_at(conn)?;
+    let tags_by_task = load_tags(conn)?;
+    let exclusive_seconds = replay_seconds(conn, window_start, now, basis)?;
+    let inclusive_seconds = derive_inclusive_seconds(&tasks, &exclusive_seconds);
+    let tracked_ancestors = derive_tracked_ancestors(&tasks, &exclusive_seconds);
+
+    Ok(tasks
+        .into_iter()
+        .map(|task| TaskRecord {
+            id: task.id.clone(),
+            parent_id: task.parent_id.clone(),
+            title: task.title,
+            status: task.status,
+            created_at: task.created_at,
+            completed_at: task.completed_at,
+            due_at: task.due_at,
+            reference_url: task.reference_url,
+            sort_order: task.sort_order,
+            last_activated_at: last_activated_by_task.get(&task.id).copied(),
+            tags: tags_by_task.get(&task.id).cloned().unwrap_or_default(),
+            inclusive_seconds: *inclusive_seconds.get(&task.id).unwrap_or(&0),
+            exclusive_seconds: *exclusive_seconds.get(&task.id).unwrap_or(&0),
+            inclusive_millis: None,
+            exclusive_millis: None,
+            has_tracked_descendants: tracked_ancestors.contains(&task.id),
+        })
+        .collect())
+}
+
+/// Completed tasks that `collapse_completed` folds into their parent: those with a live
+/// parent and no unfinished task anywhere in their subtree. Top-level tasks always stay.
+fn collect_collapsible_completed(records: &[TaskRecord]) -> HashSet<String> {
+    let parent_by_task = records
+        .iter()
+        .map(|record| (record.id.as_str(), record.parent_id.as_deref()))
+        .collect::<HashMap<_, _>>();
+    let mut open_subtree_ids = HashSet::new();
+    for record in records
+        .iter()
+        .filter(|record| record.completed_at.is_none())
+    {
+        let mut current = Some(record.id.as_str());
+        while let Some(task_id) = current {
+            if !open_subtree_ids.insert(task_id) {
+                break;
+            }
+            current = parent_by_task.get(task_id).copied().flatten();
+        }
+    }
+
+    records
+        .iter()
+        .filter(|record| record.completed_at.is_some())
+        .filter(|record| !open_subtree_ids.contains(record.id.as_str()))
+        .filter(|record| {
+            record
+                .parent_id
+                .as_deref()
+                .is_some_and(|parent_id| parent_by_task.contains_key(parent_id))
+        })
+        .map(|record| record.id.clone())
+        .collect()
+}
+
+/// Orders siblings by `sort` and emits each parent followed by its subtree, so children
+/// stay grouped under their parent. Tasks whose parent is not in `records` count as roots.
+fn sort_task_records(records: Vec<TaskRecord>, sort: &str) -> Vec<TaskRecord> {
+    let present = records
+        .iter()
+        .map(|record| record.id.clone())
+        .collect::<HashSet<_>>();
+    let mut children_by_parent: HashMap<Option<String>, Vec<TaskRecord>> = HashMap::new();
+    for record in records {
+        let parent_key = record
+            .parent_id
+            .clone()
+            .filter(|parent_id| present.contains(parent_id));
+        children_by_parent
+            .entry(parent_key)
+            .or_default()
+            .push(record);
+    }
+
+    let compare = |left: &TaskRecord, right: &TaskRecord| {
+        let primary = match sort {
+            "created_desc" => right.created_at.cmp(&left.created_at),
+            "title" => left.title.to_lowercase().cmp(&right.title.to_lowercase()),
+            "time_desc" => right.inclusive_seconds.cmp(&left.inclusive_seconds),
+            "recent_activity" => right.last_activated_at.cmp(&left.last_activated_at),
+            // Hand-ordered siblings first; the rest keep creation order after them.
+            "manual" => (left.sort_order.is_none(), left.sort_order)
+                .cmp(&(right.sort_order.is_none(), right.sort_order)),
+            _ => left.created_at.cmp(&right.created_at),
+        };
+        primary
+            .then_with(|| left.created_at.cmp(&right.created_at))
+            .then_with(|| left.id.cmp(&right.id))
+    };
+
+    let mut roots = children_by_parent.remove(&None).unwrap_or_default();
+    roots.sort_by(compare);
+    let mut ordered = Vec::with_capacity(present.len());
+    let mut stack = roots.into_iter().rev().collect::<Vec<_>>();
+    while let Some(record) = stack.pop() {
+        if let Some(mut children) = children_by_parent.remove(&Some(record.id.clone())) {
+            children.sort_by(compare);
+            stack.extend(children.into_iter().rev());
+        }
+        ordered.push(record);
+    }
+
+    // Only tasks caught in a parent cycle are left over; keep them rather than drop them.
+    let mut unreachable = children_by_parent
+        .into_values()
+        .flatten()
+        .collect::<Vec<_>>();
+    unreachable.sort_by(compare);
+    ordered.extend(unreachable);
+    ordered
+}
+
+fn build_tree_node(
+    task: TaskRecord,
+    children_by_parent: &mut HashMap<String, Vec<TaskRecord>>,
+    visited: &mut HashSet<String>,
+) -> AppResult<TaskTreeNode> {
+    if !visited.insert(task.id.clone()) {
+        return Err(conflict_error(format!(
+            "detected cycle while building task tree at {}",
+            task.id
+        )));
+    }
+
+    let children = children_by_parent
+        .remove(&task.id)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|child| build_tree_node(child, children_by_parent, visited))
+        .collect::<AppResult<Vec<_>>>()?;
+
+    Ok(TaskTreeNode { task, children })
+}
+
+fn load_overdue_tasks(
+    conn: &Connection,
+    after_ts: Option<i64>,
+    now: i64,
+) -> AppResult<Vec<OverdueTaskRecord>> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, title, due_at
+             FROM tasks
+             WHERE archived_at IS NULL
+               AND completed_at IS NULL
+               AND due_at IS NOT NULL
+               AND due_at <= ?1
+               AND (?2 IS NULL OR due_at > ?2)
+             ORDER BY due_at ASC, created_at ASC",
+        )
+        .map_err(to_error)?;
+
+    let rows = stmt
+        .query_map(params![now, after_ts], |row| {
+            let due_at: i64 = row.get(2)?;
+            Ok(OverdueTaskRecord {
+                task_id: row.get(0)?,
+                title: row.get(1)?,
+                due_at,
+                overdue_seconds: now - due_at,
+            })
+        })
+        .map_err(to_error)?;
+
+    rows.collect::<Result<Vec<_>, _>>().map_err(to_error)
+}
+
 fn load_tasks(conn: &Connection) -> AppResult<Vec<TaskRow>> {
     let mut stmt = conn
-        .prepare("SELECT id, parent_id, title, status, created_at FROM tasks WHERE archived_at IS NULL ORDER BY created_at ASC")
+        .prepare("SELECT id, parent_id, title, status, created_at, completed_at, due_at, reference_url, sort_order FROM tasks WHERE archived_at IS NULL ORDER BY created_at ASC")
         .map_err(to_error)?;
 
     let rows = stmt
@@ -926,6 +5418,10 @@ fn load_tasks(conn: &Connection) -> AppResult<Vec<TaskRow>> {
                 title: row.get(2)?,
                 status: row.get(3)?,
                 created_at: row.get(4)?,
+                completed_at: row.get(5)?,
+                due_at: row.get(6)?,
+                reference_url: row.get(7)?,
+                sort_order: row.get(8)?,
             })
         })
         .map_err(to_error)?;
@@ -936,7 +5432,8 @@ fn load_tasks(conn: &Connection) -> AppResult<Vec<TaskRow>> {
 fn load_tasks_for_reporting(conn: &Connection) -> AppResult<Vec<TaskRow>> {
     let mut stmt = conn
         .prepare(
-            "SELECT id, parent_id, title, status, created_at
+            "SELECT id, parent_id, title, status, created_at, completed_at, due_at, reference_url,
+                    sort_order
              FROM tasks
              ORDER BY created_at ASC",
         )
@@ -950,6 +5447,10 @@ fn load_tasks_for_reporting(conn: &Connection) -> AppResult<Vec<TaskRow>> {
                 title: row.get(2)?,
                 status: row.get(3)?,
                 created_at: row.get(4)?,
+                completed_at: row.get(5)?,
+                due_at: row.get(6)?,
+                reference_url: row.get(7)?,
+                sort_order: row.get(8)?,
             })
         })
         .map_err(to_error)?;
@@ -978,12 +5479,12 @@ fn load_last_activated_at(conn: &Connection) -> AppResult<HashMap<String, i64>>
     rows.collect::<Result<HashMap<_, _>, _>>().map_err(to_error)
 }
 
-fn load_tags(conn: &Connection) -> AppResult<HashMap<String, Vec<String>>> {
-    let mut tags_by_task: HashMap<String, Vec<String>> = HashMap::new();
+fn load_tags(conn: &Connection) -> AppResult<HashMap<String, Vec<TagRef>>> {
+    let mut tags_by_task: HashMap<String, Vec<TagRef>> = HashMap::new();
     let mut stmt = conn
         .prepare(
             "
-            SELECT tt.task_id, tg.name
+            SELECT tt.task_id, tg.name, tg.color
             FROM task_tags tt
             INNER JOIN tags tg ON tg.id = tt.tag_id
             INNER JOIN tasks t ON t.id = tt.task_id
@@ -995,21 +5496,153 @@ fn load_tags(conn: &Connection) -> AppResult<HashMap<String, Vec<String>>> {
 
     let rows = stmt
         .query_map([], |row| {
-            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+            Ok((
+                row.get::<_, String>(0)?,
+                TagRef {
+                    name: row.get(1)?,
+                    color: row.get(2)?,
+                },
+            ))
         })
         .map_err(to_error)?;
 
     for row in rows {
-        let (task_id, tag_name) = row.map_err(to_error)?;
-        tags_by_task.entry(task_id).or_default().push(tag_name);
+        let (task_id, tag) = row.map_err(to_error)?;
+        tags_by_task.entry(task_id).or_default().push(tag);
     }
 
     Ok(tags_by_task)
 }
 
+fn build_exported_node(
+    conn: &Connection,
+    task_id: &str,
+    include_archived: bool,
+    exclusive_seconds: Option<&HashMap<String, i64>>,
+    visited: &mut HashSet<String>,
+) -> AppResult<ExportedTaskNode> {
+    if !visited.insert(task_id.to_string()) {
+        return Err(conflict_error(format!(
+            "detected cycle while traversing task subtree at {task_id}"
+        )));
+    }
+
+    let (title, archived_at): (String, Option<i64>) = conn
+        .query_row(
+            "SELECT title, archived_at FROM tasks WHERE id = ?1",
+            params![task_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .map_err(to_error)?;
+
+    let tags = load_task_tag_names(conn, task_id)?;
+
+    let child_ids = {
+        let mut stmt = conn
+            .prepare(
+                "SELECT id
+                 FROM tasks
+                 WHERE parent_id = ?1 AND (?2 OR archived_at IS NULL)
+                 ORDER BY created_at ASC",
+            )
+            .map_err(to_error)?;
+        let rows = stmt
+            .query_map(params![task_id, include_archived], |row| {
+                row.get::<_, String>(0)
+            })
+            .map_err(to_error)?;
+        rows.collect::<Result<Vec<_>, _>>().map_err(to_error)?
+    };
+
+    let mut children = Vec::with_capacity(child_ids.len());
+    for child_id in child_ids {
+        children.push(build_exported_node(
+            conn,
+            &child_id,
+            include_archived,
+            exclusive_seconds,
+            visited,
+        )?);
+    }
+
+    let own_seconds = exclusive_seconds.map(|totals| totals.get(task_id).copied().unwrap_or(0));
+    let inclusive_seconds = own_seconds.map(|own| {
+        own + children
+            .iter()
+            .filter_map(|child| child.inclusive_seconds)
+            .sum::<i64>()
+    });
+
+    Ok(ExportedTaskNode {
+        title,
+        tags,
+        archived: archived_at.is_some(),
+        inclusive_seconds,
+        exclusive_seconds: own_seconds,
+        children,
+    })
+}
+
+fn load_task_tag_names(conn: &Connection, task_id: &str) -> AppResult<Vec<String>> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT tg.name
+             FROM task_tags tt
+             INNER JOIN tags tg ON tg.id = tt.tag_id
+             WHERE tt.task_id = ?1
+             ORDER BY tg.name ASC",
+        )
+        .map_err(to_error)?;
+    let rows = stmt
+        .query_map(params![task_id], |row| row.get::<_, String>(0))
+        .map_err(to_error)?;
+    rows.collect::<Result<Vec<_>, _>>().map_err(to_error)
+}
+
+fn load_task_tag_refs(conn: &Connection, task_id: &str) -> AppResult<Vec<TagRef>> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT tg.name, tg.color
+             FROM task_tags tt
+             INNER JOIN tags tg ON tg.id = tt.tag_id
+             WHERE tt.task_id = ?1
+             ORDER BY tg.name ASC",
+        )
+        .map_err(to_error)?;
+    let rows = stmt
+        .query_map(params![task_id], |row| {
+            Ok(TagRef {
+                name: row.get(0)?,
+                color: row.get(1)?,
+            })
+        })
+        .map_err(to_error)?;
+    rows.collect::<Result<Vec<_>, _>>().map_err(to_error)
+}
+
+fn load_task_ids_with_tag(conn: &Connection, tag: &str) -> AppResult<HashSet<String>> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT tt.task_id
+             FROM task_tags tt
+             INNER JOIN tags tg ON tg.id = tt.tag_id
+             WHERE (?1 IS NULL AND lower(tg.name) = lower(?3))
+                OR (tg.tag_key = ?1 AND lower(tg.tag_value) = lower(?2))",
+        )
+        .map_err(to_error)?;
+
+    let (key, value) = split_structured_tag(tag).unzip();
+    let rows = stmt
+        .query_map(params![key, value, tag], |row| row.get::<_, String>(0))
+        .map_err(to_error)?;
+
+    rows.collect::<Result<HashSet<_>, _>>().map_err(to_error)
+}
+
 fn load_latest_pending_rest_suggestion(
     conn: &Connection,
 ) -> AppResult<Option<RestSuggestionRecord>> {
+    let expiry_cutoff = rest_suggestion_expiry_cutoff(conn, now_ts())?.unwrap_or(i64::MIN);
     let row: Option<(
         i64,
         String,
@@ -1021,15 +5654,18 @@ fn load_latest_pending_rest_suggestion(
         String,
         String,
         i64,
+        Option<String>,
     )> = conn
         .query_row(
-            "SELECT id, trigger_type, task_id, focus_seconds, switch_count_30m, deviation_ratio,
-                    suggested_minutes, reasons, status, created_at
-             FROM rest_suggestions
-             WHERE status = ?1 AND suggested_minutes > 0
-             ORDER BY created_at DESC, id DESC
+            "SELECT rs.id, rs.trigger_type, rs.task_id, rs.focus_seconds, rs.switch_count_30m,
+                    rs.deviation_ratio, rs.suggested_minutes, rs.reasons, rs.status, rs.created_at,
+                    t.title
+             FROM rest_suggestions rs
+             LEFT JOIN tasks t ON t.id = rs.task_id AND t.archived_at IS NULL
+             WHERE rs.status = ?1 AND rs.suggested_minutes > 0 AND rs.created_at >= ?2
+             ORDER BY rs.created_at DESC, rs.id DESC
              LIMIT 1",
-            params![REST_STATUS_PENDING],
+            params![REST_STATUS_PENDING, expiry_cutoff],
             |row| {
                 Ok((
                     row.get(0)?,
@@ -1042,6 +5678,7 @@ fn load_latest_pending_rest_suggestion(
                     row.get(7)?,
                     row.get(8)?,
                     row.get(9)?,
+                    row.get(10)?,
                 ))
             },
         )
@@ -1060,6 +5697,7 @@ fn load_latest_pending_rest_suggestion(
             reasons,
             status,
             created_at,
+            task_title,
         )) => {
             let reasons = serde_json::from_str::<Vec<String>>(&reasons)
                 .unwrap_or_else(|_| vec!["unable to parse rule reasons".to_string()]);
@@ -1067,6 +5705,7 @@ fn load_latest_pending_rest_suggestion(
                 id,
                 trigger_type,
                 task_id,
+                task_title,
                 focus_seconds,
                 switch_count_30m,
                 deviation_ratio,
@@ -1081,21 +5720,25 @@ fn load_latest_pending_rest_suggestion(
 }
 
 fn load_pending_notifications(conn: &Connection) -> AppResult<Vec<NotificationRecord>> {
+    let expiry_cutoff = rest_suggestion_expiry_cutoff(conn, now_ts())?.unwrap_or(i64::MIN);
     let mut stmt = conn
         .prepare(
             "SELECT n.id, n.kind, n.level, n.status, n.title, n.message, n.detail, n.created_at,
                     rs.id, rs.trigger_type, rs.task_id, rs.focus_seconds, rs.switch_count_30m,
-                    rs.deviation_ratio, rs.suggested_minutes, rs.reasons, rs.status, rs.created_at
+                    rs.deviation_ratio, rs.suggested_minutes, rs.reasons, rs.status, rs.created_at,
+                    t.title
              FROM notifications n
              LEFT JOIN rest_suggestions rs ON rs.id = n.rest_suggestion_id
+             LEFT JOIN tasks t ON t.id = rs.task_id AND t.archived_at IS NULL
              WHERE n.status = ?1
+               AND (rs.id IS NULL OR rs.created_at >= ?2)
              ORDER BY n.created_at DESC, n.id DESC
              LIMIT 20",
         )
         .map_err(to_error)?;
 
     let rows = stmt
-        .query_map(params![REST_STATUS_PENDING], |row| {
+        .query_map(params![REST_STATUS_PENDING, expiry_cutoff], |row| {
             let rest_id: Option<i64> = row.get(8)?;
             let rest_task_id: Option<String> = row.get(10)?;
             let rest_focus_seconds: Option<i64> = row.get(11)?;
@@ -1105,6 +5748,7 @@ fn load_pending_notifications(conn: &Connection) -> AppResult<Vec<NotificationRe
             let rest_reasons_raw: Option<String> = row.get(15)?;
             let rest_status: Option<String> = row.get(16)?;
             let rest_created_at: Option<i64> = row.get(17)?;
+            let rest_task_title: Option<String> = row.get(18)?;
 
             let rest_suggestion = match (
                 rest_id,
@@ -1134,6 +5778,7 @@ fn load_pending_notifications(conn: &Connection) -> AppResult<Vec<NotificationRe
                         id,
                         trigger_type,
                         task_id: rest_task_id,
+                        task_title: rest_task_title,
                         focus_seconds,
                         switch_count_30m,
                         deviation_ratio,
@@ -1168,13 +5813,39 @@ fn replay_exclusive_seconds(
     window_start: Option<i64>,
     window_end: i64,
 ) -> AppResult<HashMap<String, i64>> {
-    let intervals = collect_focus_intervals(conn, window_start, window_end)?;
+    replay_seconds(conn, window_start, window_end, TimeBasis::Active)
+}
+
+fn replay_seconds(
+    conn: &Connection,
+    window_start: Option<i64>,
+    window_end: i64,
+    basis: TimeBasis,
+) -> AppResult<HashMap<String, i64>> {
+    replay_totals(conn, window_start, window_end, basis, Precision::Seconds)
+}
+
+/// Exclusive totals per task in `precision` units; the window bounds use the same unit.
+fn replay_totals(
+    conn: &Connection,
+    window_start: Option<i64>,
+    window_end: i64,
+    basis: TimeBasis,
+    precision: Precision,
+) -> AppResult<HashMap<String, i64>> {
+    let intervals = match basis {
+        TimeBasis::Active => collect_focus_intervals_in(conn, window_start, window_end, precision)?,
+        TimeBasis::Engaged => {
+            collect_engaged_intervals_in(conn, window_start, window_end, precision)?
+        }
+    };
     let mut exclusive: HashMap<String, i64> = HashMap::new();
     for interval in intervals {
         *exclusive.entry(interval.task_id).or_insert(0) += interval.end_ts - interval.start_ts;
     }
-    for adjustment in collect_focus_adjustments(conn, window_start, window_end)? {
-        *exclusive.entry(adjustment.task_id).or_insert(0) += adjustment.delta_seconds;
+    for adjustment in collect_focus_adjustments_in(conn, window_start, window_end, precision)? {
+        *exclusive.entry(adjustment.task_id).or_insert(0) +=
+            adjustment.delta_seconds * precision.units_per_second();
     }
     Ok(exclusive)
 }
@@ -1183,10 +5854,24 @@ fn collect_focus_intervals(
     conn: &Connection,
     window_start: Option<i64>,
     window_end: i64,
+) -> AppResult<Vec<FocusInterval>> {
+    collect_focus_intervals_in(conn, window_start, window_end, Precision::Seconds)
+}
+
+fn collect_focus_intervals_in(
+    conn: &Connection,
+    window_start: Option<i64>,
+    window_end: i64,
+    precision: Precision,
 ) -> AppResult<Vec<FocusInterval>> {
     let mut stmt = conn
-        .prepare("SELECT task_id, event_type, ts FROM time_events ORDER BY ts ASC, id ASC")
+        .prepare(&format!(
+            "SELECT task_id, event_type, {} FROM time_events ORDER BY ts ASC, id ASC",
+            precision.ts_column()
+        ))
         .map_err(to_error)?;
+    let min_session_seconds =
+        read_setting_i64(conn, SETTING_MIN_SESSION_SECONDS, 0)? * precision.units_per_second();
 
     let rows = stmt
         .query_map([], |row| {
@@ -1208,8 +5893,19 @@ fn collect_focus_intervals(
                 running_since.entry(task_id).or_insert(ts);
             }
             EVENT_PAUSE | EVENT_STOP => {
+                // Closed sessions shorter than the minimum are treated as accidental clicks;
+                // the open interval below is always kept so a fresh start keeps counting.
                 if let Some(start) = running_since.remove(&task_id) {
-                    push_interval(&mut intervals, &task_id, start, ts, window_start, window_end);
+                    if ts - start >= min_session_seconds {
+                        push_interval(
+                            &mut intervals,
+                            &task_id,
+                            start,
+                            ts,
+                            window_start,
+                            window_end,
+                        );
+                    }
                 }
             }
             _ => {}
@@ -1230,18 +5926,157 @@ fn collect_focus_intervals(
     Ok(intervals)
 }
 
+/// Like `collect_focus_intervals` but a session runs from its opening start/resume to its
+/// stop; pauses do not close it.
+fn collect_engaged_intervals(
+    conn: &Connection,
+    window_start: Option<i64>,
+    window_end: i64,
+) -> AppResult<Vec<FocusInterval>> {
+    collect_engaged_intervals_in(conn, window_start, window_end, Precision::Seconds)
+}
+
+fn collect_engaged_intervals_in(
+    conn: &Connection,
+    window_start: Option<i64>,
+    window_end: i64,
+    precision: Precision,
+) -> AppResult<Vec<FocusInterval>> {
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT task_id, event_type, {}
+             FROM time_events
+             WHERE event_type IN ('start', 'resume', 'stop')
+             ORDER BY ts ASC, id ASC",
+            precision.ts_column()
+        ))
+        .map_err(to_error)?;
+    let min_session_seconds =
+        read_setting_i64(conn, SETTING_MIN_SESSION_SECONDS, 0)? * precision.units_per_second();
+
+    let rows = stmt
+        .query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, i64>(2)?,
+            ))
+        })
+        .map_err(to_error)?;
+
+    let mut engaged_since: HashMap<String, i64> = HashMap::new();
+    let mut intervals = Vec::new();
+
+    for row in rows {
+        let (task_id, event_type, ts) = row.map_err(to_error)?;
+        if event_type == EVENT_STOP {
+            if let Some(start) = engaged_since.remove(&task_id) {
+                if ts - start >= min_session_seconds {
+                    push_interval(
+                        &mut intervals,
+                        &task_id,
+                        start,
+                        ts,
+                        window_start,
+                        window_end,
+                    );
+                }
+            }
+        } else {
+            engaged_since.entry(task_id).or_insert(ts);
+        }
+    }
+
+    for (task_id, start) in engaged_since {
+        push_interval(
+            &mut intervals,
+            &task_id,
+            start,
+            window_end,
+            window_start,
+            window_end,
+        );
+    }
+
+    Ok(intervals)
+}
+
+fn collect_closed_sessions(
+    conn: &Connection,
+    window_start: i64,
+    window_end: i64,
+) -> AppResult<Vec<FocusSession>> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT task_id, event_type, ts
+             FROM time_events
+             WHERE event_type IN ('start', 'resume', 'pause', 'stop')
+               AND ts <= ?1
+             ORDER BY ts ASC, id ASC",
+        )
+        .map_err(to_error)?;
+
+    let rows = stmt
+        .query_map(params![window_end], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, i64>(2)?,
+            ))
+        })
+        .map_err(to_error)?;
+    let min_session_seconds = read_setting_i64(conn, SETTING_MIN_SESSION_SECONDS, 0)?;
+
+    let mut running_since: HashMap<String, i64> = HashMap::new();
+    let mut sessions = Vec::new();
+
+    for row in rows {
+        let (task_id, event_type, ts) = row.map_err(to_error)?;
+        match event_type.as_str() {
+            EVENT_START | EVENT_RESUME => {
+                running_since.entry(task_id).or_insert(ts);
+            }
+            EVENT_PAUSE | EVENT_STOP => {
+                if let Some(start) = running_since.remove(&task_id) {
+                    if start >= window_start && ts > start && ts - start >= min_session_seconds {
+                        sessions.push(FocusSession {
+                            task_id,
+                            start_ts: start,
+                            end_ts: ts,
+                        });
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    Ok(sessions)
+}
+
 fn collect_focus_adjustments(
     conn: &Connection,
     window_start: Option<i64>,
     window_end: i64,
+) -> AppResult<Vec<FocusAdjustment>> {
+    collect_focus_adjustments_in(conn, window_start, window_end, Precision::Seconds)
+}
+
+/// `ts` and the window are in `precision` units; `delta_seconds` stays in seconds.
+fn collect_focus_adjustments_in(
+    conn: &Connection,
+    window_start: Option<i64>,
+    window_end: i64,
+    precision: Precision,
 ) -> AppResult<Vec<FocusAdjustment>> {
     let mut stmt = conn
-        .prepare(
-            "SELECT task_id, ts, payload
+        .prepare(&format!(
+            "SELECT task_id, {}, payload
              FROM time_events
              WHERE event_type = ?1
              ORDER BY ts ASC, id ASC",
-        )
+            precision.ts_column()
+        ))
         .map_err(to_error)?;
 
     let rows = stmt
@@ -1290,6 +6125,8 @@ fn push_interval(
             task_id: task_id.to_string(),
             start_ts: clipped_start,
             end_ts: clipped_end,
+            unclipped_start_ts: start,
+            unclipped_end_ts: end,
         });
     }
 }
@@ -1323,6 +6160,32 @@ fn derive_inclusive_seconds(
     memo
 }
 
+/// Returns the ids of tasks with at least one descendant that has nonzero exclusive time.
+fn derive_tracked_ancestors(
+    tasks: &[TaskRow],
+    exclusive: &HashMap<String, i64>,
+) -> HashSet<String> {
+    let parent_by_task = tasks
+        .iter()
+        .map(|task| (task.id.as_str(), task.parent_id.as_deref()))
+        .collect::<HashMap<_, _>>();
+
+    let mut tracked = HashSet::new();
+    for task in tasks {
+        if exclusive.get(&task.id).copied().unwrap_or(0) == 0 {
+            continue;
+        }
+        let mut current = task.parent_id.as_deref();
+        while let Some(id) = current {
+            if !tracked.insert(id.to_string()) {
+                break;
+            }
+            current = parent_by_task.get(id).copied().flatten();
+        }
+    }
+    tracked
+}
+
 fn compute_inclusive(
     task_id: &str,
     children_by_parent: &HashMap<String, Vec<String>>,
@@ -1350,18 +6213,79 @@ fn compute_inclusive(
     total
 }
 
+fn ensure_reparent_allowed(
+    conn: &Connection,
+    task_id: &str,
+    new_parent_id: Option<&str>,
+) -> AppResult<()> {
+    let subtree_ids = collect_subtree_ids(conn, task_id)?;
+    if let Some((_active_id, active_title, active_status)) =
+        find_active_in_subtree(conn, &subtree_ids)?
+    {
+        return Err(conflict_error(format!(
+            "cannot reparent while task \"{active_title}\" is {active_status}; stop or pause transitions first"
+        )));
+    }
+
+    if let Some(parent_id) = new_parent_id {
+        ensure_task_exists(conn, parent_id)?;
+        if subtree_ids.iter().any(|id| id == parent_id) {
+            return Err(validation_error(
+                "cannot reparent task under itself or its descendants",
+            ));
+        }
+        ensure_ancestor_chain_valid(conn, parent_id, task_id)?;
+    }
+
+    Ok(())
+}
+
+fn write_reparent(
+    tx: &Transaction<'_>,
+    task_id: &str,
+    old_parent_id: Option<String>,
+    new_parent_id: Option<String>,
+    ts: i64,
+) -> AppResult<()> {
+    tx.execute(
+        "UPDATE tasks SET parent_id = ?1, sort_order = NULL WHERE id = ?2 AND archived_at IS NULL",
+        params![new_parent_id, task_id],
+    )
+    .map_err(to_error)?;
+    append_event(
+        tx,
+        task_id,
+        EVENT_REPARENT,
+        ts,
+        Some(json!({
+            "old_parent_id": old_parent_id,
+            "new_parent_id": new_parent_id
+        })),
+    )
+}
+
 fn collect_subtree_ids(conn: &Connection, root_task_id: &str) -> AppResult<Vec<String>> {
+    Ok(collect_subtree_ids_with_depth(conn, root_task_id)?
+        .into_iter()
+        .map(|(task_id, _)| task_id)
+        .collect())
+}
+
+fn collect_subtree_ids_with_depth(
+    conn: &Connection,
+    root_task_id: &str,
+) -> AppResult<Vec<(String, usize)>> {
     let mut result = Vec::new();
-    let mut stack = vec![root_task_id.to_string()];
+    let mut stack = vec![(root_task_id.to_string(), 0)];
     let mut visited = HashSet::new();
 
-    while let Some(task_id) = stack.pop() {
+    while let Some((task_id, depth)) = stack.pop() {
         if !visited.insert(task_id.clone()) {
             return Err(conflict_error(format!(
                 "detected cycle while traversing task subtree at {task_id}"
             )));
         }
-        result.push(task_id.clone());
+        result.push((task_id.clone(), depth));
 
         let mut stmt = conn
             .prepare(
@@ -1377,7 +6301,7 @@ fn collect_subtree_ids(conn: &Connection, root_task_id: &str) -> AppResult<Vec<S
             .map_err(to_error)?;
 
         for row in rows {
-            stack.push(row.map_err(to_error)?);
+            stack.push((row.map_err(to_error)?, depth + 1));
         }
     }
 
@@ -1412,24 +6336,202 @@ fn expand_unique_subtree_ids(
     Ok(expanded)
 }
 
+/// Archives `task_ids` as one batch. Each archive event records the batch id and the
+/// topmost task of the batch above it, so a later restore can tell batches apart.
 fn archive_task_ids(tx: &Transaction<'_>, task_ids: &[String], archived_at: i64) -> AppResult<()> {
+    let batch_id = Uuid::new_v4().to_string();
+    for task_id in task_ids {
+        let root_task_id = find_batch_root(tx, task_id, task_ids)?;
+        let updated = tx
+            .execute(
+                "UPDATE tasks SET archived_at = ?1 WHERE id = ?2 AND archived_at IS NULL",
+                params![archived_at, task_id],
+            )
+            .map_err(to_error)?;
+        if updated > 0 {
+            append_event(
+                tx,
+                task_id,
+                EVENT_ARCHIVE,
+                archived_at,
+                Some(json!({ "batch_id": batch_id, "root_task_id": root_task_id })),
+            )?;
+        }
+    }
+    Ok(())
+}
+
+/// Walks up from `task_id` while the parent is still part of `batch_ids`.
+fn find_batch_root(conn: &Connection, task_id: &str, batch_ids: &[String]) -> AppResult<String> {
+    let mut current = task_id.to_string();
+    let mut visited = HashSet::new();
+    while visited.insert(current.clone()) {
+        let parent_id: Option<String> = conn
+            .query_row(
+                "SELECT parent_id FROM tasks WHERE id = ?1",
+                params![current],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(to_error)?
+            .flatten();
+        match parent_id {
+            Some(parent_id) if batch_ids.contains(&parent_id) => current = parent_id,
+            _ => break,
+        }
+    }
+    Ok(current)
+}
+
+fn latest_archive_batch_id(conn: &Connection, task_id: &str) -> AppResult<Option<String>> {
+    let payload: Option<Option<String>> = conn
+        .query_row(
+            "SELECT payload
+             FROM time_events
+             WHERE task_id = ?1 AND event_type = ?2
+             ORDER BY ts DESC, id DESC
+             LIMIT 1",
+            params![task_id, EVENT_ARCHIVE],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(to_error)?;
+    Ok(payload
+        .flatten()
+        .and_then(|raw| serde_json::from_str::<serde_json::Value>(&raw).ok())
+        .and_then(|value| {
+            value
+                .get("batch_id")
+                .and_then(|raw| raw.as_str())
+                .map(str::to_owned)
+        }))
+}
+
+fn load_archived_at(conn: &Connection, task_id: &str) -> AppResult<Option<i64>> {
+    conn.query_row(
+        "SELECT archived_at FROM tasks WHERE id = ?1 LIMIT 1",
+        params![task_id],
+        |row| row.get(0),
+    )
+    .optional()
+    .map_err(to_error)?
+    .ok_or_else(|| not_found_error(format!("task {task_id} not found")))
+}
+
+fn restore_task_ids(tx: &Transaction<'_>, task_ids: &[String], restored_at: i64) -> AppResult<()> {
+    let batch_id = Uuid::new_v4().to_string();
+    for task_id in task_ids {
+        let root_task_id = find_batch_root(tx, task_id, task_ids)?;
+        // The root's old siblings may have been reordered while it was archived, so its
+        // pinned position no longer means anything; descendants keep theirs.
+        let updated = tx
+            .execute(
+                "UPDATE tasks
+                 SET archived_at = NULL,
+                     sort_order = CASE WHEN id = ?2 THEN NULL ELSE sort_order END
+                 WHERE id = ?1 AND archived_at IS NOT NULL",
+                params![task_id, root_task_id],
+            )
+            .map_err(to_error)?;
+        if updated > 0 {
+            append_event(
+                tx,
+                task_id,
+                EVENT_RESTORE,
+                restored_at,
+                Some(json!({ "batch_id": batch_id, "root_task_id": root_task_id })),
+            )?;
+        }
+    }
+    Ok(())
+}
+
+/// Collects the archived root plus the descendants that were archived alongside it, so a
+/// restore brings back exactly what one archive call removed. Descendants from another
+/// archive batch in the same second are left alone; events from before batches were
+/// recorded fall back to matching `archived_at` only.
+fn collect_archived_subtree_ids(
+    conn: &Connection,
+    root_task_id: &str,
+    archived_at: i64,
+) -> AppResult<Vec<String>> {
+    let root_batch_id = latest_archive_batch_id(conn, root_task_id)?;
+    let mut result = Vec::new();
+    let mut stack = vec![root_task_id.to_string()];
+    let mut visited = HashSet::new();
+
+    while let Some(task_id) = stack.pop() {
+        if !visited.insert(task_id.clone()) {
+            return Err(conflict_error(format!(
+                "detected cycle while traversing task subtree at {task_id}"
+            )));
+        }
+        result.push(task_id.clone());
+
+        let mut stmt = conn
+            .prepare(
+                "SELECT id
+                 FROM tasks
+                 WHERE parent_id = ?1 AND archived_at = ?2
+                 ORDER BY created_at ASC",
+            )
+            .map_err(to_error)?;
+
+        let rows = stmt
+            .query_map(params![task_id, archived_at], |row| row.get::<_, String>(0))
+            .map_err(to_error)?;
+
+        for row in rows {
+            let child_id = row.map_err(to_error)?;
+            if let Some(root_batch_id) = &root_batch_id {
+                let child_batch_id = latest_archive_batch_id(conn, &child_id)?;
+                if child_batch_id.is_some_and(|batch_id| &batch_id != root_batch_id) {
+                    continue;
+                }
+            }
+            stack.push(child_id);
+        }
+    }
+
+    Ok(result)
+}
+
+/// Deletes the tasks with their events and leaves a tombstone per task, stamped with the
+/// highest event id issued so far, so `get_overview_since` can still report the removal.
+fn hard_delete_task_ids(
+    tx: &Transaction<'_>,
+    task_ids: &[String],
+    deleted_at: i64,
+) -> AppResult<()> {
+    let last_event_id: i64 = tx
+        .query_row(
+            "SELECT COALESCE(MAX(seq), 0) FROM sqlite_sequence WHERE name = 'time_events'",
+            [],
+            |row| row.get(0),
+        )
+        .map_err(to_error)?;
     for task_id in task_ids {
         tx.execute(
-            "UPDATE tasks SET archived_at = ?1 WHERE id = ?2 AND archived_at IS NULL",
-            params![archived_at, task_id],
+            "INSERT OR REPLACE INTO deleted_tasks (task_id, deleted_at, last_event_id)
+             VALUES (?1, ?2, ?3)",
+            params![task_id, deleted_at, last_event_id],
         )
         .map_err(to_error)?;
-    }
-    Ok(())
-}
-
-fn hard_delete_task_ids(tx: &Transaction<'_>, task_ids: &[String]) -> AppResult<()> {
-    for task_id in task_ids {
         tx.execute(
             "DELETE FROM rest_suggestions WHERE task_id = ?1",
             params![task_id],
         )
         .map_err(to_error)?;
+        tx.execute(
+            "DELETE FROM task_baselines WHERE task_id = ?1",
+            params![task_id],
+        )
+        .map_err(to_error)?;
+        tx.execute(
+            "DELETE FROM overview_snapshots WHERE task_id = ?1",
+            params![task_id],
+        )
+        .map_err(to_error)?;
         tx.execute(
             "DELETE FROM time_events WHERE task_id = ?1",
             params![task_id],
@@ -1480,7 +6582,32 @@ fn ensure_ancestor_chain_valid(
     new_parent_id: &str,
     blocked_task_id: &str,
 ) -> AppResult<()> {
-    let mut current_id = Some(new_parent_id.to_string());
+    walk_ancestor_chain(conn, new_parent_id, |task_id| {
+        if task_id == blocked_task_id {
+            return Err(validation_error(
+                "cannot reparent task under itself or its descendants",
+            ));
+        }
+        Ok(())
+    })
+}
+
+/// Returns `task_id` followed by its ancestors up to the root.
+fn load_ancestor_chain(conn: &Connection, task_id: &str) -> AppResult<Vec<String>> {
+    let mut chain = Vec::new();
+    walk_ancestor_chain(conn, task_id, |id| {
+        chain.push(id.to_string());
+        Ok(())
+    })?;
+    Ok(chain)
+}
+
+fn walk_ancestor_chain(
+    conn: &Connection,
+    start_id: &str,
+    mut visit: impl FnMut(&str) -> AppResult<()>,
+) -> AppResult<()> {
+    let mut current_id = Some(start_id.to_string());
     let mut visited = HashSet::new();
 
     while let Some(task_id) = current_id {
@@ -1490,11 +6617,7 @@ fn ensure_ancestor_chain_valid(
             )));
         }
 
-        if task_id == blocked_task_id {
-            return Err(validation_error(
-                "cannot reparent task under itself or its descendants",
-            ));
-        }
+        visit(&task_id)?;
 
         let parent: Option<Option<String>> = conn
             .query_row(
@@ -1553,10 +6676,15 @@ fn create_rest_suggestion(
     } else {
         0.0
     };
-    let (suggested_minutes, reasons) =
-        evaluate_rest_rules(focus_seconds, switch_count_30m, deviation_ratio);
+    let minute_tiers = read_rest_minute_tiers(conn)?;
+    let (suggested_minutes, reasons) = evaluate_rest_rules(
+        focus_seconds,
+        switch_count_30m,
+        deviation_ratio,
+        minute_tiers,
+    );
 
-    let tx = conn.transaction().map_err(to_error)?;
+    let tx = begin_transaction(conn)?;
     insert_rest_suggestion(
         &tx,
         trigger_type,
@@ -1648,6 +6776,41 @@ fn insert_rest_suggestion(
     Ok(())
 }
 
+fn rest_suggestion_expiry_cutoff(conn: &Connection, now: i64) -> AppResult<Option<i64>> {
+    let ttl_seconds = read_setting_i64(
+        conn,
+        SETTING_REST_SUGGESTION_TTL_SECONDS,
+        DEFAULT_REST_SUGGESTION_TTL_SECONDS,
+    )?;
+    if ttl_seconds <= 0 {
+        return Ok(None);
+    }
+    Ok(Some(now - ttl_seconds))
+}
+
+fn auto_resumed_child_id(payload: Option<&str>) -> Option<String> {
+    let value = serde_json::from_str::<serde_json::Value>(payload?).ok()?;
+    if value.get("reason").and_then(|reason| reason.as_str()) != Some("child_stopped") {
+        return None;
+    }
+    value
+        .get("child_id")
+        .and_then(|child_id| child_id.as_str())
+        .map(str::to_owned)
+}
+
+/// Seconds since the task's latest start/resume, i.e. the length of its open session.
+fn current_focus_seconds(conn: &Connection, task_id: &str, now: i64) -> AppResult<i64> {
+    let since: Option<i64> = conn
+        .query_row(
+            "SELECT MAX(ts) FROM time_events WHERE task_id = ?1 AND event_type IN ('start', 'resume')",
+            params![task_id],
+            |row| row.get(0),
+        )
+        .map_err(to_error)?;
+    Ok(since.map_or(0, |ts| (now - ts).max(0)))
+}
+
 fn latest_closed_session_duration(
     conn: &Connection,
     task_id: &str,
@@ -1683,7 +6846,10 @@ fn completed_session_durations(
         })
         .map_err(to_error)?;
 
+    let min_session_seconds = read_setting_i64(conn, SETTING_MIN_SESSION_SECONDS, 0)?;
     let mut running_since: Option<i64> = None;
+    // Focus before a sleep-split pause; the session carries on after the wake resume.
+    let mut split_seconds: Option<i64> = None;
     let mut sessions = Vec::new();
     let mut pending_adjustment = 0i64;
 
@@ -1693,21 +6859,35 @@ fn completed_session_durations(
             EVENT_START | EVENT_RESUME => {
                 if running_since.is_none() {
                     running_since = Some(ts);
-                    pending_adjustment = 0;
+                    if split_seconds.is_none() {
+                        pending_adjustment = 0;
+                    }
                 }
             }
             EVENT_PAUSE | EVENT_STOP => {
-                if let Some(start) = running_since.take() {
-                    sessions.push((ts - start + pending_adjustment).max(0));
-                    pending_adjustment = 0;
+                let seconds = match running_since.take() {
+                    Some(start) => ts - start + split_seconds.take().unwrap_or(0),
+                    None => match split_seconds.take() {
+                        Some(seconds) => seconds,
+                        None => continue,
+                    },
+                };
+                let (_, reason) = parse_event_payload(payload.as_deref());
+                if event_type == EVENT_PAUSE && reason.as_deref() == Some(PAUSE_REASON_SLEEP) {
+                    split_seconds = Some(seconds);
+                    continue;
                 }
+                if seconds >= min_session_seconds {
+                    sessions.push((seconds + pending_adjustment).max(0));
+                }
+                pending_adjustment = 0;
             }
             EVENT_ADJUST => {
                 let delta_seconds = parse_adjustment_delta(payload.as_deref());
                 if delta_seconds == 0 {
                     continue;
                 }
-                if running_since.is_some() {
+                if running_since.is_some() || split_seconds.is_some() {
                     pending_adjustment += delta_seconds;
                 } else if let Some(last_session) = sessions.last_mut() {
                     *last_session = (*last_session + delta_seconds).max(0);
@@ -1744,6 +6924,7 @@ fn task_total_focus_seconds(conn: &Connection, task_id: &str, until_ts: i64) ->
         })
         .map_err(to_error)?;
 
+    let min_session_seconds = read_setting_i64(conn, SETTING_MIN_SESSION_SECONDS, 0)?;
     let mut running_since: Option<i64> = None;
     let mut pending_adjustment = 0i64;
     let mut total_focus_seconds = 0i64;
@@ -1758,8 +6939,14 @@ fn task_total_focus_seconds(conn: &Connection, task_id: &str, until_ts: i64) ->
                 }
             }
             EVENT_PAUSE | EVENT_STOP => {
+                // Mirrors the global replay: a session below the minimum is dropped, but
+                // adjustments made during it still count.
                 if let Some(start) = running_since.take() {
-                    total_focus_seconds += (ts - start + pending_adjustment).max(0);
+                    if ts - start >= min_session_seconds {
+                        total_focus_seconds += (ts - start + pending_adjustment).max(0);
+                    } else {
+                        total_focus_seconds = (total_focus_seconds + pending_adjustment).max(0);
+                    }
                     pending_adjustment = 0;
                 }
             }
@@ -1789,12 +6976,19 @@ fn parse_adjustment_delta(payload: Option<&str>) -> i64 {
         .unwrap_or(0)
 }
 
+/// Counts changes of task between consecutive start/resume events in the window. Tasks
+/// tagged with one of the `rest_ignored_tags` are skipped, so dipping into them and back
+/// is not a switch.
+/// Counts focus changes between tasks in the window. Interruptions count as switches
+/// too when `count_interruptions_as_switches` is on.
 fn count_task_switches(conn: &Connection, window_start: i64, window_end: i64) -> AppResult<i64> {
+    let ignored_task_ids = load_rest_ignored_task_ids(conn)?;
+    let include_interruptions = read_setting_bool(conn, SETTING_COUNT_INTERRUPTIONS_AS_SWITCHES)?;
     let mut stmt = conn
         .prepare(
-            "SELECT task_id
+            "SELECT task_id, event_type
              FROM time_events
-             WHERE event_type IN ('start', 'resume')
+             WHERE event_type IN ('start', 'resume', 'interruption')
                AND ts >= ?1 AND ts <= ?2
              ORDER BY ts ASC, id ASC",
         )
@@ -1802,7 +6996,7 @@ fn count_task_switches(conn: &Connection, window_start: i64, window_end: i64) ->
 
     let rows = stmt
         .query_map(params![window_start, window_end], |row| {
-            row.get::<_, String>(0)
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
         })
         .map_err(to_error)?;
 
@@ -1810,7 +7004,16 @@ fn count_task_switches(conn: &Connection, window_start: i64, window_end: i64) ->
     let mut switches = 0i64;
 
     for row in rows {
-        let task_id = row.map_err(to_error)?;
+        let (task_id, event_type) = row.map_err(to_error)?;
+        if ignored_task_ids.contains(&task_id) {
+            continue;
+        }
+        if event_type == EVENT_INTERRUPTION {
+            if include_interruptions {
+                switches += 1;
+            }
+            continue;
+        }
         if let Some(previous) = &previous_task_id {
             if previous != &task_id {
                 switches += 1;
@@ -1822,6 +7025,49 @@ fn count_task_switches(conn: &Connection, window_start: i64, window_end: i64) ->
     Ok(switches)
 }
 
+fn count_interruptions(conn: &Connection, window_start: i64, window_end: i64) -> AppResult<i64> {
+    conn.query_row(
+        "SELECT COUNT(*)
+         FROM time_events
+         WHERE event_type = ?1 AND ts >= ?2 AND ts <= ?3",
+        params![EVENT_INTERRUPTION, window_start, window_end],
+        |row| row.get(0),
+    )
+    .map_err(to_error)
+}
+
+fn load_rest_ignored_task_ids(conn: &Connection) -> AppResult<HashSet<String>> {
+    let ignored_tags = read_rest_ignored_tags(conn)?
+        .into_iter()
+        .map(|tag| tag.to_lowercase())
+        .collect::<HashSet<_>>();
+    if ignored_tags.is_empty() {
+        return Ok(HashSet::new());
+    }
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT tt.task_id, tg.name
+             FROM task_tags tt
+             JOIN tags tg ON tg.id = tt.tag_id",
+        )
+        .map_err(to_error)?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })
+        .map_err(to_error)?;
+
+    let mut task_ids = HashSet::new();
+    for row in rows {
+        let (task_id, tag_name) = row.map_err(to_error)?;
+        if ignored_tags.contains(&tag_name.to_lowercase()) {
+            task_ids.insert(task_id);
+        }
+    }
+    Ok(task_ids)
+}
+
 fn compute_deviation_ratio(
     conn: &Connection,
     task_id: &str,
@@ -1832,94 +7078,502 @@ fn compute_deviation_ratio(
         return Ok(0.0);
     }
 
-    let mut sessions = completed_session_durations(conn, task_id, until_ts)?;
-    if sessions.len() < 2 {
+    // The cache covers the full history, so it only answers for `until_ts` at or after
+    // the last session it saw; earlier cut-offs (rule simulation) rescan.
+    let cached: Option<(i64, i64)> = conn
+        .query_row(
+            "SELECT session_count, median_seconds
+             FROM task_baselines
+             WHERE task_id = ?1 AND through_ts <= ?2",
+            params![task_id, until_ts],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .optional()
+        .map_err(to_error)?;
+    let (session_count, baseline) = match cached {
+        Some(cached) => cached,
+        None => prior_session_median(completed_session_durations(conn, task_id, until_ts)?),
+    };
+    if session_count < 2 || baseline <= 0 {
         return Ok(0.0);
     }
 
-    let latest = sessions.pop().unwrap_or(focus_seconds);
-    let current = if focus_seconds > 0 {
-        focus_seconds
-    } else {
-        latest
+    Ok(((focus_seconds - baseline) as f64 / baseline as f64).max(0.0))
+}
+
+/// Session count and the median of every session but the latest, which is the one
+/// being compared against the baseline.
+fn prior_session_median(mut sessions: Vec<i64>) -> (i64, i64) {
+    let session_count = sessions.len() as i64;
+    sessions.pop();
+    (session_count, median_i64(&sessions))
+}
+
+/// Recomputes the `task_baselines` row of `task_id`, dropping it when the task has no
+/// completed sessions. Returns whether a row was stored.
+fn refresh_task_baseline(conn: &Connection, task_id: &str) -> AppResult<bool> {
+    let sessions = completed_session_durations(conn, task_id, i64::MAX)?;
+    if sessions.is_empty() {
+        conn.execute(
+            "DELETE FROM task_baselines WHERE task_id = ?1",
+            params![task_id],
+        )
+        .map_err(to_error)?;
+        return Ok(false);
+    }
+
+    let through_ts: i64 = conn
+        .query_row(
+            "SELECT COALESCE(MAX(ts), 0)
+             FROM time_events
+             WHERE task_id = ?1 AND event_type IN ('pause', 'stop', 'adjust')",
+            params![task_id],
+            |row| row.get(0),
+        )
+        .map_err(to_error)?;
+    let (session_count, median_seconds) = prior_session_median(sessions);
+    conn.execute(
+        "INSERT INTO task_baselines (task_id, median_seconds, session_count, through_ts, updated_at)
+         VALUES (?1, ?2, ?3, ?4, ?5)
+         ON CONFLICT(task_id) DO UPDATE SET
+             median_seconds = excluded.median_seconds,
+             session_count = excluded.session_count,
+             through_ts = excluded.through_ts,
+             updated_at = excluded.updated_at",
+        params![task_id, median_seconds, session_count, through_ts, now_ts()],
+    )
+    .map_err(to_error)?;
+    Ok(true)
+}
+
+fn median_i64(values: &[i64]) -> i64 {
+    if values.is_empty() {
+        return 0;
+    }
+    let mut sorted = values.to_vec();
+    sorted.sort_unstable();
+    let mid = sorted.len() / 2;
+    if sorted.len() % 2 == 0 {
+        (sorted[mid - 1] + sorted[mid]) / 2
+    } else {
+        sorted[mid]
+    }
+}
+
+/// Scores the current rhythm and maps it onto the configured `[light, medium, long]`
+/// rest tiers.
+fn evaluate_rest_rules(
+    focus_seconds: i64,
+    switch_count_30m: i64,
+    deviation_ratio: f64,
+    minute_tiers: [i64; 3],
+) -> (i64, Vec<String>) {
+    let mut score = 0;
+    let mut reasons = Vec::new();
+
+    if focus_seconds >= 5_400 {
+        score += 4;
+        reasons.push("continuous focus reached 90+ minutes".to_string());
+    } else if focus_seconds >= 3_000 {
+        score += 2;
+        reasons.push("continuous focus reached 50+ minutes".to_string());
+    }
+
+    if switch_count_30m >= 6 {
+        score += 4;
+        reasons.push("task switching was very frequent in the last 30 minutes".to_string());
+    } else if switch_count_30m >= 3 {
+        score += 2;
+        reasons.push("task switching increased in the last 30 minutes".to_string());
+    }
+
+    if deviation_ratio >= 1.0 {
+        score += 2;
+        reasons.push("focus duration is significantly above historical median".to_string());
+    } else if deviation_ratio >= 0.5 {
+        score += 1;
+        reasons.push("focus duration is above historical median".to_string());
+    }
+
+    let minutes = if score >= 7 {
+        minute_tiers[2]
+    } else if score >= 4 {
+        minute_tiers[1]
+    } else if score >= 2 {
+        minute_tiers[0]
+    } else {
+        0
+    };
+
+    if reasons.is_empty() {
+        reasons.push("current rhythm is stable; continuing is reasonable".to_string());
+    }
+
+    (minutes, reasons)
+}
+
+fn format_hours(seconds: i64) -> String {
+    format!("{:.1}h", seconds as f64 / 3600.0)
+}
+
+fn format_invoice_line(label: &str, seconds: i64) -> String {
+    format!("{label} - {:.2} h", seconds as f64 / 3_600.0)
+}
+
+fn read_setting(conn: &Connection, key: &str) -> AppResult<Option<String>> {
+    conn.query_row(
+        "SELECT value FROM settings WHERE key = ?1 LIMIT 1",
+        params![key],
+        |row| row.get(0),
+    )
+    .optional()
+    .map_err(to_error)
+}
+
+fn read_setting_i64(conn: &Connection, key: &str, default: i64) -> AppResult<i64> {
+    Ok(read_setting(conn, key)?
+        .and_then(|raw| raw.trim().parse::<i64>().ok())
+        .unwrap_or(default))
+}
+
+fn read_setting_bool(conn: &Connection, key: &str) -> AppResult<bool> {
+    Ok(read_setting(conn, key)?.is_some_and(|raw| raw.trim() == "true"))
+}
+
+fn setting_default(key: &str) -> String {
+    match key {
+        SETTING_AUTO_PAUSE_ON_CROSS_BRANCH_START | SETTING_COUNT_INTERRUPTIONS_AS_SWITCHES => {
+            "false".to_string()
+        }
+        SETTING_MAX_SUBTASK_INSERT_DEPTH => DEFAULT_MAX_SUBTASK_INSERT_DEPTH.to_string(),
+        SETTING_AUTO_STOP_PAUSED_AFTER | SETTING_DAILY_MAX_SECONDS => "0".to_string(),
+        SETTING_REST_SUGGESTION_TTL_SECONDS => DEFAULT_REST_SUGGESTION_TTL_SECONDS.to_string(),
+        SETTING_WORKDAY_END => String::new(),
+        SETTING_MIN_SESSION_SECONDS => "0".to_string(),
+        SETTING_REST_MINUTE_TIERS => format_rest_minute_tiers(DEFAULT_REST_MINUTE_TIERS),
+        SETTING_REST_IGNORED_TAGS => String::new(),
+        _ => String::new(),
+    }
+}
+
+fn normalize_setting_value(key: &str, raw: &str) -> AppResult<String> {
+    let cleaned = raw.trim();
+    match key {
+        SETTING_REST_SUGGESTION_TTL_SECONDS
+        | SETTING_MIN_SESSION_SECONDS
+        | SETTING_AUTO_STOP_PAUSED_AFTER
+        | SETTING_DAILY_MAX_SECONDS => {
+            let seconds = cleaned.parse::<i64>().map_err(|_| {
+                validation_error(format!(
+                    "setting '{key}' must be an integer number of seconds"
+                ))
+            })?;
+            if seconds < 0 {
+                return Err(validation_error(format!(
+                    "setting '{key}' cannot be negative"
+                )));
+            }
+            Ok(seconds.to_string())
+        }
+        SETTING_MAX_SUBTASK_INSERT_DEPTH => {
+            let depth = cleaned.parse::<i64>().map_err(|_| {
+                validation_error(format!("setting '{key}' must be a whole number of levels"))
+            })?;
+            if depth < 1 {
+                return Err(validation_error(format!(
+                    "setting '{key}' must be at least 1"
+                )));
+            }
+            Ok(depth.to_string())
+        }
+        SETTING_REST_MINUTE_TIERS => {
+            let tiers = parse_rest_minute_tiers(cleaned).ok_or_else(|| {
+                validation_error(format!(
+                    "setting '{key}' must be three non-decreasing minute values between 0 and {MAX_REST_MINUTES}, like 3,8,15"
+                ))
+            })?;
+            Ok(format_rest_minute_tiers(tiers))
+        }
+        SETTING_REST_IGNORED_TAGS => {
+            let mut tags: Vec<String> = Vec::new();
+            for part in cleaned.split(',').filter(|part| !part.trim().is_empty()) {
+                let tag = sanitize_tag(part)?;
+                if !tags.iter().any(|seen| seen.eq_ignore_ascii_case(&tag)) {
+                    tags.push(tag);
+                }
+            }
+            Ok(tags.join(","))
+        }
+        SETTING_AUTO_PAUSE_ON_CROSS_BRANCH_START | SETTING_COUNT_INTERRUPTIONS_AS_SWITCHES => {
+            match cleaned.to_lowercase().as_str() {
+                "true" | "1" => Ok("true".to_string()),
+                "false" | "0" => Ok("false".to_string()),
+                _ => Err(validation_error(format!(
+                    "setting '{key}' must be true or false"
+                ))),
+            }
+        }
+        SETTING_WORKDAY_END => {
+            let time = NaiveTime::parse_from_str(cleaned, WORKDAY_END_FORMAT).map_err(|_| {
+                validation_error(format!("setting '{key}' must be a local time like 18:30"))
+            })?;
+            Ok(time.format(WORKDAY_END_FORMAT).to_string())
+        }
+        _ => Err(validation_error(format!("unknown setting '{key}'"))),
+    }
+}
+
+fn read_rest_minute_tiers(conn: &Connection) -> AppResult<[i64; 3]> {
+    Ok(read_setting(conn, SETTING_REST_MINUTE_TIERS)?
+        .and_then(|raw| parse_rest_minute_tiers(&raw))
+        .unwrap_or(DEFAULT_REST_MINUTE_TIERS))
+}
+
+fn read_rest_ignored_tags(conn: &Connection) -> AppResult<Vec<String>> {
+    Ok(read_setting(conn, SETTING_REST_IGNORED_TAGS)?
+        .map(|raw| {
+            raw.split(',')
+                .map(str::trim)
+                .filter(|tag| !tag.is_empty())
+                .map(str::to_string)
+                .collect()
+        })
+        .unwrap_or_default())
+}
+
+fn parse_rest_minute_tiers(raw: &str) -> Option<[i64; 3]> {
+    let values = raw
+        .split(',')
+        .map(|part| part.trim().parse::<i64>().ok())
+        .collect::<Option<Vec<_>>>()?;
+    let tiers: [i64; 3] = values.try_into().ok()?;
+    let in_range = tiers
+        .iter()
+        .all(|minutes| (0..=MAX_REST_MINUTES).contains(minutes));
+    let ordered = tiers.windows(2).all(|pair| pair[0] <= pair[1]);
+    (in_range && ordered).then_some(tiers)
+}
+
+fn format_rest_minute_tiers(tiers: [i64; 3]) -> String {
+    tiers
+        .iter()
+        .map(|minutes| minutes.to_string())
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+fn sanitize_title(raw: &str) -> AppResult<String> {
+    let cleaned = raw.trim();
+    if cleaned.is_empty() {
+        return Err(validation_error("title cannot be empty"));
+    }
+    Ok(cleaned.to_string())
+}
+
+fn sanitize_reference_url(raw: &str) -> AppResult<Option<String>> {
+    let cleaned = raw.trim();
+    if cleaned.is_empty() {
+        return Ok(None);
+    }
+    if cleaned.chars().count() > MAX_REFERENCE_URL_CHARS {
+        return Err(validation_error(format!(
+            "reference url cannot exceed {MAX_REFERENCE_URL_CHARS} characters"
+        )));
+    }
+
+    let lowered = cleaned.to_ascii_lowercase();
+    let rest = lowered
+        .strip_prefix("https://")
+        .or_else(|| lowered.strip_prefix("http://"));
+    let host = rest.and_then(|rest| rest.split(['/', '?', '#']).next());
+    let valid = host.is_some_and(|host| !host.is_empty())
+        && !cleaned
+            .chars()
+            .any(|ch| ch.is_whitespace() || ch.is_control());
+    if !valid {
+        return Err(validation_error(format!(
+            "reference url '{cleaned}' must be an http or https link"
+        )));
+    }
+    Ok(Some(cleaned.to_string()))
+}
+
+fn find_or_create_import_task(
+    tx: &Transaction<'_>,
+    title: &str,
+    created_at: i64,
+) -> AppResult<(String, bool)> {
+    let existing: Option<String> = tx
+        .query_row(
+            "SELECT id FROM tasks
+             WHERE title = ?1 AND archived_at IS NULL
+             ORDER BY created_at ASC
+             LIMIT 1",
+            params![title],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(to_error)?;
+    if let Some(task_id) = existing {
+        return Ok((task_id, false));
+    }
+
+    let task_id = Uuid::new_v4().to_string();
+    tx.execute(
+        "INSERT INTO tasks (id, parent_id, title, status, created_at) VALUES (?1, NULL, ?2, ?3, ?4)",
+        params![task_id, title, STATUS_STOPPED, created_at],
+    )
+    .map_err(to_error)?;
+    append_event(
+        tx,
+        &task_id,
+        EVENT_CREATE,
+        created_at,
+        Some(json!({ "parent_id": null, "reason": IMPORT_REASON_CSV })),
+    )?;
+    Ok((task_id, true))
+}
+
+fn resolve_csv_columns(header: &[String]) -> AppResult<CsvColumns> {
+    let names = header
+        .iter()
+        .map(|name| name.trim().trim_start_matches('\u{feff}').to_lowercase())
+        .collect::<Vec<_>>();
+    let find = |name: &str| names.iter().position(|candidate| candidate == name);
+    let time_column = |prefix: &str| {
+        find(prefix).map(CsvTimeColumn::Combined).or_else(|| {
+            Some(CsvTimeColumn::Split {
+                date: find(&format!("{prefix} date"))?,
+                time: find(&format!("{prefix} time"))?,
+            })
+        })
     };
-    let baseline = median_i64(&sessions);
-    if baseline <= 0 {
-        return Ok(0.0);
-    }
 
-    Ok(((current - baseline) as f64 / baseline as f64).max(0.0))
+    let description = find("description")
+        .ok_or_else(|| validation_error("csv header is missing a description column"))?;
+    let start = time_column("start")
+        .ok_or_else(|| validation_error("csv header is missing a start column"))?;
+    let end = time_column("end")
+        .ok_or_else(|| validation_error("csv header is missing an end column"))?;
+
+    Ok(CsvColumns {
+        description,
+        start,
+        end,
+        tags: find("tags"),
+    })
 }
 
-fn median_i64(values: &[i64]) -> i64 {
-    if values.is_empty() {
-        return 0;
+fn parse_csv_entry(columns: &CsvColumns, fields: &[String], now: i64) -> Result<CsvEntry, String> {
+    let field = |index: usize| fields.get(index).map(|value| value.trim()).unwrap_or("");
+    let timestamp = |column: &CsvTimeColumn, label: &str| {
+        let raw = match column {
+            CsvTimeColumn::Combined(index) => field(*index).to_string(),
+            CsvTimeColumn::Split { date, time } => format!("{} {}", field(*date), field(*time)),
+        };
+        parse_import_timestamp(&raw).ok_or_else(|| format!("invalid {label} timestamp '{raw}'"))
+    };
+
+    let title = sanitize_title(field(columns.description)).map_err(|error| error.message)?;
+    let start_ts = timestamp(&columns.start, "start")?;
+    let end_ts = timestamp(&columns.end, "end")?;
+    if end_ts <= start_ts {
+        return Err("entry must end after it starts".to_string());
     }
-    let mut sorted = values.to_vec();
-    sorted.sort_unstable();
-    let mid = sorted.len() / 2;
-    if sorted.len() % 2 == 0 {
-        (sorted[mid - 1] + sorted[mid]) / 2
-    } else {
-        sorted[mid]
+    if end_ts > now {
+        return Err("entry cannot end in the future".to_string());
     }
-}
 
-fn evaluate_rest_rules(
-    focus_seconds: i64,
-    switch_count_30m: i64,
-    deviation_ratio: f64,
-) -> (i64, Vec<String>) {
-    let mut score = 0;
-    let mut reasons = Vec::new();
+    let tags = match columns.tags {
+        Some(index) => field(index)
+            .split(',')
+            .filter(|tag| !tag.trim().is_empty())
+            .map(|tag| sanitize_tag(tag).map_err(|error| error.message))
+            .collect::<Result<Vec<_>, _>>()?,
+        None => Vec::new(),
+    };
 
-    if focus_seconds >= 5_400 {
-        score += 4;
-        reasons.push("continuous focus reached 90+ minutes".to_string());
-    } else if focus_seconds >= 3_000 {
-        score += 2;
-        reasons.push("continuous focus reached 50+ minutes".to_string());
-    }
+    Ok(CsvEntry {
+        title,
+        start_ts,
+        end_ts,
+        tags,
+    })
+}
 
-    if switch_count_30m >= 6 {
-        score += 4;
-        reasons.push("task switching was very frequent in the last 30 minutes".to_string());
-    } else if switch_count_30m >= 3 {
-        score += 2;
-        reasons.push("task switching increased in the last 30 minutes".to_string());
+fn parse_import_timestamp(raw: &str) -> Option<i64> {
+    let cleaned = raw.trim();
+    if let Ok(date_time) = DateTime::parse_from_rfc3339(cleaned) {
+        return Some(date_time.timestamp());
     }
 
-    if deviation_ratio >= 1.0 {
-        score += 2;
-        reasons.push("focus duration is significantly above historical median".to_string());
-    } else if deviation_ratio >= 0.5 {
-        score += 1;
-        reasons.push("focus duration is above historical median".to_string());
-    }
+    ["%Y-%m-%d %H:%M:%S", "%Y-%m-%dT%H:%M:%S", "%Y-%m-%d %H:%M"]
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(cleaned, format).ok())
+        .and_then(|naive| Local.from_local_datetime(&naive).earliest())
+        .map(|date_time| date_time.timestamp())
+}
 
-    let minutes = if score >= 7 {
-        15
-    } else if score >= 4 {
-        8
-    } else if score >= 2 {
-        3
-    } else {
-        0
-    };
+/// Minimal RFC 4180 reader: quoted fields may contain commas, doubled quotes and line
+/// breaks. Returns each record with the 1-based line it starts on.
+fn parse_csv_records(raw: &str) -> Vec<(usize, Vec<String>)> {
+    let mut records = Vec::new();
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut line = 1;
+    let mut record_line = 1;
+    let mut chars = raw.chars().peekable();
+
+    while let Some(ch) = chars.next() {
+        match ch {
+            '"' if in_quotes && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => in_quotes = !in_quotes,
+            ',' if !in_quotes => fields.push(std::mem::take(&mut field)),
+            '\r' if !in_quotes => {}
+            '\n' if !in_quotes => {
+                fields.push(std::mem::take(&mut field));
+                records.push((record_line, std::mem::take(&mut fields)));
+                line += 1;
+                record_line = line;
+            }
+            _ => {
+                if ch == '\n' {
+                    line += 1;
+                }
+                field.push(ch);
+            }
+        }
+    }
 
-    if reasons.is_empty() {
-        reasons.push("current rhythm is stable; continuing is reasonable".to_string());
+    if !field.is_empty() || !fields.is_empty() {
+        fields.push(field);
+        records.push((record_line, fields));
     }
 
-    (minutes, reasons)
+    records
 }
 
-fn sanitize_title(raw: &str) -> AppResult<String> {
+fn sanitize_pause_reason(raw: Option<String>) -> AppResult<Option<String>> {
+    let Some(raw) = raw else {
+        return Ok(None);
+    };
     let cleaned = raw.trim();
     if cleaned.is_empty() {
-        return Err(validation_error("title cannot be empty"));
+        return Ok(None);
     }
-    Ok(cleaned.to_string())
+    if RESERVED_PAUSE_REASONS
+        .iter()
+        .any(|reserved| reserved.eq_ignore_ascii_case(cleaned))
+    {
+        return Err(validation_error(format!(
+            "pause reason '{cleaned}' is reserved for pauses made by the app"
+        )));
+    }
+    Ok(Some(cleaned.chars().take(MAX_PAUSE_REASON_CHARS).collect()))
 }
 
 fn sanitize_tag(raw: &str) -> AppResult<String> {
@@ -1927,9 +7581,44 @@ fn sanitize_tag(raw: &str) -> AppResult<String> {
     if cleaned.is_empty() {
         return Err(validation_error("tag cannot be empty"));
     }
+    if let Some((key, value)) = split_structured_tag(cleaned) {
+        return Ok(format!("{key}:{value}"));
+    }
     Ok(cleaned.to_string())
 }
 
+/// The sanitized form of a tag name plus its lowercase key, which is how the `lower(name)`
+/// lookups tell tags apart. `None` when the name is not a valid tag.
+fn tag_identity(raw: &str) -> Option<(String, String)> {
+    let clean_tag = sanitize_tag(raw).ok()?;
+    Some((clean_tag.to_lowercase(), clean_tag))
+}
+
+fn sanitize_color(raw: &str) -> AppResult<String> {
+    let cleaned = raw.trim().to_lowercase();
+    let is_hex = cleaned.strip_prefix('#').is_some_and(|digits| {
+        matches!(digits.len(), 3 | 6) && digits.chars().all(|ch| ch.is_ascii_hexdigit())
+    });
+    if !is_hex {
+        return Err(validation_error(format!(
+            "color '{raw}' must be a hex color like #3b82f6"
+        )));
+    }
+    Ok(cleaned)
+}
+
+/// Splits a `key:value` tag into its lowercased key and trimmed value.
+/// Tags without a non-empty key and value around the first `:` stay plain.
+fn split_structured_tag(tag: &str) -> Option<(String, String)> {
+    let (key, value) = tag.split_once(':')?;
+    let key = key.trim();
+    let value = value.trim();
+    if key.is_empty() || value.is_empty() {
+        return None;
+    }
+    Some((key.to_lowercase(), value.to_string()))
+}
+
 fn resolve_summary_window(
     conn: &Connection,
     range: Option<String>,
@@ -2004,6 +7693,13 @@ fn now_ts() -> i64 {
         .unwrap_or(0)
 }
 
+fn now_ts_millis() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_millis() as i64)
+        .unwrap_or(0)
+}
+
 fn earliest_focus_event_ts(conn: &Connection) -> AppResult<Option<i64>> {
     conn.query_row(
         "SELECT MIN(ts)
@@ -2047,6 +7743,21 @@ fn local_day_start_ts(now: i64) -> i64 {
         .timestamp()
 }
 
+fn parse_local_date_start(raw: &str) -> AppResult<i64> {
+    let date = NaiveDate::parse_from_str(raw.trim(), "%Y-%m-%d")
+        .map_err(|_| validation_error("date must use YYYY-MM-DD"))?;
+    let Some(naive_midnight) = date.and_hms_opt(0, 0, 0) else {
+        return Err(validation_error("date must use YYYY-MM-DD"));
+    };
+    Local
+        .from_local_datetime(&naive_midnight)
+        .single()
+        .or_else(|| Local.from_local_datetime(&naive_midnight).earliest())
+        .or_else(|| Local.from_local_datetime(&naive_midnight).latest())
+        .map(|date_time| date_time.timestamp())
+        .ok_or_else(|| validation_error("date must use YYYY-MM-DD"))
+}
+
 fn shift_local_day_start(day_start_ts: i64, offset_days: i64) -> i64 {
     let Some(local_day_start) = Local.timestamp_opt(day_start_ts, 0).single() else {
         return day_start_ts + offset_days * 86_400;
@@ -2064,6 +7775,20 @@ fn shift_local_day_start(day_start_ts: i64, offset_days: i64) -> i64 {
         .timestamp()
 }
 
+fn local_time_on_day(day_start_ts: i64, time: NaiveTime) -> i64 {
+    let Some(local_day_start) = Local.timestamp_opt(day_start_ts, 0).single() else {
+        return day_start_ts + i64::from(time.num_seconds_from_midnight());
+    };
+    let naive = local_day_start.date_naive().and_time(time);
+    Local
+        .from_local_datetime(&naive)
+        .single()
+        .or_else(|| Local.from_local_datetime(&naive).earliest())
+        .or_else(|| Local.from_local_datetime(&naive).latest())
+        .map(|date_time| date_time.timestamp())
+        .unwrap_or_else(|| day_start_ts + i64::from(time.num_seconds_from_midnight()))
+}
+
 fn local_date_key(ts: i64) -> String {
     Local
         .timestamp_opt(ts, 0)
@@ -2082,6 +7807,13 @@ fn conflict_error(message: impl Into<String>) -> AppError {
     AppError::conflict(message)
 }
 
+fn task_running_error(action: &str, running_task_id: String) -> AppError {
+    AppError::task_running(
+        format!("cannot {action} task because task {running_task_id} is already running"),
+        running_task_id,
+    )
+}
+
 fn not_found_error(message: impl Into<String>) -> AppError {
     AppError::not_found(message)
 }
@@ -2089,6 +7821,750 @@ fn not_found_error(message: impl Into<String>) -> AppError {
 fn to_error(error: impl std::fmt::Display) -> AppError {
     AppError::internal("database operation failed", error.to_string())
 }
-
-
-
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+    use std::time::Duration;
+
+    use rusqlite::{params, Connection};
+    use uuid::Uuid;
+
+    use super::{
+        add_tag_to_task, auto_stop_stale_paused_tasks, begin_transaction,
+        clip_running_across_sleep, coalesce_task_sessions, completed_session_durations,
+        compute_deviation_ratio, count_task_switches, create_task, end_break, evaluate_rest_rules,
+        get_task_events, get_task_state, insert_subtask_and_start, now_ts, pause_task,
+        read_rest_minute_tiers, rebuild_baselines, reconcile_tags, replay_exclusive_seconds,
+        replay_totals, resume_task, start_break, start_task, stop_task, task_total_focus_seconds,
+        update_setting, verify_tag_consistency, verify_totals, within_edit_distance, Precision,
+        TimeBasis,
+    };
+    use crate::infra::{run_migrations, AppResult};
+
+    fn open_test_db() -> Connection {
+        let conn = Connection::open_in_memory().expect("in-memory database should open");
+        conn.pragma_update(None, "foreign_keys", "ON")
+            .expect("foreign_keys pragma should apply");
+        run_migrations(&conn).expect("migrations should apply");
+        conn
+    }
+
+    fn insert_event(conn: &Connection, task_id: &str, event_type: &str, ts: i64) {
+        conn.execute(
+            "INSERT INTO time_events (task_id, event_type, ts, payload) VALUES (?1, ?2, ?3, NULL)",
+            params![task_id, event_type, ts],
+        )
+        .expect("event should insert");
+    }
+
+    fn insert_tag_event(conn: &Connection, task_id: &str, event_type: &str, tag: &str, ts: i64) {
+        conn.execute(
+            "INSERT INTO time_events (task_id, event_type, ts, payload) VALUES (?1, ?2, ?3, ?4)",
+            params![
+                task_id,
+                event_type,
+                ts,
+                serde_json::json!({ "tag": tag }).to_string()
+            ],
+        )
+        .expect("tag event should insert");
+    }
+
+    fn task_tag_names(conn: &Connection, task_id: &str) -> Vec<String> {
+        let mut stmt = conn
+            .prepare(
+                "SELECT tg.name
+                 FROM task_tags tt
+                 INNER JOIN tags tg ON tg.id = tt.tag_id
+                 WHERE tt.task_id = ?1
+                 ORDER BY tg.name",
+            )
+            .expect("tag query should prepare");
+        let names = stmt
+            .query_map(params![task_id], |row| row.get::<_, String>(0))
+            .expect("tag query should run")
+            .collect::<Result<Vec<_>, _>>()
+            .expect("tag rows should read");
+        names
+    }
+
+    fn set_min_session_seconds(conn: &mut Connection, seconds: i64) {
+        update_setting(conn, "min_session_seconds".to_string(), seconds.to_string())
+            .expect("setting should update");
+    }
+
+    fn set_rest_minute_tiers(conn: &mut Connection, value: &str) -> AppResult<()> {
+        update_setting(conn, "rest_minute_tiers".to_string(), value.to_string())
+    }
+
+    #[test]
+    fn coalesce_task_sessions_preserves_tracked_totals() {
+        let mut conn = open_test_db();
+        let task_id = create_task(&mut conn, "write report".to_string(), None).unwrap();
+        insert_event(&conn, &task_id, "start", 1_000);
+        insert_event(&conn, &task_id, "pause", 1_600);
+        insert_event(&conn, &task_id, "resume", 1_610);
+        insert_event(&conn, &task_id, "pause", 2_000);
+        insert_event(&conn, &task_id, "resume", 2_500);
+        insert_event(&conn, &task_id, "stop", 3_000);
+
+        let before = replay_exclusive_seconds(&conn, None, 10_000).unwrap();
+        assert_eq!(before.get(&task_id).copied(), Some(1_490));
+        assert_eq!(
+            completed_session_durations(&conn, &task_id, 10_000).unwrap(),
+            vec![600, 390, 500]
+        );
+
+        let merged = coalesce_task_sessions(&mut conn, task_id.clone(), 30).unwrap();
+        assert_eq!(merged, 1);
+
+        let after = replay_exclusive_seconds(&conn, None, 10_000).unwrap();
+        assert_eq!(after.get(&task_id).copied(), Some(1_490));
+        assert_eq!(
+            task_total_focus_seconds(&conn, &task_id, 10_000).unwrap(),
+            1_490
+        );
+        assert_eq!(
+            completed_session_durations(&conn, &task_id, 10_000).unwrap(),
+            vec![990, 500]
+        );
+    }
+
+    #[test]
+    fn coalesce_task_sessions_keeps_gaps_where_another_task_ran() {
+        let mut conn = open_test_db();
+        let task_id = create_task(&mut conn, "write report".to_string(), None).unwrap();
+        let other_id = create_task(&mut conn, "answer email".to_string(), None).unwrap();
+        insert_event(&conn, &task_id, "start", 1_000);
+        insert_event(&conn, &task_id, "pause", 1_600);
+        insert_event(&conn, &other_id, "start", 1_602);
+        insert_event(&conn, &other_id, "stop", 1_608);
+        insert_event(&conn, &task_id, "resume", 1_610);
+        insert_event(&conn, &task_id, "stop", 2_000);
+
+        let merged = coalesce_task_sessions(&mut conn, task_id.clone(), 30).unwrap();
+        assert_eq!(merged, 0);
+
+        let totals = replay_exclusive_seconds(&conn, None, 10_000).unwrap();
+        assert_eq!(totals.get(&task_id).copied(), Some(990));
+        assert_eq!(totals.get(&other_id).copied(), Some(6));
+    }
+
+    #[test]
+    fn coalesce_task_sessions_rejects_non_positive_gap() {
+        let mut conn = open_test_db();
+        let task_id = create_task(&mut conn, "write report".to_string(), None).unwrap();
+        let error = coalesce_task_sessions(&mut conn, task_id, 0).unwrap_err();
+        assert_eq!(error.code, "validation");
+    }
+
+    #[test]
+    fn compute_deviation_ratio_reads_cached_baseline_for_current_history() {
+        let mut conn = open_test_db();
+        let task_id = create_task(&mut conn, "write report".to_string(), None).unwrap();
+        insert_event(&conn, &task_id, "start", 1_000);
+        insert_event(&conn, &task_id, "pause", 1_600);
+        insert_event(&conn, &task_id, "resume", 1_610);
+        insert_event(&conn, &task_id, "pause", 2_000);
+        insert_event(&conn, &task_id, "resume", 2_500);
+        insert_event(&conn, &task_id, "stop", 3_000);
+        assert_eq!(rebuild_baselines(&mut conn).unwrap(), 1);
+
+        conn.execute("UPDATE task_baselines SET median_seconds = 300", [])
+            .unwrap();
+        let ratio = compute_deviation_ratio(&conn, &task_id, 900, 10_000).unwrap();
+        assert!((ratio - 2.0).abs() < f64::EPSILON);
+        // A cut-off before the cached history ignores the cache and rescans.
+        let ratio = compute_deviation_ratio(&conn, &task_id, 900, 2_000).unwrap();
+        assert!((ratio - 0.5).abs() < f64::EPSILON);
+    }
+
+    fn set_status(conn: &Connection, task_id: &str, status: &str) {
+        conn.execute(
+            "UPDATE tasks SET status = ?1 WHERE id = ?2",
+            params![status, task_id],
+        )
+        .expect("status should update");
+    }
+
+    #[test]
+    fn sleep_while_paused_adds_no_time() {
+        let mut conn = open_test_db();
+        let task_id = create_task(&mut conn, "report".to_string(), None).unwrap();
+        let now = now_ts();
+        insert_event(&conn, &task_id, "start", now - 3_000);
+        insert_event(&conn, &task_id, "pause", now - 2_900);
+        set_status(&conn, &task_id, "paused");
+
+        assert!(clip_running_across_sleep(&mut conn, now - 2_800, now - 200)
+            .unwrap()
+            .is_none());
+        insert_event(&conn, &task_id, "resume", now - 100);
+        insert_event(&conn, &task_id, "stop", now - 50);
+        let totals = replay_exclusive_seconds(&conn, None, now).unwrap();
+        assert_eq!(totals.get(&task_id).copied(), Some(150));
+    }
+
+    #[test]
+    fn sleep_while_running_is_clipped_to_last_seen() {
+        let mut conn = open_test_db();
+        let task_id = create_task(&mut conn, "report".to_string(), None).unwrap();
+        let now = now_ts();
+        insert_event(&conn, &task_id, "start", now - 1_000);
+        set_status(&conn, &task_id, "running");
+
+        let unclipped = replay_exclusive_seconds(&conn, None, now).unwrap();
+        assert_eq!(unclipped.get(&task_id).copied(), Some(1_000));
+
+        let clip = clip_running_across_sleep(&mut conn, now - 900, now - 100)
+            .unwrap()
+            .expect("running session should be clipped");
+        assert_eq!(clip.task_id, task_id);
+        assert_eq!((clip.slept_from, clip.slept_until), (now - 900, now - 100));
+        let clipped = replay_exclusive_seconds(&conn, None, now).unwrap();
+        assert_eq!(clipped.get(&task_id).copied(), Some(200));
+        assert_eq!(get_task_state(&conn, &task_id).unwrap().status, "running");
+    }
+
+    #[test]
+    fn sleep_split_does_not_end_the_baseline_session() {
+        let mut conn = open_test_db();
+        let task_id = create_task(&mut conn, "report".to_string(), None).unwrap();
+        let now = now_ts();
+        insert_event(&conn, &task_id, "start", now - 1_000);
+        set_status(&conn, &task_id, "running");
+        clip_running_across_sleep(&mut conn, now - 900, now - 100)
+            .unwrap()
+            .expect("running session should be clipped");
+        assert!(completed_session_durations(&conn, &task_id, i64::MAX)
+            .unwrap()
+            .is_empty());
+
+        insert_event(&conn, &task_id, "stop", now);
+        let sessions = completed_session_durations(&conn, &task_id, i64::MAX).unwrap();
+        assert_eq!(sessions, vec![200]);
+    }
+
+    #[test]
+    fn sleep_clip_skips_sessions_started_after_last_seen() {
+        let mut conn = open_test_db();
+        let task_id = create_task(&mut conn, "report".to_string(), None).unwrap();
+        let now = now_ts();
+        insert_event(&conn, &task_id, "start", now - 50);
+        set_status(&conn, &task_id, "running");
+
+        assert!(clip_running_across_sleep(&mut conn, now - 100, now - 10)
+            .unwrap()
+            .is_none());
+        let totals = replay_exclusive_seconds(&conn, None, now).unwrap();
+        assert_eq!(totals.get(&task_id).copied(), Some(50));
+    }
+
+    #[test]
+    fn banded_edit_distance_matches_full_levenshtein() {
+        fn levenshtein(left: &[char], right: &[char]) -> usize {
+            let mut previous = (0..=right.len()).collect::<Vec<_>>();
+            for (i, left_char) in left.iter().enumerate() {
+                let mut current = vec![i + 1; right.len() + 1];
+                for (j, right_char) in right.iter().enumerate() {
+                    current[j + 1] = (previous[j] + usize::from(left_char != right_char))
+                        .min(previous[j + 1] + 1)
+                        .min(current[j] + 1);
+                }
+                previous = current;
+            }
+            previous[right.len()]
+        }
+
+        let words = [
+            "",
+            "a",
+            "ab",
+            "ba",
+            "abc",
+            "write report",
+            "write reprot",
+            "wirte report",
+            "write reports",
+            "rewrite report",
+            "groceries",
+            "grocery",
+            "kitten",
+            "sitting",
+        ];
+        for left in words {
+            for right in words {
+                let left = left.chars().collect::<Vec<_>>();
+                let right = right.chars().collect::<Vec<_>>();
+                let distance = levenshtein(&left, &right);
+                for max_distance in 0..=5 {
+                    assert_eq!(
+                        within_edit_distance(&left, &right, max_distance),
+                        distance <= max_distance,
+                        "{left:?} vs {right:?} within {max_distance}"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn millis_replay_reads_legacy_rows_as_whole_seconds() {
+        let mut conn = open_test_db();
+        let task_id = create_task(&mut conn, "triage".to_string(), None).unwrap();
+        insert_event(&conn, &task_id, "start", 1_000);
+        insert_event(&conn, &task_id, "pause", 1_030);
+        conn.execute(
+            "INSERT INTO time_events (task_id, event_type, ts, ts_ms) VALUES (?1, ?2, ?3, ?4)",
+            params![task_id, "resume", 1_100, 1_100_250],
+        )
+        .unwrap();
+        conn.execute(
+            "INSERT INTO time_events (task_id, event_type, ts, ts_ms) VALUES (?1, ?2, ?3, ?4)",
+            params![task_id, "stop", 1_110, 1_110_900],
+        )
+        .unwrap();
+
+        let seconds =
+            replay_totals(&conn, None, 10_000, TimeBasis::Active, Precision::Seconds).unwrap();
+        assert_eq!(seconds.get(&task_id).copied(), Some(40));
+        let millis = replay_totals(
+            &conn,
+            None,
+            10_000_000,
+            TimeBasis::Active,
+            Precision::Millis,
+        )
+        .unwrap();
+        assert_eq!(millis.get(&task_id).copied(), Some(40_650));
+    }
+
+    #[test]
+    fn min_session_seconds_keeps_sessions_at_threshold() {
+        let mut conn = open_test_db();
+        set_min_session_seconds(&mut conn, 30);
+        let task_id = create_task(&mut conn, "triage".to_string(), None).unwrap();
+        insert_event(&conn, &task_id, "start", 1_000);
+        insert_event(&conn, &task_id, "pause", 1_030);
+        insert_event(&conn, &task_id, "resume", 1_100);
+        insert_event(&conn, &task_id, "stop", 1_129);
+
+        let totals = replay_exclusive_seconds(&conn, None, 10_000).unwrap();
+        assert_eq!(totals.get(&task_id).copied(), Some(30));
+        assert_eq!(
+            completed_session_durations(&conn, &task_id, 10_000).unwrap(),
+            vec![30]
+        );
+    }
+
+    #[test]
+    fn min_session_seconds_keeps_open_interval_below_threshold() {
+        let mut conn = open_test_db();
+        set_min_session_seconds(&mut conn, 30);
+        let task_id = create_task(&mut conn, "triage".to_string(), None).unwrap();
+        insert_event(&conn, &task_id, "start", 1_000);
+
+        let totals = replay_exclusive_seconds(&conn, None, 1_010).unwrap();
+        assert_eq!(totals.get(&task_id).copied(), Some(10));
+        assert!(completed_session_durations(&conn, &task_id, 1_010)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn verify_totals_agrees_with_min_session_seconds() {
+        let mut conn = open_test_db();
+        set_min_session_seconds(&mut conn, 30);
+        let task_id = create_task(&mut conn, "triage".to_string(), None).unwrap();
+        insert_event(&conn, &task_id, "start", 1_000);
+        conn.execute(
+            "INSERT INTO time_events (task_id, event_type, ts, payload)
+             VALUES (?1, 'adjust', 1005, '{\"delta_seconds\":600}')",
+            params![task_id],
+        )
+        .expect("event should insert");
+        insert_event(&conn, &task_id, "stop", 1_010);
+        insert_event(&conn, &task_id, "start", 2_000);
+        insert_event(&conn, &task_id, "stop", 2_100);
+
+        assert!(verify_totals(&conn).unwrap().is_empty());
+    }
+
+    #[test]
+    fn legacy_structured_tag_survives_migration_and_reconcile() {
+        let mut conn = open_test_db();
+        let task_id = create_task(&mut conn, "invoice".to_string(), None).unwrap();
+        conn.execute(
+            "INSERT INTO tags (id, name, tag_key, tag_value) VALUES ('legacy', 'Client : Acme', 'client', 'Acme')",
+            [],
+        )
+        .expect("tag should insert");
+        conn.execute(
+            "INSERT INTO task_tags (task_id, tag_id, created_at) VALUES (?1, 'legacy', 1000)",
+            params![task_id],
+        )
+        .expect("task tag should insert");
+        insert_tag_event(&conn, &task_id, "tag_add", "Client : Acme", 1_000);
+        conn.execute_batch("PRAGMA user_version = 22;")
+            .expect("user_version should reset");
+        run_migrations(&conn).expect("migrations should apply");
+
+        assert_eq!(task_tag_names(&conn, &task_id), vec!["client:Acme"]);
+        assert!(verify_tag_consistency(&conn).unwrap().is_empty());
+        assert!(reconcile_tags(&mut conn).unwrap().is_empty());
+        assert_eq!(task_tag_names(&conn, &task_id), vec!["client:Acme"]);
+    }
+
+    #[test]
+    fn tag_consistency_matches_names_by_sanitized_form() {
+        let mut conn = open_test_db();
+        let task_id = create_task(&mut conn, "invoice".to_string(), None).unwrap();
+        add_tag_to_task(&mut conn, task_id.clone(), "Billable".to_string()).unwrap();
+        add_tag_to_task(&mut conn, task_id.clone(), "client:Acme".to_string()).unwrap();
+        insert_tag_event(&conn, &task_id, "tag_remove", "BILLABLE", now_ts() + 1);
+        insert_tag_event(&conn, &task_id, "tag_remove", "Client : Acme", now_ts() + 1);
+        insert_tag_event(&conn, &task_id, "tag_add", "  CLIENT :Acme ", now_ts() + 2);
+        insert_tag_event(&conn, &task_id, "tag_add", " Team : Core ", now_ts() + 2);
+
+        let discrepancies = verify_tag_consistency(&conn).unwrap();
+        assert_eq!(discrepancies.len(), 1);
+        assert_eq!(discrepancies[0].missing_rows, vec!["team:Core"]);
+        assert_eq!(discrepancies[0].unlogged_rows, vec!["Billable"]);
+
+        reconcile_tags(&mut conn).unwrap();
+        assert_eq!(
+            task_tag_names(&conn, &task_id),
+            vec!["client:Acme", "team:Core"]
+        );
+        assert!(verify_tag_consistency(&conn).unwrap().is_empty());
+    }
+
+    #[test]
+    fn task_events_page_backwards_in_timeline_order() {
+        let mut conn = open_test_db();
+        let task_id = create_task(&mut conn, "review".to_string(), None).unwrap();
+        conn.execute("DELETE FROM time_events", [])
+            .expect("events should clear");
+        for ts in [1_000, 2_000, 3_000, 4_000] {
+            insert_event(&conn, &task_id, "adjust", ts);
+        }
+        // Recorded last but backdated before everything else.
+        insert_event(&conn, &task_id, "adjust", 500);
+
+        let newest = get_task_events(&conn, task_id.clone(), Some(3), None, None).unwrap();
+        let newest_ts = newest
+            .events
+            .iter()
+            .map(|event| event.ts)
+            .collect::<Vec<_>>();
+        assert_eq!(newest_ts, vec![2_000, 3_000, 4_000]);
+        assert!(newest.has_more);
+        assert_eq!(newest.next_before_ts, Some(2_000));
+
+        let older = get_task_events(
+            &conn,
+            task_id.clone(),
+            Some(3),
+            newest.next_before_ts,
+            newest.next_before_id,
+        )
+        .unwrap();
+        let older_ts = older
+            .events
+            .iter()
+            .map(|event| event.ts)
+            .collect::<Vec<_>>();
+        assert_eq!(older_ts, vec![500, 1_000]);
+        assert!(!older.has_more);
+        assert_eq!(older.next_before_id, None);
+
+        assert!(get_task_events(&conn, task_id.clone(), Some(0), None, None).is_err());
+        assert!(get_task_events(&conn, task_id.clone(), None, Some(1_000), Some(0)).is_err());
+        assert!(get_task_events(&conn, task_id, None, Some(1_000), None).is_err());
+    }
+
+    #[test]
+    fn evaluate_rest_rules_uses_custom_minute_tiers() {
+        let tiers = [5, 20, 45];
+        assert_eq!(evaluate_rest_rules(0, 0, 0.0, tiers).0, 0);
+        assert_eq!(evaluate_rest_rules(3_000, 0, 0.0, tiers).0, 5);
+        assert_eq!(evaluate_rest_rules(5_400, 0, 0.0, tiers).0, 20);
+        assert_eq!(evaluate_rest_rules(5_400, 6, 0.0, tiers).0, 45);
+    }
+
+    #[test]
+    fn rest_minute_tiers_setting_round_trips_and_validates() {
+        let mut conn = open_test_db();
+        assert_eq!(read_rest_minute_tiers(&conn).unwrap(), [3, 8, 15]);
+
+        set_rest_minute_tiers(&mut conn, " 5, 20,45 ").unwrap();
+        assert_eq!(read_rest_minute_tiers(&conn).unwrap(), [5, 20, 45]);
+
+        for invalid in ["5,20", "20,5,45", "5,20,121", "a,b,c"] {
+            assert!(set_rest_minute_tiers(&mut conn, invalid).is_err());
+        }
+    }
+
+    #[test]
+    fn count_task_switches_skips_rest_ignored_tags() {
+        let mut conn = open_test_db();
+        let report_id = create_task(&mut conn, "write report".to_string(), None).unwrap();
+        let lookup_id = create_task(&mut conn, "check docs".to_string(), None).unwrap();
+        add_tag_to_task(&mut conn, lookup_id.clone(), "Reference".to_string()).unwrap();
+        insert_event(&conn, &report_id, "start", 1_000);
+        insert_event(&conn, &lookup_id, "start", 1_100);
+        insert_event(&conn, &report_id, "start", 1_200);
+        assert_eq!(count_task_switches(&conn, 0, 2_000).unwrap(), 2);
+
+        update_setting(
+            &mut conn,
+            "rest_ignored_tags".to_string(),
+            " reference, ,REFERENCE ".to_string(),
+        )
+        .unwrap();
+        assert_eq!(count_task_switches(&conn, 0, 2_000).unwrap(), 0);
+    }
+
+    #[test]
+    fn count_task_switches_counts_interruptions_only_when_enabled() {
+        let mut conn = open_test_db();
+        let report_id = create_task(&mut conn, "write report".to_string(), None).unwrap();
+        insert_event(&conn, &report_id, "start", 1_000);
+        insert_event(&conn, &report_id, "interruption", 1_100);
+        insert_event(&conn, &report_id, "interruption", 1_200);
+        assert_eq!(count_task_switches(&conn, 0, 2_000).unwrap(), 0);
+        assert_eq!(
+            replay_exclusive_seconds(&conn, None, 2_000)
+                .unwrap()
+                .get(&report_id)
+                .copied(),
+            Some(1_000)
+        );
+
+        update_setting(
+            &mut conn,
+            "count_interruptions_as_switches".to_string(),
+            "true".to_string(),
+        )
+        .unwrap();
+        assert_eq!(count_task_switches(&conn, 0, 2_000).unwrap(), 2);
+    }
+
+    #[test]
+    fn rest_suggestions_accept_custom_minutes_within_bounds() {
+        let conn = open_test_db();
+        let insert = |minutes: i64| {
+            conn.execute(
+                "INSERT INTO rest_suggestions
+                    (trigger_type, task_id, focus_seconds, switch_count_30m, deviation_ratio,
+                     suggested_minutes, reasons, status, created_at)
+                 VALUES ('task_switch', NULL, 0, 0, 0.0, ?1, '[]', 'pending', 0)",
+                params![minutes],
+            )
+        };
+        assert!(insert(45).is_ok());
+        assert!(insert(120).is_ok());
+        assert!(insert(121).is_err());
+        assert!(insert(-1).is_err());
+    }
+
+    #[test]
+    fn stop_task_rejects_at_ts_before_start() {
+        let mut conn = open_test_db();
+        let task_id = create_task(&mut conn, "write report".to_string(), None).unwrap();
+        let started_at = now_ts() - 600;
+        conn.execute(
+            "UPDATE time_events SET ts = ?1 WHERE task_id = ?2",
+            params![started_at - 600, task_id],
+        )
+        .unwrap();
+        start_task(&mut conn, task_id.clone(), Some(started_at)).unwrap();
+
+        let error = stop_task(&mut conn, task_id.clone(), Some(started_at - 60)).unwrap_err();
+        assert_eq!(error.code, "validation");
+        let error = pause_task(&mut conn, task_id.clone(), None, Some(started_at - 1)).unwrap_err();
+        assert_eq!(error.code, "validation");
+
+        stop_task(&mut conn, task_id.clone(), Some(started_at + 300)).unwrap();
+        assert_eq!(
+            task_total_focus_seconds(&conn, &task_id, now_ts()).unwrap(),
+            300
+        );
+    }
+
+    #[test]
+    fn stop_task_rejects_at_ts_before_resume() {
+        let mut conn = open_test_db();
+        let task_id = create_task(&mut conn, "write report".to_string(), None).unwrap();
+        insert_event(&conn, &task_id, "start", 1_000);
+        insert_event(&conn, &task_id, "pause", 1_600);
+        insert_event(&conn, &task_id, "resume", 2_000);
+        conn.execute(
+            "UPDATE tasks SET status = 'running' WHERE id = ?1",
+            params![task_id],
+        )
+        .unwrap();
+
+        let error = stop_task(&mut conn, task_id.clone(), Some(1_800)).unwrap_err();
+        assert_eq!(error.code, "validation");
+
+        stop_task(&mut conn, task_id.clone(), Some(2_000)).unwrap();
+        assert_eq!(
+            task_total_focus_seconds(&conn, &task_id, 10_000).unwrap(),
+            600
+        );
+    }
+
+    #[test]
+    fn stopping_deep_inserted_subtask_resumes_only_its_direct_parent() {
+        let mut conn = open_test_db();
+        let root_id = create_task(&mut conn, "release".to_string(), None).unwrap();
+        start_task(&mut conn, root_id.clone(), None).unwrap();
+        let child_id =
+            insert_subtask_and_start(&mut conn, root_id.clone(), "fix build".to_string()).unwrap();
+        let grandchild_id =
+            insert_subtask_and_start(&mut conn, child_id.clone(), "bump deps".to_string()).unwrap();
+        let leaf_id = insert_subtask_and_start(
+            &mut conn,
+            grandchild_id.clone(),
+            "read changelog".to_string(),
+        )
+        .unwrap();
+        let status = |conn: &Connection, id: &str| get_task_state(conn, id).unwrap().status;
+
+        stop_task(&mut conn, leaf_id.clone(), None).unwrap();
+        assert_eq!(status(&conn, &leaf_id), "stopped");
+        assert_eq!(status(&conn, &grandchild_id), "running");
+        assert_eq!(status(&conn, &child_id), "paused");
+        assert_eq!(status(&conn, &root_id), "paused");
+
+        stop_task(&mut conn, grandchild_id.clone(), None).unwrap();
+        assert_eq!(status(&conn, &child_id), "running");
+        assert_eq!(status(&conn, &root_id), "paused");
+    }
+
+    #[test]
+    fn insert_subtask_and_start_rejects_inserts_past_max_depth() {
+        let mut conn = open_test_db();
+        update_setting(
+            &mut conn,
+            "max_subtask_insert_depth".to_string(),
+            "2".to_string(),
+        )
+        .unwrap();
+        let root_id = create_task(&mut conn, "release".to_string(), None).unwrap();
+        start_task(&mut conn, root_id.clone(), None).unwrap();
+        let child_id =
+            insert_subtask_and_start(&mut conn, root_id, "fix build".to_string()).unwrap();
+        let grandchild_id =
+            insert_subtask_and_start(&mut conn, child_id, "bump deps".to_string()).unwrap();
+
+        let error = insert_subtask_and_start(&mut conn, grandchild_id.clone(), "x".to_string())
+            .unwrap_err();
+        assert_eq!(error.code, "validation");
+        assert_eq!(
+            get_task_state(&conn, &grandchild_id).unwrap().status,
+            "running"
+        );
+    }
+
+    #[test]
+    fn auto_stop_stale_paused_tasks_skips_parents_waiting_on_subtasks() {
+        let mut conn = open_test_db();
+        update_setting(
+            &mut conn,
+            "auto_stop_paused_after".to_string(),
+            "3600".to_string(),
+        )
+        .unwrap();
+        let forgotten_id = create_task(&mut conn, "draft memo".to_string(), None).unwrap();
+        let waiting_id = create_task(&mut conn, "release".to_string(), None).unwrap();
+        insert_event(&conn, &forgotten_id, "start", 1_000);
+        insert_event(&conn, &forgotten_id, "pause", 2_000);
+        insert_event(&conn, &waiting_id, "start", 1_000);
+        conn.execute(
+            "INSERT INTO time_events (task_id, event_type, ts, payload)
+             VALUES (?1, 'pause', ?2, '{\"reason\":\"insert_subtask\"}')",
+            params![waiting_id, 2_000],
+        )
+        .unwrap();
+        conn.execute("UPDATE tasks SET status = 'paused'", [])
+            .unwrap();
+
+        let stopped = auto_stop_stale_paused_tasks(&mut conn).unwrap();
+        assert_eq!(stopped.len(), 1);
+        assert_eq!(stopped[0].task_id, forgotten_id);
+        assert_eq!(stopped[0].stopped_at, 2_000);
+        assert_eq!(
+            get_task_state(&conn, &forgotten_id).unwrap().status,
+            "stopped"
+        );
+        assert_eq!(get_task_state(&conn, &waiting_id).unwrap().status, "paused");
+        assert_eq!(
+            task_total_focus_seconds(&conn, &forgotten_id, now_ts()).unwrap(),
+            1_000
+        );
+    }
+
+    #[test]
+    fn end_break_resumes_task_paused_by_break() {
+        let mut conn = open_test_db();
+        let task_id = create_task(&mut conn, "write report".to_string(), None).unwrap();
+        start_task(&mut conn, task_id.clone(), None).unwrap();
+
+        assert_eq!(start_break(&mut conn).unwrap(), Some(task_id.clone()));
+        assert_eq!(get_task_state(&conn, &task_id).unwrap().status, "paused");
+
+        assert_eq!(end_break(&mut conn).unwrap(), Some(task_id.clone()));
+        assert_eq!(get_task_state(&conn, &task_id).unwrap().status, "running");
+    }
+
+    #[test]
+    fn end_break_keeps_manual_pause() {
+        let mut conn = open_test_db();
+        let task_id = create_task(&mut conn, "write report".to_string(), None).unwrap();
+        start_task(&mut conn, task_id.clone(), None).unwrap();
+        pause_task(&mut conn, task_id.clone(), None, None).unwrap();
+
+        assert_eq!(start_break(&mut conn).unwrap(), None);
+        assert_eq!(end_break(&mut conn).unwrap(), None);
+        assert_eq!(get_task_state(&conn, &task_id).unwrap().status, "paused");
+    }
+
+    #[test]
+    fn end_break_keeps_manual_pause_made_during_break() {
+        let mut conn = open_test_db();
+        let task_id = create_task(&mut conn, "write report".to_string(), None).unwrap();
+        start_task(&mut conn, task_id.clone(), None).unwrap();
+        start_break(&mut conn).unwrap();
+        resume_task(&mut conn, task_id.clone()).unwrap();
+        pause_task(&mut conn, task_id.clone(), None, None).unwrap();
+
+        assert_eq!(end_break(&mut conn).unwrap(), None);
+        assert_eq!(get_task_state(&conn, &task_id).unwrap().status, "paused");
+    }
+
+    #[test]
+    fn begin_transaction_retries_while_database_is_locked() {
+        let path = std::env::temp_dir().join(format!("timeflies-busy-{}.db", Uuid::new_v4()));
+        let mut conn = Connection::open(&path).unwrap();
+        conn.busy_timeout(Duration::ZERO).unwrap();
+        let blocker = Connection::open(&path).unwrap();
+
+        blocker.execute_batch("BEGIN IMMEDIATE").unwrap();
+        let error = begin_transaction(&mut conn).map(|_| ()).unwrap_err();
+        assert_eq!(error.code, "busy");
+
+        let releaser = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(30));
+            blocker.execute_batch("ROLLBACK").unwrap();
+        });
+        let tx = begin_transaction(&mut conn).unwrap();
+        tx.commit().unwrap();
+        releaser.join().unwrap();
+
+        drop(conn);
+        let _ = std::fs::remove_file(&path);
+    }
+}
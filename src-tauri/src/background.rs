@@ -0,0 +1,201 @@
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::{app, domain::OverviewInvalidated, infra::AppState};
+
+pub const OVERDUE_EVENT: &str = "overdue";
+pub const WORKDAY_END_EVENT: &str = "workday_end";
+pub const PAUSED_AUTO_STOP_EVENT: &str = "paused_auto_stop";
+pub const OVERWORK_WARNING_EVENT: &str = "overwork-warning";
+pub const SLEEP_CLIPPED_EVENT: &str = "sleep_clipped";
+pub const OVERVIEW_INVALIDATED_EVENT: &str = "overview-invalidated";
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(30);
+/// Wall-clock time between ticks beyond the monotonic time is time spent suspended; more
+/// than this is treated as sleep.
+const SLEEP_GAP_SECONDS: i64 = 4 * CHECK_INTERVAL.as_secs() as i64;
+const OVERVIEW_DEBOUNCE_INTERVAL: Duration = Duration::from_millis(250);
+
+pub fn spawn_background_checks(app_handle: AppHandle) {
+    thread::spawn(move || {
+        let mut overdue_checked_until = unix_now();
+        let mut overwork_warned_on = None;
+        let mut last_seen = unix_now();
+        let mut last_seen_at = Instant::now();
+        loop {
+            thread::sleep(CHECK_INTERVAL);
+            let now = unix_now();
+            // A tick held up by a busy database advances both clocks alike; only a suspend
+            // (or a forward clock jump) moves the wall clock ahead of the monotonic one.
+            let slept_seconds = now - last_seen - last_seen_at.elapsed().as_secs() as i64;
+            if slept_seconds > SLEEP_GAP_SECONDS {
+                check_sleep_gap(&app_handle, last_seen, last_seen + slept_seconds);
+            }
+            last_seen = now;
+            last_seen_at = Instant::now();
+            check_overdue_tasks(&app_handle, &mut overdue_checked_until);
+            check_workday_end(&app_handle);
+            check_paused_timeouts(&app_handle);
+            check_overwork(&app_handle, &mut overwork_warned_on);
+        }
+    });
+}
+
+/// Coalesces mutations flagged through `AppState::mark_overview_dirty` into at most one
+/// `overview-invalidated` event per debounce interval.
+pub fn spawn_overview_debounce(app_handle: AppHandle) {
+    thread::spawn(move || loop {
+        thread::sleep(OVERVIEW_DEBOUNCE_INTERVAL);
+        let state = app_handle.state::<AppState>();
+        if !state.take_overview_dirty() {
+            continue;
+        }
+
+        let latest_event_id = {
+            let Ok(conn) = state.db.lock() else {
+                eprintln!("failed to emit overview invalidation: poisoned db mutex");
+                continue;
+            };
+            app::get_latest_event_id(&conn)
+        };
+        match latest_event_id {
+            Ok(latest_event_id) => {
+                let payload = OverviewInvalidated { latest_event_id };
+                if let Err(error) = app_handle.emit(OVERVIEW_INVALIDATED_EVENT, payload) {
+                    eprintln!("failed to emit overview invalidation: {error}");
+                }
+            }
+            Err(error) => eprintln!("failed to emit overview invalidation: {error}"),
+        }
+    });
+}
+
+fn check_overdue_tasks(app_handle: &AppHandle, checked_until: &mut i64) {
+    let now = unix_now();
+    let due_tasks = {
+        let state = app_handle.state::<AppState>();
+        let Ok(conn) = state.db.lock() else {
+            eprintln!("failed to check overdue tasks: poisoned db mutex");
+            return;
+        };
+        app::get_tasks_due_between(&conn, *checked_until, now)
+    };
+
+    match due_tasks {
+        Ok(tasks) => {
+            for task in tasks {
+                if let Err(error) = app_handle.emit(OVERDUE_EVENT, task) {
+                    eprintln!("failed to emit overdue event: {error}");
+                }
+            }
+            *checked_until = now;
+        }
+        Err(error) => eprintln!("failed to check overdue tasks: {error}"),
+    }
+}
+
+fn check_workday_end(app_handle: &AppHandle) {
+    let stopped = {
+        let state = app_handle.state::<AppState>();
+        let Ok(mut conn) = state.db.lock() else {
+            eprintln!("failed to enforce workday end: poisoned db mutex");
+            return;
+        };
+        app::enforce_workday_end(&mut conn)
+    };
+
+    match stopped {
+        Ok(Some(stop)) => {
+            app_handle.state::<AppState>().mark_overview_dirty();
+            if let Err(error) = app_handle.emit(WORKDAY_END_EVENT, stop) {
+                eprintln!("failed to emit workday end event: {error}");
+            }
+        }
+        Ok(None) => {}
+        Err(error) => eprintln!("failed to enforce workday end: {error}"),
+    }
+}
+
+fn check_paused_timeouts(app_handle: &AppHandle) {
+    let stopped = {
+        let state = app_handle.state::<AppState>();
+        let Ok(mut conn) = state.db.lock() else {
+            eprintln!("failed to auto-stop paused tasks: poisoned db mutex");
+            return;
+        };
+        app::auto_stop_stale_paused_tasks(&mut conn)
+    };
+
+    match stopped {
+        Ok(stops) if stops.is_empty() => {}
+        Ok(stops) => {
+            app_handle.state::<AppState>().mark_overview_dirty();
+            for stop in stops {
+                if let Err(error) = app_handle.emit(PAUSED_AUTO_STOP_EVENT, stop) {
+                    eprintln!("failed to emit paused auto-stop event: {error}");
+                }
+            }
+        }
+        Err(error) => eprintln!("failed to auto-stop paused tasks: {error}"),
+    }
+}
+
+/// Only fires where the monotonic clock stops during suspend (Linux, macOS); elsewhere no
+/// sleep is detected and nothing is clipped.
+fn check_sleep_gap(app_handle: &AppHandle, slept_from: i64, slept_until: i64) {
+    let clipped = {
+        let state = app_handle.state::<AppState>();
+        let Ok(mut conn) = state.db.lock() else {
+            eprintln!("failed to clip sleep gap: poisoned db mutex");
+            return;
+        };
+        app::clip_running_across_sleep(&mut conn, slept_from, slept_until)
+    };
+
+    match clipped {
+        Ok(Some(clip)) => {
+            app_handle.state::<AppState>().mark_overview_dirty();
+            if let Err(error) = app_handle.emit(SLEEP_CLIPPED_EVENT, clip) {
+                eprintln!("failed to emit sleep clip event: {error}");
+            }
+        }
+        Ok(None) => {}
+        Err(error) => eprintln!("failed to clip sleep gap: {error}"),
+    }
+}
+
+/// Emits `overwork-warning` at most once per local day, and only while a task is still
+/// running past the cap.
+fn check_overwork(app_handle: &AppHandle, warned_on: &mut Option<String>) {
+    let status = {
+        let state = app_handle.state::<AppState>();
+        let Ok(conn) = state.db.lock() else {
+            eprintln!("failed to check overwork: poisoned db mutex");
+            return;
+        };
+        app::get_overwork_status(&conn)
+    };
+
+    match status {
+        Ok(status) if status.exceeded && status.running_task_id.is_some() => {
+            if warned_on.as_deref() == Some(status.date_key.as_str()) {
+                return;
+            }
+            *warned_on = Some(status.date_key.clone());
+            if let Err(error) = app_handle.emit(OVERWORK_WARNING_EVENT, status) {
+                eprintln!("failed to emit overwork warning: {error}");
+            }
+        }
+        Ok(_) => {}
+        Err(error) => eprintln!("failed to check overwork: {error}"),
+    }
+}
+
+fn unix_now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs() as i64)
+        .unwrap_or(0)
+}
@@ -5,8 +5,20 @@ use tauri::State;
 
 use crate::app;
 use crate::command_catalog::{load_builtin_command_catalog, CommandCatalog};
-use crate::domain::{FocusSummaryResponse, OverviewResponse};
-use crate::infra::{AppError, AppResult, AppState};
+use crate::domain::{
+    BillableReport, BreakAdherence, CompletionRateResponse, CreatedSubtasks, CsvImportReport,
+    DebugIntervalsResponse, DescendantRecord, DuplicateTasksResponse, FocusDensityResponse,
+    FocusSummaryResponse, FragmentationResponse, InProgressTaskRecord, InclusiveExcludingResponse,
+    InclusiveOverlapWarning, MidnightCrossingSession, OverdueTaskRecord, OverviewDelta,
+    OverviewOptions, OverviewResponse, OverviewSnapshot, OverviewSnapshotSummary, OverworkStatus,
+    ProjectsResponse, RestPreview, RestRulesConfig, RestSignals, RestSimulationResponse,
+    RestSuggestionRecord, RollingAverageResponse, SettingRecord, StaleTaskRecord, StatusCounts,
+    SummaryCardData, TagDailyTotalsResponse, TagDiscrepancy, TagRecord, TagSetTotalsResponse,
+    TagSuggestion, TaskDetail, TaskEventsPage, TaskRecord, TaskSubtreeExport, TaskTotalsResponse,
+    TaskTreeResponse, TaskUpdate, TimeBreakdownResponse, TimeEventsPage, TotalsDrift, UntrackedGap,
+    UntrackedGapsResponse, WeeklySummary, WorkBoundsResponse,
+};
+use crate::infra::{self, AppError, AppResult, AppState, MigrationStatus, StorageStatus};
 
 fn lock_db<'a>(state: &'a State<'_, AppState>) -> AppResult<MutexGuard<'a, Connection>> {
     state
@@ -15,11 +27,42 @@ fn lock_db<'a>(state: &'a State<'_, AppState>) -> AppResult<MutexGuard<'a, Conne
         .map_err(|_| AppError::internal("failed to lock database state", "poisoned mutex"))
 }
 
+fn mutate_db<T>(
+    state: &State<'_, AppState>,
+    mutation: impl FnOnce(&mut Connection) -> AppResult<T>,
+) -> AppResult<T> {
+    let mut conn = lock_db(state)?;
+    let result = mutation(&mut conn)?;
+    state.mark_overview_dirty();
+    Ok(result)
+}
+
 #[tauri::command]
 pub fn ping() -> String {
     "pong".to_string()
 }
 
+#[tauri::command]
+pub fn get_storage_status(state: State<'_, AppState>) -> AppResult<StorageStatus> {
+    state.storage_status()
+}
+
+#[tauri::command]
+pub fn relocate_database(state: State<'_, AppState>, new_path: String) -> AppResult<StorageStatus> {
+    state.relocate_database(&new_path)
+}
+
+#[tauri::command]
+pub fn pending_migrations(state: State<'_, AppState>) -> AppResult<Vec<i64>> {
+    Ok(state.startup_migrations())
+}
+
+#[tauri::command]
+pub fn get_migration_status(state: State<'_, AppState>) -> AppResult<MigrationStatus> {
+    let conn = lock_db(&state)?;
+    infra::get_migration_status(&conn)
+}
+
 #[tauri::command]
 pub fn get_command_catalog() -> AppResult<CommandCatalog> {
     load_builtin_command_catalog()
@@ -29,9 +72,46 @@ pub fn get_command_catalog() -> AppResult<CommandCatalog> {
 pub fn get_overview(
     state: State<'_, AppState>,
     range: Option<String>,
+    options: Option<OverviewOptions>,
 ) -> AppResult<OverviewResponse> {
     let conn = lock_db(&state)?;
-    app::get_overview(&conn, range)
+    app::get_overview(&conn, range, options.unwrap_or_default())
+}
+
+#[tauri::command]
+pub fn get_status_counts(state: State<'_, AppState>) -> AppResult<StatusCounts> {
+    let conn = lock_db(&state)?;
+    app::get_status_counts(&conn)
+}
+
+#[tauri::command]
+pub fn get_overview_since(
+    state: State<'_, AppState>,
+    last_event_id: i64,
+) -> AppResult<OverviewDelta> {
+    let conn = lock_db(&state)?;
+    app::get_overview_since(&conn, last_event_id)
+}
+
+#[tauri::command]
+pub fn get_running_task(state: State<'_, AppState>) -> AppResult<Option<TaskRecord>> {
+    let conn = lock_db(&state)?;
+    app::get_running_task(&conn)
+}
+
+#[tauri::command]
+pub fn get_overwork_status(state: State<'_, AppState>) -> AppResult<OverworkStatus> {
+    let conn = lock_db(&state)?;
+    app::get_overwork_status(&conn)
+}
+
+#[tauri::command]
+pub fn get_task_tree_nested(
+    state: State<'_, AppState>,
+    range: Option<String>,
+) -> AppResult<TaskTreeResponse> {
+    let conn = lock_db(&state)?;
+    app::get_task_tree_nested(&conn, range)
 }
 
 #[tauri::command]
@@ -43,26 +123,391 @@ pub fn get_focus_summary(
     app::get_focus_summary(&conn, range)
 }
 
+#[tauri::command]
+pub fn get_tag_daily_totals(
+    state: State<'_, AppState>,
+    range: Option<String>,
+) -> AppResult<TagDailyTotalsResponse> {
+    let conn = lock_db(&state)?;
+    app::get_tag_daily_totals(&conn, range)
+}
+
+#[tauri::command]
+pub fn get_rolling_average(
+    state: State<'_, AppState>,
+    range: Option<String>,
+    window_days: i64,
+) -> AppResult<RollingAverageResponse> {
+    let conn = lock_db(&state)?;
+    app::get_rolling_average(&conn, range, window_days)
+}
+
+#[tauri::command]
+pub fn get_fragmentation(
+    state: State<'_, AppState>,
+    range: Option<String>,
+) -> AppResult<FragmentationResponse> {
+    let conn = lock_db(&state)?;
+    app::get_fragmentation(&conn, range)
+}
+
+#[tauri::command]
+pub fn get_work_bounds(
+    state: State<'_, AppState>,
+    range: Option<String>,
+) -> AppResult<WorkBoundsResponse> {
+    let conn = lock_db(&state)?;
+    app::get_work_bounds(&conn, range)
+}
+
+#[tauri::command]
+pub fn get_focus_density(
+    state: State<'_, AppState>,
+    range: Option<String>,
+) -> AppResult<FocusDensityResponse> {
+    let conn = lock_db(&state)?;
+    app::get_focus_density(&conn, range)
+}
+
+#[tauri::command]
+pub fn get_untracked_gaps(
+    state: State<'_, AppState>,
+    date: String,
+) -> AppResult<UntrackedGapsResponse> {
+    let conn = lock_db(&state)?;
+    app::get_untracked_gaps(&conn, date)
+}
+
+#[tauri::command]
+pub fn get_longest_untracked_gap(
+    state: State<'_, AppState>,
+    date: String,
+) -> AppResult<Option<UntrackedGap>> {
+    let conn = lock_db(&state)?;
+    app::get_longest_untracked_gap(&conn, date)
+}
+
+#[tauri::command]
+pub fn get_billable_report(
+    state: State<'_, AppState>,
+    range: Option<String>,
+    client: String,
+    include_zero: Option<bool>,
+) -> AppResult<BillableReport> {
+    let conn = lock_db(&state)?;
+    app::get_billable_report(&conn, range, client, include_zero.unwrap_or(false))
+}
+
+#[tauri::command]
+pub fn get_weekly_summary(state: State<'_, AppState>) -> AppResult<WeeklySummary> {
+    let conn = lock_db(&state)?;
+    app::get_weekly_summary(&conn)
+}
+
+#[tauri::command]
+pub fn get_summary_card_data(
+    state: State<'_, AppState>,
+    range: Option<String>,
+) -> AppResult<SummaryCardData> {
+    let conn = lock_db(&state)?;
+    app::get_summary_card_data(&conn, range)
+}
+
+#[tauri::command]
+pub fn get_projects(
+    state: State<'_, AppState>,
+    range: Option<String>,
+    include_zero: Option<bool>,
+) -> AppResult<ProjectsResponse> {
+    let conn = lock_db(&state)?;
+    app::get_projects(&conn, range, include_zero.unwrap_or(false))
+}
+
+#[tauri::command]
+pub fn get_container_tasks(
+    state: State<'_, AppState>,
+    range: Option<String>,
+    ratio: f64,
+) -> AppResult<Vec<TaskRecord>> {
+    let conn = lock_db(&state)?;
+    app::get_container_tasks(&conn, range, ratio)
+}
+
+#[tauri::command]
+pub fn get_time_breakdown(
+    state: State<'_, AppState>,
+    range: Option<String>,
+    include_zero: Option<bool>,
+) -> AppResult<TimeBreakdownResponse> {
+    let conn = lock_db(&state)?;
+    app::get_time_breakdown(&conn, range, include_zero.unwrap_or(false))
+}
+
+#[tauri::command]
+pub fn get_time_by_tag_set(
+    state: State<'_, AppState>,
+    range: Option<String>,
+    include_zero: Option<bool>,
+) -> AppResult<TagSetTotalsResponse> {
+    let conn = lock_db(&state)?;
+    app::get_time_by_tag_set(&conn, range, include_zero.unwrap_or(false))
+}
+
+#[tauri::command]
+pub fn get_completion_rate(
+    state: State<'_, AppState>,
+    range: Option<String>,
+) -> AppResult<CompletionRateResponse> {
+    let conn = lock_db(&state)?;
+    app::get_completion_rate(&conn, range)
+}
+
+#[tauri::command]
+pub fn get_break_adherence(
+    state: State<'_, AppState>,
+    range: Option<String>,
+) -> AppResult<BreakAdherence> {
+    let conn = lock_db(&state)?;
+    app::get_break_adherence(&conn, range)
+}
+
+#[tauri::command]
+pub fn get_totals_for(
+    state: State<'_, AppState>,
+    task_ids: Vec<String>,
+    range: Option<String>,
+) -> AppResult<TaskTotalsResponse> {
+    let conn = lock_db(&state)?;
+    app::get_totals_for(&conn, task_ids, range)
+}
+
+#[tauri::command]
+pub fn get_inclusive_excluding(
+    state: State<'_, AppState>,
+    task_id: String,
+    excluded_ids: Vec<String>,
+    range: Option<String>,
+) -> AppResult<InclusiveExcludingResponse> {
+    let conn = lock_db(&state)?;
+    app::get_inclusive_excluding(&conn, task_id, excluded_ids, range)
+}
+
+#[tauri::command]
+pub fn get_in_progress_tasks(state: State<'_, AppState>) -> AppResult<Vec<InProgressTaskRecord>> {
+    let conn = lock_db(&state)?;
+    app::get_in_progress_tasks(&conn)
+}
+
+#[tauri::command]
+pub fn find_duplicate_tasks(
+    state: State<'_, AppState>,
+    threshold: Option<i64>,
+) -> AppResult<DuplicateTasksResponse> {
+    let conn = lock_db(&state)?;
+    app::find_duplicate_tasks(&conn, threshold)
+}
+
+#[tauri::command]
+pub fn get_stale_tasks(
+    state: State<'_, AppState>,
+    min_idle_days: i64,
+) -> AppResult<Vec<StaleTaskRecord>> {
+    let conn = lock_db(&state)?;
+    app::get_stale_tasks(&conn, min_idle_days)
+}
+
+#[tauri::command]
+pub fn export_task_subtree(
+    state: State<'_, AppState>,
+    task_id: String,
+    include_archived: bool,
+    include_totals: bool,
+) -> AppResult<TaskSubtreeExport> {
+    let conn = lock_db(&state)?;
+    app::export_task_subtree(&conn, task_id, include_archived, include_totals)
+}
+
+#[tauri::command]
+pub fn verify_totals(state: State<'_, AppState>) -> AppResult<Vec<TotalsDrift>> {
+    let conn = lock_db(&state)?;
+    app::verify_totals(&conn)
+}
+
+#[tauri::command]
+pub fn debug_intervals(
+    state: State<'_, AppState>,
+    task_id: String,
+    range: Option<String>,
+) -> AppResult<DebugIntervalsResponse> {
+    let conn = lock_db(&state)?;
+    app::debug_intervals(&conn, task_id, range)
+}
+
+#[tauri::command]
+pub fn verify_tag_consistency(state: State<'_, AppState>) -> AppResult<Vec<TagDiscrepancy>> {
+    let conn = lock_db(&state)?;
+    app::verify_tag_consistency(&conn)
+}
+
+#[tauri::command]
+pub fn reconcile_tags(state: State<'_, AppState>) -> AppResult<Vec<TagDiscrepancy>> {
+    mutate_db(&state, app::reconcile_tags)
+}
+
+#[tauri::command]
+pub fn get_midnight_crossing_sessions(
+    state: State<'_, AppState>,
+    range: Option<String>,
+) -> AppResult<Vec<MidnightCrossingSession>> {
+    let conn = lock_db(&state)?;
+    app::get_midnight_crossing_sessions(&conn, range)
+}
+
+#[tauri::command]
+pub fn get_inclusive_overlap_warnings(
+    state: State<'_, AppState>,
+    range: Option<String>,
+) -> AppResult<Vec<InclusiveOverlapWarning>> {
+    let conn = lock_db(&state)?;
+    app::get_inclusive_overlap_warnings(&conn, range)
+}
+
+#[tauri::command]
+pub fn find_cycles(state: State<'_, AppState>) -> AppResult<Vec<Vec<String>>> {
+    let conn = lock_db(&state)?;
+    app::find_cycles(&conn)
+}
+
+#[tauri::command]
+pub fn break_cycle(state: State<'_, AppState>, task_id: String) -> AppResult<()> {
+    mutate_db(&state, |conn| app::break_cycle(conn, task_id))
+}
+
+#[tauri::command]
+pub fn get_overdue_tasks(state: State<'_, AppState>) -> AppResult<Vec<OverdueTaskRecord>> {
+    let conn = lock_db(&state)?;
+    app::get_overdue_tasks(&conn)
+}
+
+#[tauri::command]
+pub fn get_descendants(
+    state: State<'_, AppState>,
+    task_id: String,
+    include_root: Option<bool>,
+) -> AppResult<Vec<DescendantRecord>> {
+    let conn = lock_db(&state)?;
+    app::get_descendants(&conn, task_id, include_root.unwrap_or(false))
+}
+
+#[tauri::command]
+pub fn get_events_in_window(
+    state: State<'_, AppState>,
+    from_ts: i64,
+    to_ts: i64,
+    limit: Option<i64>,
+    after_ts: Option<i64>,
+    after_id: Option<i64>,
+) -> AppResult<TimeEventsPage> {
+    let conn = lock_db(&state)?;
+    app::get_events_in_window(&conn, from_ts, to_ts, limit, after_ts, after_id)
+}
+
+#[tauri::command]
+pub fn get_task_detail(state: State<'_, AppState>, task_id: String) -> AppResult<TaskDetail> {
+    let conn = lock_db(&state)?;
+    app::get_task_detail(&conn, task_id)
+}
+
+#[tauri::command]
+pub fn annotate_session(
+    state: State<'_, AppState>,
+    task_id: String,
+    session_start_ts: i64,
+    note: String,
+) -> AppResult<()> {
+    mutate_db(&state, |conn| {
+        app::annotate_session(conn, task_id, session_start_ts, note)
+    })
+}
+
+#[tauri::command]
+pub fn get_task_events(
+    state: State<'_, AppState>,
+    task_id: String,
+    limit: Option<i64>,
+    before_ts: Option<i64>,
+    before_id: Option<i64>,
+) -> AppResult<TaskEventsPage> {
+    let conn = lock_db(&state)?;
+    app::get_task_events(&conn, task_id, limit, before_ts, before_id)
+}
+
+#[tauri::command]
+pub fn get_pending_rest_suggestion(
+    state: State<'_, AppState>,
+) -> AppResult<Option<RestSuggestionRecord>> {
+    let conn = lock_db(&state)?;
+    app::get_pending_rest_suggestion(&conn)
+}
+
 #[tauri::command]
 pub fn create_task(
     state: State<'_, AppState>,
     title: String,
     parent_id: Option<String>,
 ) -> AppResult<String> {
-    let mut conn = lock_db(&state)?;
-    app::create_task(&mut conn, title, parent_id)
+    mutate_db(&state, |conn| app::create_task(conn, title, parent_id))
+}
+
+#[tauri::command]
+pub fn create_subtasks(
+    state: State<'_, AppState>,
+    parent_id: String,
+    titles: Vec<String>,
+) -> AppResult<CreatedSubtasks> {
+    mutate_db(&state, |conn| app::create_subtasks(conn, parent_id, titles))
 }
 
 #[tauri::command]
 pub fn rename_task(state: State<'_, AppState>, task_id: String, title: String) -> AppResult<()> {
-    let mut conn = lock_db(&state)?;
-    app::rename_task(&mut conn, task_id, title)
+    mutate_db(&state, |conn| app::rename_task(conn, task_id, title))
 }
 
 #[tauri::command]
 pub fn archive_task(state: State<'_, AppState>, task_id: String) -> AppResult<()> {
-    let mut conn = lock_db(&state)?;
-    app::archive_task(&mut conn, task_id)
+    mutate_db(&state, |conn| app::archive_task(conn, task_id))
+}
+
+#[tauri::command]
+pub fn reset_task_time(
+    state: State<'_, AppState>,
+    task_id: String,
+    include_descendants: bool,
+) -> AppResult<Vec<String>> {
+    mutate_db(&state, |conn| {
+        app::reset_task_time(conn, task_id, include_descendants)
+    })
+}
+
+#[tauri::command]
+pub fn restore_task(state: State<'_, AppState>, task_id: String) -> AppResult<()> {
+    mutate_db(&state, |conn| app::restore_task(conn, task_id))
+}
+
+#[tauri::command]
+pub fn archive_tasks_by_tag(
+    state: State<'_, AppState>,
+    tag_name: String,
+) -> AppResult<Vec<String>> {
+    mutate_db(&state, |conn| app::archive_tasks_by_tag(conn, tag_name))
+}
+
+#[tauri::command]
+pub fn restore_tasks_by_tag(
+    state: State<'_, AppState>,
+    tag_name: String,
+) -> AppResult<Vec<String>> {
+    mutate_db(&state, |conn| app::restore_tasks_by_tag(conn, tag_name))
 }
 
 #[tauri::command]
@@ -71,8 +516,9 @@ pub fn delete_tasks(
     task_ids: Vec<String>,
     hard_delete: bool,
 ) -> AppResult<()> {
-    let mut conn = lock_db(&state)?;
-    app::delete_tasks(&mut conn, task_ids, hard_delete)
+    mutate_db(&state, |conn| {
+        app::delete_tasks(conn, task_ids, hard_delete)
+    })
 }
 
 #[tauri::command]
@@ -81,32 +527,129 @@ pub fn reparent_task(
     task_id: String,
     new_parent_id: Option<String>,
 ) -> AppResult<()> {
-    let mut conn = lock_db(&state)?;
-    app::reparent_task(&mut conn, task_id, new_parent_id)
+    mutate_db(&state, |conn| {
+        app::reparent_task(conn, task_id, new_parent_id)
+    })
+}
+
+#[tauri::command]
+pub fn reparent_tasks_by_tag(
+    state: State<'_, AppState>,
+    tag_name: String,
+    new_parent_id: Option<String>,
+) -> AppResult<Vec<String>> {
+    mutate_db(&state, |conn| {
+        app::reparent_tasks_by_tag(conn, tag_name, new_parent_id)
+    })
+}
+
+#[tauri::command]
+pub fn nudge_task_order(
+    state: State<'_, AppState>,
+    task_id: String,
+    delta: i64,
+) -> AppResult<Vec<String>> {
+    mutate_db(&state, |conn| app::nudge_task_order(conn, task_id, delta))
+}
+
+#[tauri::command]
+pub fn update_task(
+    state: State<'_, AppState>,
+    task_id: String,
+    changes: TaskUpdate,
+) -> AppResult<TaskRecord> {
+    mutate_db(&state, |conn| {
+        app::update_task(conn, task_id, changes.title, changes.parent_id)
+    })
+}
+
+#[tauri::command]
+pub fn quick_start(
+    state: State<'_, AppState>,
+    title: String,
+    tags: Vec<String>,
+    parent_id: Option<String>,
+) -> AppResult<String> {
+    mutate_db(&state, |conn| {
+        app::quick_start(conn, title, tags, parent_id)
+    })
+}
+
+#[tauri::command]
+pub fn start_task(
+    state: State<'_, AppState>,
+    task_id: String,
+    at_ts: Option<i64>,
+) -> AppResult<()> {
+    mutate_db(&state, |conn| app::start_task(conn, task_id, at_ts))
 }
 
 #[tauri::command]
-pub fn start_task(state: State<'_, AppState>, task_id: String) -> AppResult<()> {
-    let mut conn = lock_db(&state)?;
-    app::start_task(&mut conn, task_id)
+pub fn pause_task(
+    state: State<'_, AppState>,
+    task_id: String,
+    reason: Option<String>,
+    at_ts: Option<i64>,
+) -> AppResult<()> {
+    mutate_db(&state, |conn| app::pause_task(conn, task_id, reason, at_ts))
+}
+
+#[tauri::command]
+pub fn start_break(state: State<'_, AppState>) -> AppResult<Option<String>> {
+    mutate_db(&state, app::start_break)
+}
+
+#[tauri::command]
+pub fn end_break(state: State<'_, AppState>) -> AppResult<Option<String>> {
+    mutate_db(&state, app::end_break)
 }
 
 #[tauri::command]
-pub fn pause_task(state: State<'_, AppState>, task_id: String) -> AppResult<()> {
-    let mut conn = lock_db(&state)?;
-    app::pause_task(&mut conn, task_id)
+pub fn log_interruption(state: State<'_, AppState>, note: Option<String>) -> AppResult<String> {
+    mutate_db(&state, |conn| app::log_interruption(conn, note))
 }
 
 #[tauri::command]
 pub fn resume_task(state: State<'_, AppState>, task_id: String) -> AppResult<()> {
-    let mut conn = lock_db(&state)?;
-    app::resume_task(&mut conn, task_id)
+    mutate_db(&state, |conn| app::resume_task(conn, task_id))
+}
+
+#[tauri::command]
+pub fn toggle_task(state: State<'_, AppState>, task_id: String) -> AppResult<String> {
+    mutate_db(&state, |conn| app::toggle_task(conn, task_id))
 }
 
 #[tauri::command]
-pub fn stop_task(state: State<'_, AppState>, task_id: String) -> AppResult<()> {
-    let mut conn = lock_db(&state)?;
-    app::stop_task(&mut conn, task_id)
+pub fn stop_task(state: State<'_, AppState>, task_id: String, at_ts: Option<i64>) -> AppResult<()> {
+    mutate_db(&state, |conn| app::stop_task(conn, task_id, at_ts))
+}
+
+#[tauri::command]
+pub fn complete_task(state: State<'_, AppState>, task_id: String) -> AppResult<()> {
+    mutate_db(&state, |conn| app::complete_task(conn, task_id))
+}
+
+#[tauri::command]
+pub fn reopen_task(state: State<'_, AppState>, task_id: String) -> AppResult<()> {
+    mutate_db(&state, |conn| app::reopen_task(conn, task_id))
+}
+
+#[tauri::command]
+pub fn set_task_due(
+    state: State<'_, AppState>,
+    task_id: String,
+    due_at: Option<i64>,
+) -> AppResult<()> {
+    mutate_db(&state, |conn| app::set_task_due(conn, task_id, due_at))
+}
+
+#[tauri::command]
+pub fn set_task_reference(
+    state: State<'_, AppState>,
+    task_id: String,
+    url: String,
+) -> AppResult<()> {
+    mutate_db(&state, |conn| app::set_task_reference(conn, task_id, url))
 }
 
 #[tauri::command]
@@ -115,8 +658,20 @@ pub fn adjust_task_focus(
     task_id: String,
     delta_seconds: i64,
 ) -> AppResult<()> {
-    let mut conn = lock_db(&state)?;
-    app::adjust_task_focus(&mut conn, task_id, delta_seconds)
+    mutate_db(&state, |conn| {
+        app::adjust_task_focus(conn, task_id, delta_seconds)
+    })
+}
+
+#[tauri::command]
+pub fn coalesce_task_sessions(
+    state: State<'_, AppState>,
+    task_id: String,
+    max_gap_seconds: i64,
+) -> AppResult<usize> {
+    mutate_db(&state, |conn| {
+        app::coalesce_task_sessions(conn, task_id, max_gap_seconds)
+    })
 }
 
 #[tauri::command]
@@ -125,8 +680,9 @@ pub fn insert_subtask_and_start(
     parent_task_id: String,
     title: String,
 ) -> AppResult<String> {
-    let mut conn = lock_db(&state)?;
-    app::insert_subtask_and_start(&mut conn, parent_task_id, title)
+    mutate_db(&state, |conn| {
+        app::insert_subtask_and_start(conn, parent_task_id, title)
+    })
 }
 
 #[tauri::command]
@@ -135,8 +691,24 @@ pub fn add_tag_to_task(
     task_id: String,
     tag_name: String,
 ) -> AppResult<()> {
-    let mut conn = lock_db(&state)?;
-    app::add_tag_to_task(&mut conn, task_id, tag_name)
+    mutate_db(&state, |conn| app::add_tag_to_task(conn, task_id, tag_name))
+}
+
+#[tauri::command]
+pub fn tag_and_view(
+    state: State<'_, AppState>,
+    task_id: String,
+    tag_name: String,
+    range: Option<String>,
+) -> AppResult<OverviewResponse> {
+    mutate_db(&state, |conn| {
+        app::tag_and_view(conn, task_id, tag_name, range)
+    })
+}
+
+#[tauri::command]
+pub fn import_csv_sessions(state: State<'_, AppState>, csv: String) -> AppResult<CsvImportReport> {
+    mutate_db(&state, |conn| app::import_csv_sessions(conn, csv))
 }
 
 #[tauri::command]
@@ -145,8 +717,46 @@ pub fn remove_tag_from_task(
     task_id: String,
     tag_name: String,
 ) -> AppResult<()> {
-    let mut conn = lock_db(&state)?;
-    app::remove_tag_from_task(&mut conn, task_id, tag_name)
+    mutate_db(&state, |conn| {
+        app::remove_tag_from_task(conn, task_id, tag_name)
+    })
+}
+
+#[tauri::command]
+pub fn list_untagged_tasks(state: State<'_, AppState>) -> AppResult<Vec<TaskRecord>> {
+    let conn = lock_db(&state)?;
+    app::list_untagged_tasks(&conn)
+}
+
+#[tauri::command]
+pub fn suggest_tags(
+    state: State<'_, AppState>,
+    task_id: String,
+    limit: Option<i64>,
+) -> AppResult<Vec<TagSuggestion>> {
+    let conn = lock_db(&state)?;
+    app::suggest_tags(&conn, task_id, limit)
+}
+
+#[tauri::command]
+pub fn set_tag_color(
+    state: State<'_, AppState>,
+    tag_name: String,
+    color: Option<String>,
+) -> AppResult<()> {
+    mutate_db(&state, |conn| app::set_tag_color(conn, tag_name, color))
+}
+
+#[tauri::command]
+pub fn get_top_tags(state: State<'_, AppState>, limit: Option<i64>) -> AppResult<Vec<TagRecord>> {
+    let conn = lock_db(&state)?;
+    app::get_top_tags(&conn, limit)
+}
+
+#[tauri::command]
+pub fn get_tags_by_key(state: State<'_, AppState>, key: String) -> AppResult<Vec<TagRecord>> {
+    let conn = lock_db(&state)?;
+    app::get_tags_by_key(&conn, key)
 }
 
 #[tauri::command]
@@ -155,7 +765,80 @@ pub fn respond_rest_suggestion(
     suggestion_id: i64,
     accept: bool,
 ) -> AppResult<()> {
-    let mut conn = lock_db(&state)?;
-    app::respond_rest_suggestion(&mut conn, suggestion_id, accept)
+    mutate_db(&state, |conn| {
+        app::respond_rest_suggestion(conn, suggestion_id, accept)
+    })
+}
+
+#[tauri::command]
+pub fn respond_all_pending_rest_suggestions(
+    state: State<'_, AppState>,
+    accept: bool,
+) -> AppResult<usize> {
+    mutate_db(&state, |conn| {
+        app::respond_all_pending_rest_suggestions(conn, accept)
+    })
+}
+
+#[tauri::command]
+pub fn accept_rest_suggestion_with_minutes(
+    state: State<'_, AppState>,
+    suggestion_id: i64,
+    minutes: i64,
+) -> AppResult<()> {
+    mutate_db(&state, |conn| {
+        app::accept_rest_suggestion_with_minutes(conn, suggestion_id, minutes)
+    })
+}
+
+#[tauri::command]
+pub fn get_rest_signals(state: State<'_, AppState>) -> AppResult<RestSignals> {
+    let conn = lock_db(&state)?;
+    app::get_rest_signals(&conn)
+}
+
+#[tauri::command]
+pub fn preview_current_rest(state: State<'_, AppState>) -> AppResult<RestPreview> {
+    let conn = lock_db(&state)?;
+    app::preview_current_rest(&conn)
+}
+
+#[tauri::command]
+pub fn simulate_rest_rules(
+    state: State<'_, AppState>,
+    config: Option<RestRulesConfig>,
+    range: Option<String>,
+) -> AppResult<RestSimulationResponse> {
+    let conn = lock_db(&state)?;
+    app::simulate_rest_rules(&conn, config.unwrap_or_default(), range)
+}
+
+#[tauri::command]
+pub fn get_settings(state: State<'_, AppState>) -> AppResult<Vec<SettingRecord>> {
+    let conn = lock_db(&state)?;
+    app::get_settings(&conn)
+}
+
+#[tauri::command]
+pub fn rebuild_baselines(state: State<'_, AppState>) -> AppResult<usize> {
+    mutate_db(&state, app::rebuild_baselines)
+}
+
+#[tauri::command]
+pub fn snapshot_overview(state: State<'_, AppState>) -> AppResult<OverviewSnapshotSummary> {
+    mutate_db(&state, app::snapshot_overview)
+}
+
+#[tauri::command]
+pub fn get_snapshots(
+    state: State<'_, AppState>,
+    task_id: String,
+) -> AppResult<Vec<OverviewSnapshot>> {
+    let conn = lock_db(&state)?;
+    app::get_snapshots(&conn, task_id)
+}
+
+#[tauri::command]
+pub fn update_setting(state: State<'_, AppState>, key: String, value: String) -> AppResult<()> {
+    mutate_db(&state, |conn| app::update_setting(conn, key, value))
 }
-
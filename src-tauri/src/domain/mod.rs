@@ -1,4 +1,6 @@
-use serde::Serialize;
+use std::collections::HashMap;
+
+use serde::{Deserialize, Deserializer, Serialize};
 
 #[derive(Debug, Clone, Serialize)]
 pub struct TaskRecord {
@@ -7,10 +9,19 @@ pub struct TaskRecord {
     pub title: String,
     pub status: String,
     pub created_at: i64,
+    pub completed_at: Option<i64>,
+    pub due_at: Option<i64>,
+    pub reference_url: Option<String>,
+    /// Position among siblings once they have been reordered by hand, else `None`.
+    pub sort_order: Option<i64>,
     pub last_activated_at: Option<i64>,
-    pub tags: Vec<String>,
+    pub tags: Vec<TagRef>,
     pub inclusive_seconds: i64,
     pub exclusive_seconds: i64,
+    /// Only filled when the overview is requested with `precision: "millis"`.
+    pub inclusive_millis: Option<i64>,
+    pub exclusive_millis: Option<i64>,
+    pub has_tracked_descendants: bool,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -18,6 +29,7 @@ pub struct RestSuggestionRecord {
     pub id: i64,
     pub trigger_type: String,
     pub task_id: Option<String>,
+    pub task_title: Option<String>,
     pub focus_seconds: i64,
     pub switch_count_30m: i64,
     pub deviation_ratio: f64,
@@ -27,6 +39,72 @@ pub struct RestSuggestionRecord {
     pub created_at: i64,
 }
 
+#[derive(Debug, Clone, Serialize)]
+pub struct TagRecord {
+    pub name: String,
+    pub key: Option<String>,
+    pub value: Option<String>,
+    pub color: Option<String>,
+    pub task_count: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TagRef {
+    pub name: String,
+    pub color: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TagSuggestion {
+    pub name: String,
+    pub co_occurrences: i64,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct OverviewOptions {
+    pub tag: Option<String>,
+    pub exclude_completed: bool,
+    pub sort: Option<String>,
+    pub time_basis: Option<String>,
+    pub only_active_in_window: bool,
+    pub as_of: Option<i64>,
+    pub precision: Option<String>,
+    pub collapse_completed: bool,
+}
+
+/// Overrides for `simulate_rest_rules`; unset fields fall back to the saved settings.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct RestRulesConfig {
+    pub minute_tiers: Option<String>,
+}
+
+/// Changes for `update_task`. An absent `parent_id` leaves the parent untouched while an
+/// explicit `null` moves the task to the root.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct TaskUpdate {
+    pub title: Option<String>,
+    #[serde(deserialize_with = "deserialize_present")]
+    pub parent_id: Option<Option<String>>,
+}
+
+fn deserialize_present<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    T::deserialize(deserializer).map(Some)
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SettingRecord {
+    pub key: String,
+    pub value: String,
+    pub is_default: bool,
+}
+
 #[derive(Debug, Clone, Serialize)]
 pub struct NotificationRecord {
     pub id: i64,
@@ -42,13 +120,18 @@ pub struct NotificationRecord {
 
 #[derive(Debug, Clone, Serialize)]
 pub struct OverviewResponse {
+    pub schema_version: u32,
     pub range: String,
+    pub time_basis: String,
+    pub precision: String,
     pub generated_at: i64,
     pub active_task_id: Option<String>,
     pub last_used_task_id: Option<String>,
     pub rest_suggestion: Option<RestSuggestionRecord>,
     pub notifications: Vec<NotificationRecord>,
     pub tasks: Vec<TaskRecord>,
+    /// Ancestors included only to keep the tree connected under `only_active_in_window`.
+    pub context_task_ids: Vec<String>,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -88,3 +171,586 @@ pub struct FocusSummaryResponse {
     pub generated_at: i64,
     pub days: Vec<FocusSummaryDay>,
 }
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FragmentationDay {
+    pub date_key: String,
+    pub day_start_ts: i64,
+    pub day_end_ts: i64,
+    pub switch_count: i64,
+    pub interruption_count: i64,
+    pub focus_block_count: i64,
+    pub average_block_seconds: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FragmentationResponse {
+    pub range: String,
+    pub generated_at: i64,
+    pub days: Vec<FragmentationDay>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct WeeklyTopTask {
+    pub task_id: String,
+    pub title: String,
+    pub exclusive_seconds: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct WeeklySummary {
+    pub range_start: i64,
+    pub range_end: i64,
+    pub total_focus_seconds: i64,
+    pub top_tasks: Vec<WeeklyTopTask>,
+    pub breaks_accepted: i64,
+    pub breaks_ignored: i64,
+    pub most_fragmented_day: Option<FragmentationDay>,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SummaryCardData {
+    pub range: String,
+    pub range_start: i64,
+    pub range_end: i64,
+    pub total_focus_seconds: i64,
+    pub total_hours: f64,
+    pub top_task: Option<WeeklyTopTask>,
+    pub streak_days: i64,
+    pub breaks_taken: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BreakAdherence {
+    pub range: String,
+    pub range_start: i64,
+    pub range_end: i64,
+    pub generated_at: i64,
+    /// Answered suggestions that proposed a break; pending ones are left out.
+    pub suggestion_count: i64,
+    pub accepted_count: i64,
+    pub suggested_minutes: i64,
+    pub accepted_minutes: i64,
+    /// `accepted_minutes / suggested_minutes`, or `None` without any suggestions.
+    pub adherence_ratio: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BillableTaskLine {
+    pub task_id: String,
+    pub title: String,
+    pub seconds: i64,
+    pub invoice_line: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BillableReport {
+    pub range: String,
+    pub client: String,
+    pub generated_at: i64,
+    pub matched_by: String,
+    pub tasks: Vec<BillableTaskLine>,
+    pub total_seconds: i64,
+    pub invoice_line: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CompletionRateResponse {
+    pub range: String,
+    pub generated_at: i64,
+    pub created_count: i64,
+    pub completed_count: i64,
+    pub median_seconds_to_complete: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OverdueTaskRecord {
+    pub task_id: String,
+    pub title: String,
+    pub due_at: i64,
+    pub overdue_seconds: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct WorkdayEndStop {
+    pub task_id: String,
+    pub title: String,
+    pub stopped_at: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SleepClip {
+    pub task_id: String,
+    /// Last heartbeat before the machine slept; the running session is paused here.
+    pub slept_from: i64,
+    /// Wake-up time, where the session is resumed.
+    pub slept_until: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PausedAutoStop {
+    pub task_id: String,
+    pub title: String,
+    pub stopped_at: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OverworkStatus {
+    pub date_key: String,
+    pub day_start_ts: i64,
+    pub total_seconds: i64,
+    pub daily_max_seconds: i64,
+    pub exceeded: bool,
+    pub running_task_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TaskTotals {
+    pub inclusive_seconds: i64,
+    pub exclusive_seconds: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TaskTotalsResponse {
+    pub range: String,
+    pub generated_at: i64,
+    pub totals: HashMap<String, TaskTotals>,
+    pub skipped_archived_ids: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct InclusiveExcludingResponse {
+    pub task_id: String,
+    pub range: String,
+    pub generated_at: i64,
+    pub inclusive_seconds: i64,
+    pub excluded_seconds: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OverviewInvalidated {
+    pub latest_event_id: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StaleTaskRecord {
+    pub task_id: String,
+    pub title: String,
+    pub last_worked_at: Option<i64>,
+    pub idle_seconds: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DuplicateTaskCandidate {
+    pub task_id: String,
+    pub title: String,
+    pub parent_id: Option<String>,
+    pub created_at: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DuplicateTaskGroup {
+    /// Normalized title of the oldest task in the group.
+    pub normalized_title: String,
+    pub tasks: Vec<DuplicateTaskCandidate>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DuplicateTasksResponse {
+    pub threshold: i64,
+    /// Set when the comparison or group cap cut the scan short.
+    pub truncated: bool,
+    pub groups: Vec<DuplicateTaskGroup>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct InProgressTaskRecord {
+    pub task_id: String,
+    pub parent_id: Option<String>,
+    pub title: String,
+    pub status: String,
+    pub exclusive_seconds: i64,
+    pub state_since: Option<i64>,
+}
+
+/// Portable snapshot of a task subtree: structure, tags and optional totals, never raw
+/// events, so it can be shared and instantiated again as a template.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskSubtreeExport {
+    pub format_version: u32,
+    pub exported_at: i64,
+    pub root: ExportedTaskNode,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportedTaskNode {
+    pub title: String,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub archived: bool,
+    pub inclusive_seconds: Option<i64>,
+    pub exclusive_seconds: Option<i64>,
+    #[serde(default)]
+    pub children: Vec<ExportedTaskNode>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TagDiscrepancy {
+    pub task_id: String,
+    pub title: String,
+    /// Tags the event log leaves attached that have no `task_tags` row.
+    pub missing_rows: Vec<String>,
+    /// `task_tags` rows the event log does not account for.
+    pub unlogged_rows: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DebugInterval {
+    /// The session as replayed, before clipping; an open session ends at the window end.
+    pub start_ts: i64,
+    pub end_ts: i64,
+    pub clipped_start_ts: i64,
+    pub clipped_end_ts: i64,
+    pub seconds: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DebugAdjustment {
+    pub ts: i64,
+    pub delta_seconds: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DebugIntervalsResponse {
+    pub task_id: String,
+    pub range: String,
+    pub window_start: Option<i64>,
+    pub window_end: i64,
+    pub intervals: Vec<DebugInterval>,
+    pub adjustments: Vec<DebugAdjustment>,
+    /// Sum of interval seconds and adjustments; matches the task's exclusive seconds.
+    pub total_seconds: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TotalsDrift {
+    pub task_id: String,
+    pub title: String,
+    pub replayed_seconds: i64,
+    pub stored_seconds: i64,
+    pub difference_seconds: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TaskTreeNode {
+    #[serde(flatten)]
+    pub task: TaskRecord,
+    pub children: Vec<TaskTreeNode>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TaskTreeResponse {
+    pub schema_version: u32,
+    pub range: String,
+    pub generated_at: i64,
+    pub roots: Vec<TaskTreeNode>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TaskEventRecord {
+    pub id: i64,
+    pub task_id: String,
+    pub event_type: String,
+    pub ts: i64,
+    pub reason: Option<String>,
+    pub payload: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TaskEventsPage {
+    pub events: Vec<TaskEventRecord>,
+    pub has_more: bool,
+    pub next_before_ts: Option<i64>,
+    pub next_before_id: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TimeEventRecord {
+    pub id: i64,
+    pub task_id: String,
+    pub task_title: String,
+    pub event_type: String,
+    pub ts: i64,
+    pub reason: Option<String>,
+    pub payload: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TimeEventsPage {
+    pub events: Vec<TimeEventRecord>,
+    pub has_more: bool,
+    pub next_after_ts: Option<i64>,
+    pub next_after_id: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct WorkBoundsDay {
+    pub date_key: String,
+    pub day_start_ts: i64,
+    pub first_start_ts: i64,
+    pub last_end_ts: Option<i64>,
+    pub still_working: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct WorkBoundsResponse {
+    pub range: String,
+    pub generated_at: i64,
+    pub days: Vec<WorkBoundsDay>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FocusDensityDay {
+    pub date_key: String,
+    pub day_start_ts: i64,
+    pub tracked_seconds: i64,
+    /// From the day's first focused moment to its last, clipped to the day and to now.
+    pub elapsed_seconds: i64,
+    /// `tracked_seconds / elapsed_seconds`; `None` when nothing has elapsed yet.
+    pub density: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FocusDensityResponse {
+    pub range: String,
+    pub generated_at: i64,
+    pub days: Vec<FocusDensityDay>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CsvImportRowError {
+    pub line: usize,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CsvImportReport {
+    pub tasks_created: i64,
+    pub sessions_imported: i64,
+    pub skipped_rows: Vec<CsvImportRowError>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CreatedSubtasks {
+    pub task_ids: Vec<String>,
+    pub warnings: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct StatusCounts {
+    pub idle: i64,
+    pub running: i64,
+    pub paused: i64,
+    pub stopped: i64,
+    pub archived: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TimeBreakdownEntry {
+    pub task_id: String,
+    pub title: String,
+    pub inclusive_seconds: i64,
+    pub percentage: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ProjectRecord {
+    pub id: String,
+    pub title: String,
+    pub status: String,
+    pub inclusive_seconds: i64,
+    pub tags: Vec<TagRef>,
+    pub child_count: usize,
+    pub descendant_count: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ProjectsResponse {
+    pub range: String,
+    pub generated_at: i64,
+    pub projects: Vec<ProjectRecord>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TimeBreakdownResponse {
+    pub range: String,
+    pub generated_at: i64,
+    pub total_seconds: i64,
+    pub untracked_seconds: Option<i64>,
+    pub entries: Vec<TimeBreakdownEntry>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TagSetTotal {
+    pub tags: Vec<String>,
+    pub seconds: i64,
+    pub task_count: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TagSetTotalsResponse {
+    pub range: String,
+    pub generated_at: i64,
+    pub total_seconds: i64,
+    pub combinations: Vec<TagSetTotal>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct InclusiveOverlapWarning {
+    pub task_id: String,
+    pub title: String,
+    pub inclusive_seconds: i64,
+    pub window_seconds: i64,
+    pub excess_seconds: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MidnightCrossingSession {
+    pub task_id: String,
+    pub title: String,
+    /// Unclipped bounds; a session still running ends at the time of the call.
+    pub start_ts: i64,
+    pub end_ts: i64,
+    pub start_date: String,
+    pub end_date: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RestTierCount {
+    pub minutes: i64,
+    pub count: i64,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RestPreview {
+    pub task_id: Option<String>,
+    pub focus_seconds: i64,
+    pub switch_count_30m: i64,
+    pub deviation_ratio: f64,
+    pub suggested_minutes: i64,
+    pub reasons: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RestSignals {
+    pub task_id: Option<String>,
+    pub measured_at: i64,
+    pub focus_seconds: i64,
+    pub switch_count_30m: i64,
+    pub deviation_ratio: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RestSimulationResponse {
+    pub range: String,
+    pub generated_at: i64,
+    pub minute_tiers: Vec<i64>,
+    pub trigger_count: i64,
+    pub task_switch_count: i64,
+    pub subtask_end_count: i64,
+    pub prompt_count: i64,
+    pub tiers: Vec<RestTierCount>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TaskSession {
+    pub start_ts: i64,
+    pub end_ts: Option<i64>,
+    pub duration_seconds: i64,
+    pub note: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DescendantRecord {
+    pub task: TaskRecord,
+    pub depth: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TaskDetail {
+    pub task: TaskRecord,
+    pub sessions: Vec<TaskSession>,
+    /// Earliest start/resume and latest pause/stop; `None` without timing events.
+    pub first_worked_at: Option<i64>,
+    pub last_worked_at: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OverviewSnapshotSummary {
+    pub taken_at: i64,
+    pub task_count: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OverviewSnapshot {
+    pub taken_at: i64,
+    pub inclusive_seconds: i64,
+    pub exclusive_seconds: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OverviewDelta {
+    pub latest_event_id: Option<i64>,
+    pub full_overview: Option<OverviewResponse>,
+    pub changed_tasks: Vec<TaskRecord>,
+    pub removed_task_ids: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TagDailyTotal {
+    pub date_key: String,
+    pub day_start_ts: i64,
+    pub tag_name: String,
+    pub seconds: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TagDailyTotalsResponse {
+    pub range: String,
+    pub generated_at: i64,
+    pub rows: Vec<TagDailyTotal>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RollingAverageDay {
+    pub date_key: String,
+    pub day_start_ts: i64,
+    pub total_seconds: i64,
+    pub average_seconds: i64,
+    pub sample_days: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RollingAverageResponse {
+    pub range: String,
+    pub generated_at: i64,
+    pub window_days: i64,
+    pub days: Vec<RollingAverageDay>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct UntrackedGap {
+    pub start_ts: i64,
+    pub end_ts: i64,
+    pub seconds: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct UntrackedGapsResponse {
+    pub date_key: String,
+    pub day_start_ts: i64,
+    pub total_seconds: i64,
+    pub gaps: Vec<UntrackedGap>,
+}
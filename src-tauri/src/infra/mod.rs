@@ -1,3 +1,8 @@
 mod sqlite;
 
-pub use sqlite::{AppError, AppResult, AppState};
+pub use sqlite::{
+    get_migration_status, AppError, AppResult, AppState, MigrationStatus, StorageStatus,
+};
+
+#[cfg(test)]
+pub(crate) use sqlite::run_migrations;
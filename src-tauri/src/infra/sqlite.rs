@@ -1,17 +1,32 @@
 use std::fs;
-use std::sync::Mutex;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, MutexGuard};
+use std::time::{SystemTime, UNIX_EPOCH};
 
-use rusqlite::Connection;
+use rusqlite::{params, Connection, ErrorCode};
 use serde::Serialize;
 use tauri::{AppHandle, Manager};
 
 pub type AppResult<T> = Result<T, AppError>;
 
+/// Version written by the last migration in `run_migrations`; bump together with it.
+const LATEST_SCHEMA_VERSION: i64 = 23;
+
+const DATABASE_FILE_NAME: &str = "timeflies.db";
+const DATABASE_LOCATION_FILE_NAME: &str = "database_location";
+const STORAGE_PERMISSION_DENIED: &str = "storage_permission_denied";
+const STORAGE_DISK_FULL: &str = "storage_disk_full";
+
 #[derive(Debug, Clone, Serialize)]
 pub struct AppError {
     pub code: String,
     pub message: String,
     pub detail: Option<String>,
+    /// Set on conflicts caused by another task already running.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub running_task_id: Option<String>,
 }
 
 impl std::fmt::Display for AppError {
@@ -30,6 +45,7 @@ impl AppError {
             code: "validation".to_string(),
             message: message.into(),
             detail: None,
+            running_task_id: None,
         }
     }
 
@@ -38,6 +54,23 @@ impl AppError {
             code: "conflict".to_string(),
             message: message.into(),
             detail: None,
+            running_task_id: None,
+        }
+    }
+
+    pub fn task_running(message: impl Into<String>, running_task_id: impl Into<String>) -> Self {
+        Self {
+            running_task_id: Some(running_task_id.into()),
+            ..Self::conflict(message)
+        }
+    }
+
+    pub fn busy(detail: impl Into<String>) -> Self {
+        Self {
+            code: "busy".to_string(),
+            message: "database is busy, please try again".to_string(),
+            detail: Some(detail.into()),
+            running_task_id: None,
         }
     }
 
@@ -46,6 +79,7 @@ impl AppError {
             code: "not_found".to_string(),
             message: message.into(),
             detail: None,
+            running_task_id: None,
         }
     }
 
@@ -54,15 +88,46 @@ impl AppError {
             code: "internal".to_string(),
             message: message.into(),
             detail: Some(detail.into()),
+            running_task_id: None,
         }
     }
 }
 
+#[derive(Debug, Clone, Serialize)]
+pub struct StorageStatus {
+    pub data_dir: String,
+    pub db_path: String,
+    pub using_fallback: bool,
+    pub fallback_reason: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AppliedMigration {
+    pub version: i64,
+    /// Missing for versions applied before migrations were tracked.
+    pub applied_at: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MigrationStatus {
+    pub user_version: i64,
+    pub latest_version: i64,
+    pub applied: Vec<AppliedMigration>,
+    pub pending: Vec<i64>,
+}
+
 pub struct AppState {
     pub db: Mutex<Connection>,
+    overview_dirty: AtomicBool,
+    app_data_dir: PathBuf,
+    storage: Mutex<StorageStatus>,
+    startup_migrations: Vec<i64>,
 }
 
 impl AppState {
+    /// Opens the database in the app data directory, or wherever `relocate_database` moved
+    /// it. When that location is read-only or full, falls back to a temporary directory and
+    /// records why in `storage_status`.
     pub fn initialize(app: &AppHandle) -> AppResult<Self> {
         let app_data_dir = app
             .path()
@@ -73,62 +138,402 @@ impl AppState {
                     format!("failed to resolve app data directory: {error}"),
                 )
             })?;
+        let db_path = read_database_location(&app_data_dir)
+            .unwrap_or_else(|| app_data_dir.join(DATABASE_FILE_NAME));
 
-        fs::create_dir_all(&app_data_dir).map_err(|error| {
-            AppError::internal(
-                "failed to create app data directory",
-                format!(
-                "failed to create app data directory {}: {error}",
-                app_data_dir.display()
-                ),
+        match open_database(&db_path) {
+            Ok((connection, migrations)) => Ok(Self::with_connection(
+                connection,
+                migrations,
+                app_data_dir,
+                &db_path,
+                None,
+            )),
+            Err(error) if is_storage_unavailable(&error) => {
+                let fallback_path = std::env::temp_dir()
+                    .join("timeflies")
+                    .join(DATABASE_FILE_NAME);
+                let (connection, migrations) =
+                    open_database(&fallback_path).map_err(|_| error.clone())?;
+                eprintln!(
+                    "data directory unavailable, using temporary storage at {}: {error}",
+                    fallback_path.display()
+                );
+                Ok(Self::with_connection(
+                    connection,
+                    migrations,
+                    app_data_dir,
+                    &fallback_path,
+                    Some(error.message),
+                ))
+            }
+            Err(error) => Err(error),
+        }
+    }
+
+    fn with_connection(
+        connection: Connection,
+        startup_migrations: Vec<i64>,
+        app_data_dir: PathBuf,
+        db_path: &Path,
+        fallback_reason: Option<String>,
+    ) -> Self {
+        Self {
+            db: Mutex::new(connection),
+            overview_dirty: AtomicBool::new(false),
+            app_data_dir,
+            storage: Mutex::new(StorageStatus {
+                data_dir: database_dir(db_path).display().to_string(),
+                db_path: db_path.display().to_string(),
+                using_fallback: fallback_reason.is_some(),
+                fallback_reason,
+            }),
+            startup_migrations,
+        }
+    }
+
+    pub fn storage_status(&self) -> AppResult<StorageStatus> {
+        Ok(self.lock_storage()?.clone())
+    }
+
+    /// Migration versions that were pending when the database was opened at startup, and
+    /// were applied before any command could run. Empty when the schema was already current.
+    pub fn startup_migrations(&self) -> Vec<i64> {
+        self.startup_migrations.clone()
+    }
+
+    /// Moves the database to `new_path`: copies it there, opens the copy, records the new
+    /// location, and only then swaps connections and removes the old files. A failure at
+    /// any step leaves the current database in place. The location is remembered in a
+    /// pointer file next to the default database, since the settings table travels with
+    /// the file being moved.
+    pub fn relocate_database(&self, new_path: &str) -> AppResult<StorageStatus> {
+        let new_path = PathBuf::from(new_path.trim());
+        if !new_path.is_absolute() || new_path.file_name().is_none() {
+            return Err(AppError::validation(
+                "new_path must be an absolute path to a database file",
+            ));
+        }
+
+        let mut conn = self
+            .db
+            .lock()
+            .map_err(|_| AppError::internal("failed to lock database state", "poisoned mutex"))?;
+        let mut storage = self.lock_storage()?;
+        let old_path = PathBuf::from(&storage.db_path);
+        if new_path == old_path {
+            return Err(AppError::validation("database is already at that location"));
+        }
+        if database_files(&new_path).iter().any(|path| path.exists()) {
+            return Err(AppError::conflict(format!(
+                "{} already contains a database",
+                new_path.display()
+            )));
+        }
+        ensure_directory_writable(database_dir(&new_path))?;
+
+        let copied = copy_database(&conn, &new_path)
+            .and_then(|()| open_database(&new_path))
+            .and_then(|(new_conn, _)| {
+                write_database_location(&self.app_data_dir, &new_path)?;
+                Ok(new_conn)
+            });
+        let new_conn = match copied {
+            Ok(new_conn) => new_conn,
+            Err(error) => {
+                remove_database_files(&new_path);
+                return Err(error);
+            }
+        };
+
+        if let Err((_, error)) = std::mem::replace(&mut *conn, new_conn).close() {
+            eprintln!(
+                "failed to close old database {}: {error}",
+                old_path.display()
+            );
+        }
+        remove_database_files(&old_path);
+
+        storage.data_dir = database_dir(&new_path).display().to_string();
+        storage.db_path = new_path.display().to_string();
+        Ok(storage.clone())
+    }
+
+    fn lock_storage(&self) -> AppResult<MutexGuard<'_, StorageStatus>> {
+        self.storage
+            .lock()
+            .map_err(|_| AppError::internal("failed to lock storage state", "poisoned mutex"))
+    }
+
+    pub fn mark_overview_dirty(&self) {
+        self.overview_dirty.store(true, Ordering::Release);
+    }
+
+    pub fn take_overview_dirty(&self) -> bool {
+        self.overview_dirty.swap(false, Ordering::AcqRel)
+    }
+}
+
+fn read_database_location(app_data_dir: &Path) -> Option<PathBuf> {
+    let raw = fs::read_to_string(app_data_dir.join(DATABASE_LOCATION_FILE_NAME)).ok()?;
+    let path = PathBuf::from(raw.trim());
+    path.is_absolute().then_some(path)
+}
+
+fn database_dir(db_path: &Path) -> &Path {
+    db_path.parent().unwrap_or(db_path)
+}
+
+/// The database file followed by its `-wal` and `-shm` sidecars.
+fn database_files(db_path: &Path) -> [PathBuf; 3] {
+    let sidecar = |suffix: &str| {
+        let mut name = db_path.as_os_str().to_owned();
+        name.push(suffix);
+        PathBuf::from(name)
+    };
+    [db_path.to_path_buf(), sidecar("-wal"), sidecar("-shm")]
+}
+
+fn ensure_directory_writable(dir: &Path) -> AppResult<()> {
+    let probe = dir.join(".timeflies-write-test");
+    fs::create_dir_all(dir)
+        .and_then(|_| fs::write(&probe, b""))
+        .and_then(|_| fs::remove_file(&probe))
+        .map_err(|error| {
+            io_storage_error(
+                dir,
+                "destination is not writable",
+                format!("failed to write to {}: {error}", dir.display()),
+                &error,
             )
-        })?;
+        })
+}
+
+/// Writes a consistent copy of the open database to `target`, WAL contents included.
+fn copy_database(connection: &Connection, target: &Path) -> AppResult<()> {
+    connection
+        .execute(
+            "VACUUM INTO ?1",
+            params![target.to_string_lossy().into_owned()],
+        )
+        .map(|_| ())
+        .map_err(|error| {
+            sqlite_storage_error(
+                database_dir(target),
+                "failed to copy database",
+                format!("failed to copy database to {}: {error}", target.display()),
+                &error,
+            )
+        })
+}
+
+/// Replaces the location pointer through a rename so a failed write never leaves it
+/// half-written or pointing at the wrong file.
+fn write_database_location(app_data_dir: &Path, db_path: &Path) -> AppResult<()> {
+    let target = app_data_dir.join(DATABASE_LOCATION_FILE_NAME);
+    let staging = app_data_dir.join(format!("{DATABASE_LOCATION_FILE_NAME}.tmp"));
+    fs::create_dir_all(app_data_dir)
+        .and_then(|_| fs::write(&staging, db_path.display().to_string()))
+        .and_then(|_| fs::rename(&staging, &target))
+        .map_err(|error| {
+            let _ = fs::remove_file(&staging);
+            io_storage_error(
+                app_data_dir,
+                "failed to save database location",
+                format!("failed to save database location: {error}"),
+                &error,
+            )
+        })
+}
+
+fn remove_database_files(db_path: &Path) {
+    for path in database_files(db_path) {
+        match fs::remove_file(&path) {
+            Err(error) if error.kind() != ErrorKind::NotFound => {
+                eprintln!("failed to remove {}: {error}", path.display());
+            }
+            _ => {}
+        }
+    }
+}
 
-        let db_path = app_data_dir.join("timeflies.db");
-        let connection = Connection::open(&db_path).map_err(|error| {
+/// Opens and migrates the database at `db_path`, returning the connection together with
+/// the migration versions that were applied.
+fn open_database(db_path: &Path) -> AppResult<(Connection, Vec<i64>)> {
+    let data_dir = database_dir(db_path);
+    fs::create_dir_all(data_dir).map_err(|error| {
+        io_storage_error(
+            data_dir,
+            "failed to create app data directory",
+            format!(
+                "failed to create app data directory {}: {error}",
+                data_dir.display()
+            ),
+            &error,
+        )
+    })?;
+
+    let connection = Connection::open(db_path).map_err(|error| {
+        sqlite_storage_error(
+            data_dir,
+            "failed to open sqlite database",
+            format!(
+                "failed to open sqlite database {}: {error}",
+                db_path.display()
+            ),
+            &error,
+        )
+    })?;
+
+    connection
+        .pragma_update(None, "foreign_keys", "ON")
+        .map_err(|error| {
             AppError::internal(
-                "failed to open sqlite database",
+                "failed to initialize sqlite",
+                format!("failed to enable sqlite foreign_keys pragma: {error}"),
+            )
+        })?;
+    connection
+        .pragma_update(None, "journal_mode", "WAL")
+        .map_err(|error| {
+            sqlite_storage_error(
+                data_dir,
+                "failed to initialize sqlite",
+                format!("failed to enable sqlite WAL mode: {error}"),
+                &error,
+            )
+        })?;
+    // A read-only database file opens fine; take a write lock so that surfaces here
+    // instead of as a migration failure.
+    connection
+        .execute_batch("BEGIN IMMEDIATE; ROLLBACK;")
+        .map_err(|error| {
+            sqlite_storage_error(
+                data_dir,
+                "failed to initialize sqlite",
                 format!(
-                    "failed to open sqlite database {}: {error}",
+                    "sqlite database {} is not writable: {error}",
                     db_path.display()
                 ),
+                &error,
             )
         })?;
 
-        connection
-            .pragma_update(None, "foreign_keys", "ON")
-            .map_err(|error| {
-                AppError::internal(
-                    "failed to initialize sqlite",
-                    format!("failed to enable sqlite foreign_keys pragma: {error}"),
-                )
-            })?;
-        connection
-            .pragma_update(None, "journal_mode", "WAL")
-            .map_err(|error| {
-                AppError::internal(
-                    "failed to initialize sqlite",
-                    format!("failed to enable sqlite WAL mode: {error}"),
-                )
-            })?;
+    let pending = pending_migrations(&connection)?;
+    run_migrations(&connection)?;
+    Ok((connection, pending))
+}
+
+fn is_storage_unavailable(error: &AppError) -> bool {
+    error.code == STORAGE_PERMISSION_DENIED || error.code == STORAGE_DISK_FULL
+}
 
-        run_migrations(&connection)?;
+fn io_storage_error(
+    data_dir: &Path,
+    message: &str,
+    detail: String,
+    error: &std::io::Error,
+) -> AppError {
+    match error.kind() {
+        ErrorKind::PermissionDenied | ErrorKind::ReadOnlyFilesystem => {
+            storage_permission_error(data_dir, detail)
+        }
+        ErrorKind::StorageFull => storage_full_error(data_dir, detail),
+        _ => AppError::internal(message, detail),
+    }
+}
 
-        Ok(Self {
-            db: Mutex::new(connection),
-        })
+fn sqlite_storage_error(
+    data_dir: &Path,
+    message: &str,
+    detail: String,
+    error: &rusqlite::Error,
+) -> AppError {
+    match error.sqlite_error_code() {
+        Some(ErrorCode::ReadOnly | ErrorCode::PermissionDenied | ErrorCode::CannotOpen) => {
+            storage_permission_error(data_dir, detail)
+        }
+        Some(ErrorCode::DiskFull) => storage_full_error(data_dir, detail),
+        _ => AppError::internal(message, detail),
+    }
+}
+
+fn storage_permission_error(data_dir: &Path, detail: String) -> AppError {
+    AppError {
+        code: STORAGE_PERMISSION_DENIED.to_string(),
+        message: format!(
+            "cannot write to data directory {}; check its permissions or move it off read-only storage",
+            data_dir.display()
+        ),
+        detail: Some(detail),
+        running_task_id: None,
+    }
+}
+
+fn storage_full_error(data_dir: &Path, detail: String) -> AppError {
+    AppError {
+        code: STORAGE_DISK_FULL.to_string(),
+        message: format!(
+            "the disk holding data directory {} is full; free some space and restart",
+            data_dir.display()
+        ),
+        detail: Some(detail),
+        running_task_id: None,
+    }
+}
+
+/// Migration versions `run_migrations` would apply to this database, oldest first. Read-only.
+fn pending_migrations(connection: &Connection) -> AppResult<Vec<i64>> {
+    let current_version = read_user_version(connection)?;
+    Ok((current_version + 1..=LATEST_SCHEMA_VERSION).collect())
+}
+
+/// Current `user_version` plus when each migration was applied, from `schema_migrations`.
+pub fn get_migration_status(connection: &Connection) -> AppResult<MigrationStatus> {
+    let user_version = read_user_version(connection)?;
+    let to_error = |error: rusqlite::Error| {
+        AppError::internal(
+            "failed to read migration history",
+            format!("failed to read migration history: {error}"),
+        )
+    };
+    let mut applied = Vec::new();
+    if user_version >= 15 {
+        let mut stmt = connection
+            .prepare("SELECT version, applied_at FROM schema_migrations ORDER BY version ASC")
+            .map_err(to_error)?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok(AppliedMigration {
+                    version: row.get(0)?,
+                    applied_at: row.get(1)?,
+                })
+            })
+            .map_err(to_error)?;
+        applied = rows.collect::<Result<Vec<_>, _>>().map_err(to_error)?;
     }
+
+    Ok(MigrationStatus {
+        user_version,
+        latest_version: LATEST_SCHEMA_VERSION,
+        applied,
+        pending: pending_migrations(connection)?,
+    })
 }
 
-fn run_migrations(connection: &Connection) -> AppResult<()> {
-    let current_version: i64 = connection
+fn read_user_version(connection: &Connection) -> AppResult<i64> {
+    connection
         .query_row("PRAGMA user_version;", [], |row| row.get(0))
         .map_err(|error| {
             AppError::internal(
                 "failed to fetch sqlite user_version",
                 format!("failed to fetch sqlite user_version: {error}"),
             )
-        })?;
+        })
+}
+
+pub(crate) fn run_migrations(connection: &Connection) -> AppResult<()> {
+    let current_version = read_user_version(connection)?;
 
     if current_version < 1 {
         connection.execute_batch(
@@ -320,7 +725,643 @@ fn run_migrations(connection: &Connection) -> AppResult<()> {
             })?;
     }
 
-    Ok(())
-}
-
-
+    if current_version < 5 {
+        connection
+            .execute_batch(
+                "
+                BEGIN;
+
+                CREATE TABLE IF NOT EXISTS settings (
+                    key TEXT PRIMARY KEY,
+                    value TEXT NOT NULL,
+                    updated_at INTEGER NOT NULL
+                );
+
+                ALTER TABLE rest_suggestions ADD COLUMN response_reason TEXT;
+
+                PRAGMA user_version = 5;
+
+                COMMIT;
+                ",
+            )
+            .map_err(|error| {
+                AppError::internal(
+                    "failed to apply sqlite migration v5",
+                    format!("failed to apply sqlite migration v5: {error}"),
+                )
+            })?;
+    }
+
+    if current_version < 6 {
+        connection
+            .execute_batch(
+                "
+                BEGIN;
+
+                ALTER TABLE rest_suggestions ADD COLUMN accepted_minutes INTEGER;
+
+                UPDATE rest_suggestions
+                SET accepted_minutes = suggested_minutes
+                WHERE status = 'accepted';
+
+                PRAGMA user_version = 6;
+
+                COMMIT;
+                ",
+            )
+            .map_err(|error| {
+                AppError::internal(
+                    "failed to apply sqlite migration v6",
+                    format!("failed to apply sqlite migration v6: {error}"),
+                )
+            })?;
+    }
+
+    if current_version < 7 {
+        connection
+            .execute_batch(
+                "
+                BEGIN;
+
+                ALTER TABLE tags ADD COLUMN tag_key TEXT;
+                ALTER TABLE tags ADD COLUMN tag_value TEXT;
+
+                UPDATE tags
+                SET tag_key = lower(trim(substr(name, 1, instr(name, ':') - 1))),
+                    tag_value = trim(substr(name, instr(name, ':') + 1))
+                WHERE instr(name, ':') > 0
+                  AND length(trim(substr(name, 1, instr(name, ':') - 1))) > 0
+                  AND length(trim(substr(name, instr(name, ':') + 1))) > 0;
+
+                CREATE INDEX IF NOT EXISTS idx_tags_key_value ON tags(tag_key, tag_value);
+
+                PRAGMA user_version = 7;
+
+                COMMIT;
+                ",
+            )
+            .map_err(|error| {
+                AppError::internal(
+                    "failed to apply sqlite migration v7",
+                    format!("failed to apply sqlite migration v7: {error}"),
+                )
+            })?;
+    }
+
+    if current_version < 8 {
+        connection
+            .execute_batch(
+                "
+                BEGIN;
+
+                ALTER TABLE tasks ADD COLUMN completed_at INTEGER;
+
+                PRAGMA user_version = 8;
+
+                COMMIT;
+                ",
+            )
+            .map_err(|error| {
+                AppError::internal(
+                    "failed to apply sqlite migration v8",
+                    format!("failed to apply sqlite migration v8: {error}"),
+                )
+            })?;
+    }
+
+    if current_version < 9 {
+        connection
+            .execute_batch(
+                "
+                BEGIN;
+
+                ALTER TABLE tasks ADD COLUMN due_at INTEGER;
+
+                PRAGMA user_version = 9;
+
+                COMMIT;
+                ",
+            )
+            .map_err(|error| {
+                AppError::internal(
+                    "failed to apply sqlite migration v9",
+                    format!("failed to apply sqlite migration v9: {error}"),
+                )
+            })?;
+    }
+
+    if current_version < 10 {
+        connection
+            .execute_batch(
+                "
+                BEGIN;
+
+                ALTER TABLE time_events RENAME TO time_events_v9;
+
+                CREATE TABLE time_events (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    task_id TEXT NOT NULL REFERENCES tasks(id),
+                    event_type TEXT NOT NULL CHECK(
+                        event_type IN ('create', 'start', 'pause', 'resume', 'stop', 'adjust', 'reparent', 'tag_add', 'tag_remove')
+                    ),
+                    ts INTEGER NOT NULL,
+                    payload TEXT
+                );
+
+                INSERT INTO time_events (id, task_id, event_type, ts, payload)
+                SELECT id, task_id, event_type, ts, payload
+                FROM time_events_v9;
+
+                DROP TABLE time_events_v9;
+
+                CREATE INDEX IF NOT EXISTS idx_time_events_task_ts ON time_events(task_id, ts, id);
+                CREATE INDEX IF NOT EXISTS idx_time_events_ts ON time_events(ts, id);
+
+                PRAGMA user_version = 10;
+
+                COMMIT;
+                ",
+            )
+            .map_err(|error| {
+                AppError::internal(
+                    "failed to apply sqlite migration v10",
+                    format!("failed to apply sqlite migration v10: {error}"),
+                )
+            })?;
+    }
+
+    if current_version < 11 {
+        connection
+            .execute_batch(
+                "
+                BEGIN;
+
+                ALTER TABLE time_events RENAME TO time_events_v10;
+
+                CREATE TABLE time_events (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    task_id TEXT NOT NULL REFERENCES tasks(id),
+                    event_type TEXT NOT NULL CHECK(
+                        event_type IN ('create', 'start', 'pause', 'resume', 'stop', 'adjust', 'reparent', 'tag_add', 'tag_remove', 'archive', 'restore')
+                    ),
+                    ts INTEGER NOT NULL,
+                    payload TEXT
+                );
+
+                INSERT INTO time_events (id, task_id, event_type, ts, payload)
+                SELECT id, task_id, event_type, ts, payload
+                FROM time_events_v10;
+
+                DROP TABLE time_events_v10;
+
+                CREATE INDEX IF NOT EXISTS idx_time_events_task_ts ON time_events(task_id, ts, id);
+                CREATE INDEX IF NOT EXISTS idx_time_events_ts ON time_events(ts, id);
+
+                PRAGMA user_version = 11;
+
+                COMMIT;
+                ",
+            )
+            .map_err(|error| {
+                AppError::internal(
+                    "failed to apply sqlite migration v11",
+                    format!("failed to apply sqlite migration v11: {error}"),
+                )
+            })?;
+    }
+
+    if current_version < 12 {
+        // Dropping the old rest_suggestions table would cascade into notifications, so
+        // foreign keys are suspended around the swap (the pragma is ignored inside BEGIN).
+        let foreign_keys: i64 = connection
+            .query_row("PRAGMA foreign_keys;", [], |row| row.get(0))
+            .map_err(|error| {
+                AppError::internal(
+                    "failed to apply sqlite migration v12",
+                    format!("failed to read sqlite foreign_keys pragma: {error}"),
+                )
+            })?;
+        connection
+            .pragma_update(None, "foreign_keys", "OFF")
+            .map_err(|error| {
+                AppError::internal(
+                    "failed to apply sqlite migration v12",
+                    format!("failed to disable sqlite foreign_keys pragma: {error}"),
+                )
+            })?;
+
+        let migration = connection.execute_batch(
+            "
+            BEGIN;
+
+            CREATE TABLE rest_suggestions_v12 (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                trigger_type TEXT NOT NULL CHECK(trigger_type IN ('subtask_end', 'task_switch')),
+                task_id TEXT REFERENCES tasks(id),
+                focus_seconds INTEGER NOT NULL,
+                switch_count_30m INTEGER NOT NULL,
+                deviation_ratio REAL NOT NULL,
+                suggested_minutes INTEGER NOT NULL CHECK(suggested_minutes >= 0 AND suggested_minutes <= 120),
+                reasons TEXT NOT NULL,
+                status TEXT NOT NULL CHECK(status IN ('pending', 'accepted', 'ignored')),
+                created_at INTEGER NOT NULL,
+                responded_at INTEGER,
+                response_reason TEXT,
+                accepted_minutes INTEGER
+            );
+
+            INSERT INTO rest_suggestions_v12
+                (id, trigger_type, task_id, focus_seconds, switch_count_30m, deviation_ratio,
+                 suggested_minutes, reasons, status, created_at, responded_at, response_reason,
+                 accepted_minutes)
+            SELECT
+                id, trigger_type, task_id, focus_seconds, switch_count_30m, deviation_ratio,
+                suggested_minutes, reasons, status, created_at, responded_at, response_reason,
+                accepted_minutes
+            FROM rest_suggestions;
+
+            DROP TABLE rest_suggestions;
+            ALTER TABLE rest_suggestions_v12 RENAME TO rest_suggestions;
+
+            CREATE INDEX IF NOT EXISTS idx_rest_suggestions_status_created_at
+                ON rest_suggestions(status, created_at DESC, id DESC);
+
+            PRAGMA user_version = 12;
+
+            COMMIT;
+            ",
+        );
+
+        connection
+            .pragma_update(None, "foreign_keys", foreign_keys)
+            .map_err(|error| {
+                AppError::internal(
+                    "failed to apply sqlite migration v12",
+                    format!("failed to restore sqlite foreign_keys pragma: {error}"),
+                )
+            })?;
+        migration.map_err(|error| {
+            AppError::internal(
+                "failed to apply sqlite migration v12",
+                format!("failed to apply sqlite migration v12: {error}"),
+            )
+        })?;
+    }
+
+    if current_version < 13 {
+        connection
+            .execute_batch(
+                "
+                BEGIN;
+
+                ALTER TABLE tags ADD COLUMN color TEXT;
+
+                PRAGMA user_version = 13;
+
+                COMMIT;
+                ",
+            )
+            .map_err(|error| {
+                AppError::internal(
+                    "failed to apply sqlite migration v13",
+                    format!("failed to apply sqlite migration v13: {error}"),
+                )
+            })?;
+    }
+
+    if current_version < 14 {
+        // Tasks created before `create` events existed get one at their creation time (or
+        // their first event, if that is earlier, e.g. imported history).
+        connection
+            .execute_batch(
+                "
+                BEGIN;
+
+                INSERT INTO time_events (task_id, event_type, ts, payload)
+                SELECT t.id,
+                       'create',
+                       MIN(t.created_at, COALESCE(MIN(e.ts), t.created_at)),
+                       '{\"backfilled\":true}'
+                FROM tasks t
+                LEFT JOIN time_events e ON e.task_id = t.id
+                WHERE NOT EXISTS (
+                    SELECT 1 FROM time_events c WHERE c.task_id = t.id AND c.event_type = 'create'
+                )
+                GROUP BY t.id
+                ORDER BY t.created_at ASC, t.id ASC;
+
+                PRAGMA user_version = 14;
+
+                COMMIT;
+                ",
+            )
+            .map_err(|error| {
+                AppError::internal(
+                    "failed to apply sqlite migration v14",
+                    format!("failed to apply sqlite migration v14: {error}"),
+                )
+            })?;
+    }
+
+    if current_version < 15 {
+        connection
+            .execute_batch(
+                "
+                BEGIN;
+
+                CREATE TABLE IF NOT EXISTS schema_migrations (
+                    version INTEGER PRIMARY KEY,
+                    applied_at INTEGER
+                );
+
+                PRAGMA user_version = 15;
+
+                COMMIT;
+                ",
+            )
+            .map_err(|error| {
+                AppError::internal(
+                    "failed to apply sqlite migration v15",
+                    format!("failed to apply sqlite migration v15: {error}"),
+                )
+            })?;
+    }
+
+    if current_version < 16 {
+        connection
+            .execute_batch(
+                "
+                BEGIN;
+
+                ALTER TABLE tasks ADD COLUMN reference_url TEXT;
+
+                PRAGMA user_version = 16;
+
+                COMMIT;
+                ",
+            )
+            .map_err(|error| {
+                AppError::internal(
+                    "failed to apply sqlite migration v16",
+                    format!("failed to apply sqlite migration v16: {error}"),
+                )
+            })?;
+    }
+
+    if current_version < 17 {
+        connection
+            .execute_batch(
+                "
+                BEGIN;
+
+                CREATE TABLE IF NOT EXISTS task_baselines (
+                    task_id TEXT PRIMARY KEY REFERENCES tasks(id) ON DELETE CASCADE,
+                    median_seconds INTEGER NOT NULL,
+                    session_count INTEGER NOT NULL,
+                    through_ts INTEGER NOT NULL,
+                    updated_at INTEGER NOT NULL
+                );
+
+                PRAGMA user_version = 17;
+
+                COMMIT;
+                ",
+            )
+            .map_err(|error| {
+                AppError::internal(
+                    "failed to apply sqlite migration v17",
+                    format!("failed to apply sqlite migration v17: {error}"),
+                )
+            })?;
+    }
+
+    if current_version < 18 {
+        connection
+            .execute_batch(
+                "
+                BEGIN;
+
+                ALTER TABLE time_events RENAME TO time_events_v17;
+
+                CREATE TABLE time_events (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    task_id TEXT NOT NULL REFERENCES tasks(id),
+                    event_type TEXT NOT NULL CHECK(
+                        event_type IN ('create', 'start', 'pause', 'resume', 'stop', 'adjust', 'reparent', 'tag_add', 'tag_remove', 'archive', 'restore', 'interruption')
+                    ),
+                    ts INTEGER NOT NULL,
+                    payload TEXT
+                );
+
+                INSERT INTO time_events (id, task_id, event_type, ts, payload)
+                SELECT id, task_id, event_type, ts, payload
+                FROM time_events_v17;
+
+                DROP TABLE time_events_v17;
+
+                CREATE INDEX IF NOT EXISTS idx_time_events_task_ts ON time_events(task_id, ts, id);
+                CREATE INDEX IF NOT EXISTS idx_time_events_ts ON time_events(ts, id);
+
+                PRAGMA user_version = 18;
+
+                COMMIT;
+                ",
+            )
+            .map_err(|error| {
+                AppError::internal(
+                    "failed to apply sqlite migration v18",
+                    format!("failed to apply sqlite migration v18: {error}"),
+                )
+            })?;
+    }
+
+    if current_version < 19 {
+        // `ts_ms` carries the sub-second part for events written from now on; older rows
+        // keep it NULL and read as `ts * 1000`.
+        connection
+            .execute_batch(
+                "
+                BEGIN;
+
+                ALTER TABLE time_events ADD COLUMN ts_ms INTEGER;
+
+                PRAGMA user_version = 19;
+
+                COMMIT;
+                ",
+            )
+            .map_err(|error| {
+                AppError::internal(
+                    "failed to apply sqlite migration v19",
+                    format!("failed to apply sqlite migration v19: {error}"),
+                )
+            })?;
+    }
+
+    if current_version < 20 {
+        connection
+            .execute_batch(
+                "
+                BEGIN;
+
+                ALTER TABLE tasks ADD COLUMN sort_order INTEGER;
+
+                PRAGMA user_version = 20;
+
+                COMMIT;
+                ",
+            )
+            .map_err(|error| {
+                AppError::internal(
+                    "failed to apply sqlite migration v20",
+                    format!("failed to apply sqlite migration v20: {error}"),
+                )
+            })?;
+    }
+
+    if current_version < 21 {
+        connection
+            .execute_batch(
+                "
+                BEGIN;
+
+                CREATE TABLE IF NOT EXISTS overview_snapshots (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    task_id TEXT NOT NULL REFERENCES tasks(id) ON DELETE CASCADE,
+                    taken_at INTEGER NOT NULL,
+                    inclusive_seconds INTEGER NOT NULL,
+                    exclusive_seconds INTEGER NOT NULL
+                );
+
+                CREATE INDEX IF NOT EXISTS idx_overview_snapshots_task_taken
+                    ON overview_snapshots(task_id, taken_at);
+
+                PRAGMA user_version = 21;
+
+                COMMIT;
+                ",
+            )
+            .map_err(|error| {
+                AppError::internal(
+                    "failed to apply sqlite migration v21",
+                    format!("failed to apply sqlite migration v21: {error}"),
+                )
+            })?;
+    }
+
+    if current_version < 22 {
+        connection
+            .execute_batch(
+                "
+                BEGIN;
+
+                CREATE TABLE IF NOT EXISTS deleted_tasks (
+                    task_id TEXT PRIMARY KEY,
+                    deleted_at INTEGER NOT NULL,
+                    last_event_id INTEGER NOT NULL
+                );
+
+                PRAGMA user_version = 22;
+
+                COMMIT;
+                ",
+            )
+            .map_err(|error| {
+                AppError::internal(
+                    "failed to apply sqlite migration v22",
+                    format!("failed to apply sqlite migration v22: {error}"),
+                )
+            })?;
+    }
+
+    // Structured tags created before `sanitize_tag` normalized them (e.g. `Client : Acme`)
+    // are renamed to `key:value`. Tags that then match case-insensitively are merged into
+    // one, preferring the tag that already had the normalized name.
+    if current_version < 23 {
+        connection
+            .execute_batch(
+                "
+                BEGIN;
+
+                CREATE TEMP TABLE tag_merges AS
+                SELECT
+                    id,
+                    new_name,
+                    FIRST_VALUE(id) OVER (
+                        PARTITION BY lower(new_name)
+                        ORDER BY name = new_name DESC, id ASC
+                    ) AS keep_id
+                FROM (
+                    SELECT id, name, COALESCE(tag_key || ':' || tag_value, name) AS new_name
+                    FROM tags
+                );
+
+                INSERT OR IGNORE INTO task_tags (task_id, tag_id, created_at)
+                SELECT task_tags.task_id, tag_merges.keep_id, task_tags.created_at
+                FROM task_tags
+                JOIN tag_merges ON tag_merges.id = task_tags.tag_id
+                WHERE tag_merges.id <> tag_merges.keep_id;
+
+                UPDATE tags
+                SET color = (
+                    SELECT merged.color
+                    FROM tag_merges
+                    JOIN tags merged ON merged.id = tag_merges.id
+                    WHERE tag_merges.keep_id = tags.id AND merged.color IS NOT NULL
+                    ORDER BY merged.id
+                    LIMIT 1
+                )
+                WHERE color IS NULL;
+
+                DELETE FROM task_tags
+                WHERE tag_id IN (SELECT id FROM tag_merges WHERE id <> keep_id);
+                DELETE FROM tags
+                WHERE id IN (SELECT id FROM tag_merges WHERE id <> keep_id);
+
+                UPDATE tags
+                SET name = (SELECT new_name FROM tag_merges WHERE tag_merges.id = tags.id)
+                WHERE name <> (SELECT new_name FROM tag_merges WHERE tag_merges.id = tags.id);
+
+                DROP TABLE tag_merges;
+
+                PRAGMA user_version = 23;
+
+                COMMIT;
+                ",
+            )
+            .map_err(|error| {
+                AppError::internal(
+                    "failed to apply sqlite migration v23",
+                    format!("failed to apply sqlite migration v23: {error}"),
+                )
+            })?;
+    }
+
+    record_applied_migrations(connection, current_version)
+}
+
+/// Stamps the versions applied by this run with the current time. Versions that were
+/// already in place before tracking started are listed without a timestamp.
+fn record_applied_migrations(connection: &Connection, previous_version: i64) -> AppResult<()> {
+    let applied_at = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs() as i64)
+        .unwrap_or(0);
+    for version in 1..=LATEST_SCHEMA_VERSION {
+        let stamp = (version > previous_version).then_some(applied_at);
+        connection
+            .execute(
+                "INSERT OR IGNORE INTO schema_migrations (version, applied_at) VALUES (?1, ?2)",
+                params![version, stamp],
+            )
+            .map_err(|error| {
+                AppError::internal(
+                    "failed to record applied migrations",
+                    format!("failed to record migration v{version}: {error}"),
+                )
+            })?;
+    }
+    Ok(())
+}
+
+
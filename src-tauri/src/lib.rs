@@ -1,6 +1,7 @@
 use tauri::Manager;
 
 mod app;
+mod background;
 mod command_api;
 mod command_catalog;
 mod domain;
@@ -16,29 +17,117 @@ pub fn run() {
             let state = infra::AppState::initialize(&app.handle()).map_err(|error| {
                 std::io::Error::other(format!("failed to initialize app state: {error}"))
             })?;
+            if let Ok(mut conn) = state.db.lock() {
+                if let Err(error) = app::expire_stale_rest_suggestions(&mut conn) {
+                    eprintln!("failed to expire stale rest suggestions: {error}");
+                }
+            }
             app.manage(state);
+            background::spawn_background_checks(app.handle().clone());
+            background::spawn_overview_debounce(app.handle().clone());
             Ok(())
         })
         .plugin(tauri_plugin_opener::init())
         .invoke_handler(tauri::generate_handler![
             command_api::ping,
+            command_api::get_storage_status,
+            command_api::relocate_database,
+            command_api::pending_migrations,
+            command_api::get_migration_status,
             command_api::get_command_catalog,
             command_api::get_overview,
+            command_api::get_task_tree_nested,
+            command_api::get_descendants,
+            command_api::get_overview_since,
+            command_api::get_running_task,
+            command_api::get_overwork_status,
+            command_api::get_status_counts,
             command_api::get_focus_summary,
+            command_api::get_tag_daily_totals,
+            command_api::get_rolling_average,
+            command_api::get_fragmentation,
+            command_api::get_work_bounds,
+            command_api::get_focus_density,
+            command_api::get_untracked_gaps,
+            command_api::get_longest_untracked_gap,
+            command_api::get_billable_report,
+            command_api::get_completion_rate,
+            command_api::get_time_breakdown,
+            command_api::get_time_by_tag_set,
+            command_api::get_projects,
+            command_api::get_container_tasks,
+            command_api::get_weekly_summary,
+            command_api::get_summary_card_data,
+            command_api::get_break_adherence,
+            command_api::get_totals_for,
+            command_api::get_inclusive_excluding,
+            command_api::export_task_subtree,
+            command_api::verify_totals,
+            command_api::debug_intervals,
+            command_api::verify_tag_consistency,
+            command_api::reconcile_tags,
+            command_api::get_inclusive_overlap_warnings,
+            command_api::get_midnight_crossing_sessions,
+            command_api::find_cycles,
+            command_api::break_cycle,
+            command_api::get_overdue_tasks,
+            command_api::get_in_progress_tasks,
+            command_api::get_stale_tasks,
+            command_api::find_duplicate_tasks,
+            command_api::get_task_events,
+            command_api::get_events_in_window,
+            command_api::get_task_detail,
+            command_api::annotate_session,
+            command_api::get_pending_rest_suggestion,
+            command_api::get_rest_signals,
+            command_api::preview_current_rest,
             command_api::create_task,
+            command_api::create_subtasks,
             command_api::rename_task,
             command_api::archive_task,
+            command_api::restore_task,
+            command_api::reset_task_time,
+            command_api::archive_tasks_by_tag,
+            command_api::restore_tasks_by_tag,
             command_api::delete_tasks,
             command_api::reparent_task,
+            command_api::reparent_tasks_by_tag,
+            command_api::nudge_task_order,
+            command_api::update_task,
             command_api::start_task,
+            command_api::quick_start,
             command_api::pause_task,
+            command_api::start_break,
+            command_api::end_break,
+            command_api::log_interruption,
             command_api::resume_task,
+            command_api::toggle_task,
             command_api::stop_task,
+            command_api::complete_task,
+            command_api::reopen_task,
+            command_api::set_task_due,
+            command_api::set_task_reference,
             command_api::adjust_task_focus,
+            command_api::coalesce_task_sessions,
             command_api::insert_subtask_and_start,
             command_api::add_tag_to_task,
+            command_api::tag_and_view,
             command_api::remove_tag_from_task,
-            command_api::respond_rest_suggestion
+            command_api::import_csv_sessions,
+            command_api::get_tags_by_key,
+            command_api::get_top_tags,
+            command_api::set_tag_color,
+            command_api::suggest_tags,
+            command_api::list_untagged_tasks,
+            command_api::respond_rest_suggestion,
+            command_api::respond_all_pending_rest_suggestions,
+            command_api::accept_rest_suggestion_with_minutes,
+            command_api::get_settings,
+            command_api::simulate_rest_rules,
+            command_api::rebuild_baselines,
+            command_api::snapshot_overview,
+            command_api::get_snapshots,
+            command_api::update_setting
         ])
         .build(tauri::generate_context!())
         .expect("error while building tauri application");
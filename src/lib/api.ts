@@ -11,16 +11,28 @@ export interface TaskRecord {
   title: string;
   status: TaskStatus;
   created_at: number;
+  completed_at: number | null;
+  due_at: number | null;
+  /** http(s) link to the ticket or doc the task is about. */
+  reference_url: string | null;
+  /** Position among siblings once reordered by hand; null sorts after, oldest first. */
+  sort_order: number | null;
   last_activated_at: number | null;
-  tags: string[];
+  tags: TagRef[];
   inclusive_seconds: number;
   exclusive_seconds: number;
+  /** Set only when the overview was requested with `precision: "millis"`. */
+  inclusive_millis: number | null;
+  exclusive_millis: number | null;
+  /** True when some descendant has its own tracked time; useful for default expansion. */
+  has_tracked_descendants: boolean;
 }
 
 export interface RestSuggestionRecord {
   id: number;
   trigger_type: "subtask_end" | "task_switch";
   task_id: string | null;
+  task_title: string | null;
   focus_seconds: number;
   switch_count_30m: number;
   deviation_ratio: number;
@@ -30,6 +42,242 @@ export interface RestSuggestionRecord {
   created_at: number;
 }
 
+export interface TagRecord {
+  name: string;
+  key: string | null;
+  value: string | null;
+  color: string | null;
+  task_count: number;
+}
+
+export interface TagRef {
+  name: string;
+  /** Hex chip color such as `#3b82f6`, or null when unset. */
+  color: string | null;
+}
+
+export interface TaskTreeNode extends TaskRecord {
+  children: TaskTreeNode[];
+}
+
+export interface TaskTreeResponse {
+  schema_version: number;
+  range: OverviewRange;
+  generated_at: number;
+  roots: TaskTreeNode[];
+}
+
+export interface TaskEventRecord {
+  id: number;
+  task_id: string;
+  event_type: string;
+  ts: number;
+  reason: string | null;
+  payload: Record<string, unknown> | null;
+}
+
+export interface TaskSession {
+  start_ts: number;
+  /** Null while the session is still open. */
+  end_ts: number | null;
+  duration_seconds: number;
+  note: string | null;
+}
+
+export interface DescendantRecord {
+  task: TaskRecord;
+  /** Levels below the requested task; the root itself is 0. */
+  depth: number;
+}
+
+export interface TaskDetail {
+  task: TaskRecord;
+  sessions: TaskSession[];
+  first_worked_at: number | null;
+  last_worked_at: number | null;
+}
+
+export interface TaskEventsPage {
+  events: TaskEventRecord[];
+  has_more: boolean;
+  next_before_ts: number | null;
+  next_before_id: number | null;
+}
+
+export interface TimeEventRecord extends TaskEventRecord {
+  task_title: string;
+}
+
+export interface TimeEventsPage {
+  events: TimeEventRecord[];
+  has_more: boolean;
+  next_after_ts: number | null;
+  next_after_id: number | null;
+}
+
+export interface OverviewSnapshotSummary {
+  taken_at: number;
+  task_count: number;
+}
+
+export interface OverviewSnapshot {
+  taken_at: number;
+  inclusive_seconds: number;
+  exclusive_seconds: number;
+}
+
+export interface OverviewDelta {
+  latest_event_id: number | null;
+  /** Set instead of the task lists when the caller's event id could not be diffed from. */
+  full_overview: OverviewResponse | null;
+  changed_tasks: TaskRecord[];
+  removed_task_ids: string[];
+}
+
+export interface CreatedSubtasks {
+  task_ids: string[];
+  warnings: string[];
+}
+
+export interface AppliedMigration {
+  version: number;
+  /** Null for versions applied before migration history was recorded. */
+  applied_at: number | null;
+}
+
+export interface MigrationStatus {
+  user_version: number;
+  latest_version: number;
+  applied: AppliedMigration[];
+  pending: number[];
+}
+
+export interface StorageStatus {
+  data_dir: string;
+  db_path: string;
+  /** True when the regular data directory was unusable and a temporary one is in use. */
+  using_fallback: boolean;
+  fallback_reason: string | null;
+}
+
+export interface StatusCounts {
+  idle: number;
+  running: number;
+  paused: number;
+  stopped: number;
+  archived: number;
+}
+
+export interface CsvImportRowError {
+  line: number;
+  message: string;
+}
+
+export interface CsvImportReport {
+  tasks_created: number;
+  sessions_imported: number;
+  skipped_rows: CsvImportRowError[];
+}
+
+export interface TagSuggestion {
+  name: string;
+  co_occurrences: number;
+}
+
+export interface OverviewOptions {
+  /** Plain tag name or structured `key:value` tag to filter tasks by. */
+  tag?: string | null;
+  /** Omit tasks that have been marked complete. */
+  exclude_completed?: boolean;
+  /** Sibling order; children always follow their parent. Defaults to `created_asc`. */
+  sort?: OverviewSort | null;
+  /**
+   * `active` (default) counts running time only. `engaged` spans each session from start to
+   * stop, so pauses inside a session count too and a paused parent also accrues the time of
+   * the subtask it is waiting on. Every seconds figure in the response follows this basis.
+   */
+  time_basis?: TimeBasis | null;
+  /**
+   * Keep only tasks with time in the window, plus their ancestors so the tree stays
+   * connected; those ancestors are listed in `context_task_ids`.
+   */
+  only_active_in_window?: boolean;
+  /**
+   * End the window here instead of now, clipping any session still open at that moment.
+   * Only the seconds are historical; task list and statuses are current.
+   */
+  as_of?: number | null;
+  /**
+   * `millis` also fills `inclusive_millis`/`exclusive_millis` on each task. Events recorded
+   * before millisecond tracking count as whole seconds.
+   */
+  precision?: TimePrecision | null;
+  /**
+   * Hide completed subtasks whose whole subtree is completed; their time stays in the
+   * ancestors' inclusive totals. Applied after `tag`, `exclude_completed` and
+   * `only_active_in_window`, and top-level tasks are never hidden.
+   */
+  collapse_completed?: boolean;
+}
+
+export type TimeBasis = "active" | "engaged";
+
+export type TimePrecision = "seconds" | "millis";
+
+export type OverviewSort =
+  | "created_asc"
+  | "created_desc"
+  | "title"
+  | "time_desc"
+  | "recent_activity"
+  | "manual";
+
+export interface RestRulesConfig {
+  /** Same format as the `rest_minute_tiers` setting, e.g. "3,8,15". */
+  minute_tiers?: string | null;
+}
+
+export interface RestTierCount {
+  minutes: number;
+  count: number;
+}
+
+/** Raw rest-rule inputs for the running task; all zero when nothing runs. */
+export interface RestSignals {
+  task_id: string | null;
+  measured_at: number;
+  focus_seconds: number;
+  switch_count_30m: number;
+  deviation_ratio: number;
+}
+
+/** Live, unsaved rest evaluation for the running session; all zero when nothing runs. */
+export interface RestPreview {
+  task_id: string | null;
+  focus_seconds: number;
+  switch_count_30m: number;
+  deviation_ratio: number;
+  suggested_minutes: number;
+  reasons: string[];
+}
+
+export interface RestSimulationResponse {
+  range: FocusSummaryRange;
+  generated_at: number;
+  minute_tiers: number[];
+  trigger_count: number;
+  task_switch_count: number;
+  subtask_end_count: number;
+  prompt_count: number;
+  tiers: RestTierCount[];
+}
+
+export interface SettingRecord {
+  key: string;
+  value: string;
+  is_default: boolean;
+}
+
 export interface NotificationRecord {
   id: number;
   kind: "rest_suggestion";
@@ -43,13 +291,19 @@ export interface NotificationRecord {
 }
 
 export interface OverviewResponse {
+  /** Shape of `TaskRecord`; 2 introduced `TagRef` tags. */
+  schema_version: number;
   range: OverviewRange;
+  time_basis: TimeBasis;
+  precision: TimePrecision;
   generated_at: number;
   active_task_id: string | null;
   last_used_task_id: string | null;
   rest_suggestion: RestSuggestionRecord | null;
   notifications: NotificationRecord[];
   tasks: TaskRecord[];
+  /** Ancestors kept only for tree connectivity under `only_active_in_window`. */
+  context_task_ids: string[];
 }
 
 export interface DayTaskBreakdown {
@@ -86,6 +340,403 @@ export interface FocusSummaryResponse {
   days: FocusSummaryDay[];
 }
 
+export interface FragmentationDay {
+  date_key: string;
+  day_start_ts: number;
+  day_end_ts: number;
+  switch_count: number;
+  interruption_count: number;
+  focus_block_count: number;
+  average_block_seconds: number;
+}
+
+export interface WorkBoundsDay {
+  date_key: string;
+  day_start_ts: number;
+  first_start_ts: number;
+  last_end_ts: number | null;
+  still_working: boolean;
+}
+
+export interface WorkBoundsResponse {
+  range: FocusSummaryRange;
+  generated_at: number;
+  days: WorkBoundsDay[];
+}
+
+export interface FocusDensityDay {
+  date_key: string;
+  day_start_ts: number;
+  tracked_seconds: number;
+  elapsed_seconds: number;
+  /** Tracked over elapsed seconds; null when nothing has elapsed yet. */
+  density: number | null;
+}
+
+export interface FocusDensityResponse {
+  range: FocusSummaryRange;
+  generated_at: number;
+  days: FocusDensityDay[];
+}
+
+export interface UntrackedGap {
+  start_ts: number;
+  end_ts: number;
+  seconds: number;
+}
+
+export interface UntrackedGapsResponse {
+  date_key: string;
+  day_start_ts: number;
+  total_seconds: number;
+  gaps: UntrackedGap[];
+}
+
+export interface TagDailyTotal {
+  date_key: string;
+  day_start_ts: number;
+  tag_name: string;
+  seconds: number;
+}
+
+/** Only day/tag pairs with tracked time; untagged tasks are left out. */
+export interface TagDailyTotalsResponse {
+  range: FocusSummaryRange;
+  generated_at: number;
+  rows: TagDailyTotal[];
+}
+
+export interface RollingAverageDay {
+  date_key: string;
+  day_start_ts: number;
+  total_seconds: number;
+  average_seconds: number;
+  /** Days averaged over; below `window_days` at the start of the range. */
+  sample_days: number;
+}
+
+/** Days are oldest first. */
+export interface RollingAverageResponse {
+  range: FocusSummaryRange;
+  generated_at: number;
+  window_days: number;
+  days: RollingAverageDay[];
+}
+
+export interface FragmentationResponse {
+  range: FocusSummaryRange;
+  generated_at: number;
+  days: FragmentationDay[];
+}
+
+export interface BillableTaskLine {
+  task_id: string;
+  title: string;
+  seconds: number;
+  invoice_line: string;
+}
+
+export interface BillableReport {
+  range: OverviewRange;
+  client: string;
+  generated_at: number;
+  matched_by: "structured" | "plain";
+  tasks: BillableTaskLine[];
+  total_seconds: number;
+  invoice_line: string;
+}
+
+export interface TimeBreakdownEntry {
+  task_id: string;
+  title: string;
+  inclusive_seconds: number;
+  /** Share of `total_seconds`, 0-100. */
+  percentage: number;
+}
+
+export interface ProjectRecord {
+  id: string;
+  title: string;
+  status: TaskStatus;
+  /** Time of the whole subtree within the range. */
+  inclusive_seconds: number;
+  /** Union of tags across the subtree. */
+  tags: TagRef[];
+  child_count: number;
+  descendant_count: number;
+}
+
+export interface ProjectsResponse {
+  range: OverviewRange;
+  generated_at: number;
+  projects: ProjectRecord[];
+}
+
+export interface TimeBreakdownResponse {
+  range: OverviewRange;
+  generated_at: number;
+  total_seconds: number;
+  untracked_seconds: number | null;
+  entries: TimeBreakdownEntry[];
+}
+
+export interface TagSetTotal {
+  /** Sorted tag names; empty for untagged tasks. */
+  tags: string[];
+  seconds: number;
+  task_count: number;
+}
+
+export interface TagSetTotalsResponse {
+  range: OverviewRange;
+  generated_at: number;
+  total_seconds: number;
+  combinations: TagSetTotal[];
+}
+
+export interface InclusiveOverlapWarning {
+  task_id: string;
+  title: string;
+  inclusive_seconds: number;
+  window_seconds: number;
+  excess_seconds: number;
+}
+
+export interface MidnightCrossingSession {
+  task_id: string;
+  title: string;
+  start_ts: number;
+  end_ts: number;
+  start_date: string;
+  end_date: string;
+}
+
+export interface WeeklyTopTask {
+  task_id: string;
+  title: string;
+  exclusive_seconds: number;
+}
+
+export interface WeeklySummary {
+  range_start: number;
+  range_end: number;
+  total_focus_seconds: number;
+  top_tasks: WeeklyTopTask[];
+  breaks_accepted: number;
+  breaks_ignored: number;
+  most_fragmented_day: FragmentationDay | null;
+  text: string;
+}
+
+export interface SummaryCardData {
+  range: FocusSummaryRange;
+  range_start: number;
+  range_end: number;
+  total_focus_seconds: number;
+  /** Rounded to one decimal. */
+  total_hours: number;
+  top_task: WeeklyTopTask | null;
+  streak_days: number;
+  breaks_taken: number;
+}
+
+export interface BreakAdherence {
+  range: FocusSummaryRange;
+  range_start: number;
+  range_end: number;
+  generated_at: number;
+  /** Answered suggestions that proposed a break; pending ones are left out. */
+  suggestion_count: number;
+  accepted_count: number;
+  suggested_minutes: number;
+  accepted_minutes: number;
+  /** Accepted over suggested minutes; null when nothing was suggested. */
+  adherence_ratio: number | null;
+}
+
+export interface CompletionRateResponse {
+  range: OverviewRange;
+  generated_at: number;
+  created_count: number;
+  completed_count: number;
+  median_seconds_to_complete: number | null;
+}
+
+export interface TaskUpdate {
+  title?: string;
+  /** Omit to keep the current parent; pass `null` to move the task to the root. */
+  parent_id?: string | null;
+}
+
+export interface TaskTotals {
+  inclusive_seconds: number;
+  exclusive_seconds: number;
+}
+
+export interface TaskTotalsResponse {
+  range: OverviewRange;
+  generated_at: number;
+  totals: Record<string, TaskTotals>;
+  skipped_archived_ids: string[];
+}
+
+export interface InclusiveExcludingResponse {
+  task_id: string;
+  range: OverviewRange;
+  generated_at: number;
+  inclusive_seconds: number;
+  /** Time the excluded subtrees would otherwise have added. */
+  excluded_seconds: number;
+}
+
+export interface ExportedTaskNode {
+  title: string;
+  tags: string[];
+  archived: boolean;
+  inclusive_seconds: number | null;
+  exclusive_seconds: number | null;
+  children: ExportedTaskNode[];
+}
+
+export interface TaskSubtreeExport {
+  format_version: number;
+  exported_at: number;
+  root: ExportedTaskNode;
+}
+
+export interface TotalsDrift {
+  task_id: string;
+  title: string;
+  replayed_seconds: number;
+  stored_seconds: number;
+  difference_seconds: number;
+}
+
+export interface DebugInterval {
+  /** The session as replayed, before clipping; an open session ends at the window end. */
+  start_ts: number;
+  end_ts: number;
+  clipped_start_ts: number;
+  clipped_end_ts: number;
+  seconds: number;
+}
+
+export interface DebugAdjustment {
+  ts: number;
+  delta_seconds: number;
+}
+
+export interface DebugIntervalsResponse {
+  task_id: string;
+  range: OverviewRange;
+  window_start: number | null;
+  window_end: number;
+  intervals: DebugInterval[];
+  adjustments: DebugAdjustment[];
+  /** Matches the task's exclusive seconds for the same range. */
+  total_seconds: number;
+}
+
+export interface TagDiscrepancy {
+  task_id: string;
+  title: string;
+  /** Tags the event log leaves attached that have no `task_tags` row. */
+  missing_rows: string[];
+  /** `task_tags` rows the event log does not account for. */
+  unlogged_rows: string[];
+}
+
+export interface DuplicateTaskCandidate {
+  task_id: string;
+  title: string;
+  parent_id: string | null;
+  created_at: number;
+}
+
+export interface DuplicateTaskGroup {
+  normalized_title: string;
+  tasks: DuplicateTaskCandidate[];
+}
+
+export interface DuplicateTasksResponse {
+  threshold: number;
+  truncated: boolean;
+  groups: DuplicateTaskGroup[];
+}
+
+export interface StaleTaskRecord {
+  task_id: string;
+  title: string;
+  last_worked_at: number | null;
+  idle_seconds: number;
+}
+
+export interface InProgressTaskRecord {
+  task_id: string;
+  parent_id: string | null;
+  title: string;
+  status: Extract<TaskStatus, "running" | "paused">;
+  /** All-time, including the open interval of the running task. */
+  exclusive_seconds: number;
+  /** When the task entered its current state. */
+  state_since: number | null;
+}
+
+export interface OverdueTaskRecord {
+  task_id: string;
+  title: string;
+  due_at: number;
+  overdue_seconds: number;
+}
+
+export const OVERDUE_EVENT = "overdue";
+
+export interface WorkdayEndStop {
+  task_id: string;
+  title: string;
+  stopped_at: number;
+}
+
+export const WORKDAY_END_EVENT = "workday_end";
+
+/** Emitted once per task stopped by the `auto_stop_paused_after` setting. */
+export interface PausedAutoStop {
+  task_id: string;
+  title: string;
+  /** The original pause time; the stop is backdated so no time is added. */
+  stopped_at: number;
+}
+
+export const PAUSED_AUTO_STOP_EVENT = "paused_auto_stop";
+
+/** Emitted when a task left running while the machine slept has the suspend cut out of its session. */
+export interface SleepClip {
+  task_id: string;
+  slept_from: number;
+  slept_until: number;
+}
+
+export const SLEEP_CLIPPED_EVENT = "sleep_clipped";
+
+/** Emitted at most once per day when today's total passes `daily_max_seconds` mid-task. */
+export interface OverworkStatus {
+  date_key: string;
+  day_start_ts: number;
+  total_seconds: number;
+  /** 0 when the cap is off. */
+  daily_max_seconds: number;
+  exceeded: boolean;
+  running_task_id: string | null;
+}
+
+export const OVERWORK_WARNING_EVENT = "overwork-warning";
+
+export interface OverviewInvalidated {
+  latest_event_id: number | null;
+}
+
+export const OVERVIEW_INVALIDATED_EVENT = "overview-invalidated";
+
 function notifyDataChanged() {
   if (typeof window === "undefined") return;
   window.dispatchEvent(new CustomEvent(APP_DATA_CHANGED_EVENT));
@@ -95,14 +746,289 @@ export async function ping(): Promise<string> {
   return invoke<string>("ping");
 }
 
-export async function getOverview(range: OverviewRange): Promise<OverviewResponse> {
-  return invoke<OverviewResponse>("get_overview", { range });
+/**
+ * Moves the database file (and its WAL sidecars) to an absolute path that holds no database
+ * yet, then keeps using it from there on future launches.
+ */
+export async function relocateDatabase(newPath: string): Promise<StorageStatus> {
+  return invoke<StorageStatus>("relocate_database", { newPath });
+}
+
+/**
+ * Schema versions that were pending when the database was opened at startup; they are
+ * applied before any command runs. Empty when the schema was already current.
+ */
+export async function pendingMigrations(): Promise<number[]> {
+  return invoke<number[]>("pending_migrations");
+}
+
+export async function getMigrationStatus(): Promise<MigrationStatus> {
+  return invoke<MigrationStatus>("get_migration_status");
+}
+
+export async function getStorageStatus(): Promise<StorageStatus> {
+  return invoke<StorageStatus>("get_storage_status");
+}
+
+export async function getOverview(
+  range: OverviewRange,
+  options?: OverviewOptions,
+): Promise<OverviewResponse> {
+  return invoke<OverviewResponse>("get_overview", { range, options: options ?? null });
+}
+
+export async function getOverviewSince(lastEventId: number): Promise<OverviewDelta> {
+  return invoke<OverviewDelta>("get_overview_since", { lastEventId });
+}
+
+export async function getRunningTask(): Promise<TaskRecord | null> {
+  return invoke<TaskRecord | null>("get_running_task");
+}
+
+export async function getOverworkStatus(): Promise<OverworkStatus> {
+  return invoke<OverworkStatus>("get_overwork_status");
+}
+
+export async function getStatusCounts(): Promise<StatusCounts> {
+  return invoke<StatusCounts>("get_status_counts");
+}
+
+export async function getTaskTreeNested(range: OverviewRange): Promise<TaskTreeResponse> {
+  return invoke<TaskTreeResponse>("get_task_tree_nested", { range });
 }
 
 export async function getFocusSummary(range: FocusSummaryRange): Promise<FocusSummaryResponse> {
   return invoke<FocusSummaryResponse>("get_focus_summary", { range });
 }
 
+export async function getTagDailyTotals(
+  range: FocusSummaryRange,
+): Promise<TagDailyTotalsResponse> {
+  return invoke<TagDailyTotalsResponse>("get_tag_daily_totals", { range });
+}
+
+export async function getRollingAverage(
+  range: FocusSummaryRange,
+  windowDays: number,
+): Promise<RollingAverageResponse> {
+  return invoke<RollingAverageResponse>("get_rolling_average", { range, windowDays });
+}
+
+export async function getFragmentation(range: FocusSummaryRange): Promise<FragmentationResponse> {
+  return invoke<FragmentationResponse>("get_fragmentation", { range });
+}
+
+export async function getWorkBounds(range: FocusSummaryRange): Promise<WorkBoundsResponse> {
+  return invoke<WorkBoundsResponse>("get_work_bounds", { range });
+}
+
+export async function getFocusDensity(range: FocusSummaryRange): Promise<FocusDensityResponse> {
+  return invoke<FocusDensityResponse>("get_focus_density", { range });
+}
+
+export async function getUntrackedGaps(date: string): Promise<UntrackedGapsResponse> {
+  return invoke<UntrackedGapsResponse>("get_untracked_gaps", { date });
+}
+
+/** Largest untracked stretch of the day, or null when there is none. */
+export async function getLongestUntrackedGap(date: string): Promise<UntrackedGap | null> {
+  return invoke<UntrackedGap | null>("get_longest_untracked_gap", { date });
+}
+
+export async function getBillableReport(
+  range: OverviewRange,
+  client: string,
+  includeZero = false,
+): Promise<BillableReport> {
+  return invoke<BillableReport>("get_billable_report", { range, client, includeZero });
+}
+
+export async function getWeeklySummary(): Promise<WeeklySummary> {
+  return invoke<WeeklySummary>("get_weekly_summary");
+}
+
+export async function getSummaryCardData(range: FocusSummaryRange): Promise<SummaryCardData> {
+  return invoke<SummaryCardData>("get_summary_card_data", { range });
+}
+
+export async function getBreakAdherence(range: FocusSummaryRange): Promise<BreakAdherence> {
+  return invoke<BreakAdherence>("get_break_adherence", { range });
+}
+
+export async function getTimeBreakdown(
+  range: OverviewRange,
+  includeZero = false,
+): Promise<TimeBreakdownResponse> {
+  return invoke<TimeBreakdownResponse>("get_time_breakdown", { range, includeZero });
+}
+
+export async function getTimeByTagSet(
+  range: OverviewRange,
+  includeZero = false,
+): Promise<TagSetTotalsResponse> {
+  return invoke<TagSetTotalsResponse>("get_time_by_tag_set", { range, includeZero });
+}
+
+export async function getProjects(
+  range: OverviewRange,
+  includeZero = false,
+): Promise<ProjectsResponse> {
+  return invoke<ProjectsResponse>("get_projects", { range, includeZero });
+}
+
+/** Parents whose own time is below `ratio` of their subtree time; leaves are never included. */
+export async function getContainerTasks(range: OverviewRange, ratio: number): Promise<TaskRecord[]> {
+  return invoke<TaskRecord[]>("get_container_tasks", { range, ratio });
+}
+
+export async function getCompletionRate(range: OverviewRange): Promise<CompletionRateResponse> {
+  return invoke<CompletionRateResponse>("get_completion_rate", { range });
+}
+
+export async function getTotalsFor(
+  taskIds: string[],
+  range: OverviewRange,
+): Promise<TaskTotalsResponse> {
+  return invoke<TaskTotalsResponse>("get_totals_for", { taskIds, range });
+}
+
+export async function getInclusiveExcluding(
+  taskId: string,
+  excludedIds: string[],
+  range: OverviewRange,
+): Promise<InclusiveExcludingResponse> {
+  return invoke<InclusiveExcludingResponse>("get_inclusive_excluding", {
+    taskId,
+    excludedIds,
+    range,
+  });
+}
+
+export async function exportTaskSubtree(
+  taskId: string,
+  includeArchived = false,
+  includeTotals = false,
+): Promise<TaskSubtreeExport> {
+  return invoke<TaskSubtreeExport>("export_task_subtree", {
+    taskId,
+    includeArchived,
+    includeTotals,
+  });
+}
+
+export async function verifyTotals(): Promise<TotalsDrift[]> {
+  return invoke<TotalsDrift[]>("verify_totals");
+}
+
+/** Diagnostic: the replayed intervals and adjustments behind a task's exclusive seconds. */
+export async function debugIntervals(
+  taskId: string,
+  range: OverviewRange,
+): Promise<DebugIntervalsResponse> {
+  return invoke<DebugIntervalsResponse>("debug_intervals", { taskId, range });
+}
+
+export async function verifyTagConsistency(): Promise<TagDiscrepancy[]> {
+  return invoke<TagDiscrepancy[]>("verify_tag_consistency");
+}
+
+/** Rebuilds `task_tags` from the event log; returns the discrepancies it fixed. */
+export async function reconcileTags(): Promise<TagDiscrepancy[]> {
+  const fixed = await invoke<TagDiscrepancy[]>("reconcile_tags");
+  notifyDataChanged();
+  return fixed;
+}
+
+export async function getInclusiveOverlapWarnings(
+  range: OverviewRange,
+): Promise<InclusiveOverlapWarning[]> {
+  return invoke<InclusiveOverlapWarning[]>("get_inclusive_overlap_warnings", { range });
+}
+
+export async function getMidnightCrossingSessions(
+  range: OverviewRange,
+): Promise<MidnightCrossingSession[]> {
+  return invoke<MidnightCrossingSession[]>("get_midnight_crossing_sessions", { range });
+}
+
+export async function findCycles(): Promise<string[][]> {
+  return invoke<string[][]>("find_cycles");
+}
+
+export async function breakCycle(taskId: string): Promise<void> {
+  await invoke("break_cycle", { taskId });
+  notifyDataChanged();
+}
+
+export async function getOverdueTasks(): Promise<OverdueTaskRecord[]> {
+  return invoke<OverdueTaskRecord[]>("get_overdue_tasks");
+}
+
+export async function getInProgressTasks(): Promise<InProgressTaskRecord[]> {
+  return invoke<InProgressTaskRecord[]>("get_in_progress_tasks");
+}
+
+export async function getStaleTasks(minIdleDays: number): Promise<StaleTaskRecord[]> {
+  return invoke<StaleTaskRecord[]>("get_stale_tasks", { minIdleDays });
+}
+
+/** `threshold` is the max character edit distance between titles (default 2). */
+export async function findDuplicateTasks(threshold?: number): Promise<DuplicateTasksResponse> {
+  return invoke<DuplicateTasksResponse>("find_duplicate_tasks", { threshold });
+}
+
+export async function getDescendants(
+  taskId: string,
+  includeRoot = false,
+): Promise<DescendantRecord[]> {
+  return invoke<DescendantRecord[]>("get_descendants", { taskId, includeRoot });
+}
+
+export async function getEventsInWindow(
+  fromTs: number,
+  toTs: number,
+  limit?: number,
+  after?: { ts: number; id: number },
+): Promise<TimeEventsPage> {
+  return invoke<TimeEventsPage>("get_events_in_window", {
+    fromTs,
+    toTs,
+    limit: limit ?? null,
+    afterTs: after?.ts ?? null,
+    afterId: after?.id ?? null,
+  });
+}
+
+export async function getTaskDetail(taskId: string): Promise<TaskDetail> {
+  return invoke<TaskDetail>("get_task_detail", { taskId });
+}
+
+export async function annotateSession(
+  taskId: string,
+  sessionStartTs: number,
+  note: string,
+): Promise<void> {
+  await invoke("annotate_session", { taskId, sessionStartTs, note });
+  notifyDataChanged();
+}
+
+export async function getTaskEvents(
+  taskId: string,
+  limit?: number,
+  before?: { ts: number; id: number },
+): Promise<TaskEventsPage> {
+  return invoke<TaskEventsPage>("get_task_events", {
+    taskId,
+    limit: limit ?? null,
+    beforeTs: before?.ts ?? null,
+    beforeId: before?.id ?? null,
+  });
+}
+
+export async function getPendingRestSuggestion(): Promise<RestSuggestionRecord | null> {
+  return invoke<RestSuggestionRecord | null>("get_pending_rest_suggestion");
+}
+
 export async function createTask(title: string, parentId?: string | null): Promise<string> {
   const createdTaskId = await invoke<string>("create_task", {
     title,
@@ -112,6 +1038,15 @@ export async function createTask(title: string, parentId?: string | null): Promi
   return createdTaskId;
 }
 
+export async function createSubtasks(
+  parentId: string,
+  titles: string[],
+): Promise<CreatedSubtasks> {
+  const result = await invoke<CreatedSubtasks>("create_subtasks", { parentId, titles });
+  notifyDataChanged();
+  return result;
+}
+
 export async function renameTask(taskId: string, title: string): Promise<void> {
   await invoke("rename_task", { taskId, title });
   notifyDataChanged();
@@ -122,6 +1057,32 @@ export async function archiveTask(taskId: string): Promise<void> {
   notifyDataChanged();
 }
 
+export async function restoreTask(taskId: string): Promise<void> {
+  await invoke("restore_task", { taskId });
+  notifyDataChanged();
+}
+
+export async function resetTaskTime(
+  taskId: string,
+  includeDescendants = false,
+): Promise<string[]> {
+  const resetIds = await invoke<string[]>("reset_task_time", { taskId, includeDescendants });
+  notifyDataChanged();
+  return resetIds;
+}
+
+export async function archiveTasksByTag(tagName: string): Promise<string[]> {
+  const archivedIds = await invoke<string[]>("archive_tasks_by_tag", { tagName });
+  notifyDataChanged();
+  return archivedIds;
+}
+
+export async function restoreTasksByTag(tagName: string): Promise<string[]> {
+  const restoredIds = await invoke<string[]>("restore_tasks_by_tag", { tagName });
+  notifyDataChanged();
+  return restoredIds;
+}
+
 export async function deleteTasks(taskIds: string[], hardDelete = false): Promise<void> {
   const normalizedTaskIds = [...new Set(taskIds.map((id) => id.trim()).filter((id) => id.length > 0))];
   if (normalizedTaskIds.length === 0) return;
@@ -132,6 +1093,12 @@ export async function deleteTasks(taskIds: string[], hardDelete = false): Promis
   notifyDataChanged();
 }
 
+export async function updateTask(taskId: string, changes: TaskUpdate): Promise<TaskRecord> {
+  const record = await invoke<TaskRecord>("update_task", { taskId, changes });
+  notifyDataChanged();
+  return record;
+}
+
 export async function reparentTask(taskId: string, newParentId?: string | null): Promise<void> {
   await invoke("reparent_task", {
     taskId,
@@ -140,14 +1107,65 @@ export async function reparentTask(taskId: string, newParentId?: string | null):
   notifyDataChanged();
 }
 
-export async function startTask(taskId: string): Promise<void> {
-  await invoke("start_task", { taskId });
+/** Moves a task `delta` places among its siblings; returns the sibling ids in order. */
+export async function nudgeTaskOrder(taskId: string, delta: number): Promise<string[]> {
+  const siblingIds = await invoke<string[]>("nudge_task_order", { taskId, delta });
+  notifyDataChanged();
+  return siblingIds;
+}
+
+/** Moves the top-most tasks carrying the tag under a new parent; returns the moved ids. */
+export async function reparentTasksByTag(
+  tagName: string,
+  newParentId?: string | null,
+): Promise<string[]> {
+  const movedIds = await invoke<string[]>("reparent_tasks_by_tag", {
+    tagName,
+    newParentId: newParentId ?? null,
+  });
+  notifyDataChanged();
+  return movedIds;
+}
+
+export async function quickStart(
+  title: string,
+  tags: string[],
+  parentId?: string | null,
+): Promise<string> {
+  const taskId = await invoke<string>("quick_start", { title, tags, parentId: parentId ?? null });
+  notifyDataChanged();
+  return taskId;
+}
+
+export async function startTask(taskId: string, atTs?: number): Promise<void> {
+  await invoke("start_task", { taskId, atTs: atTs ?? null });
+  notifyDataChanged();
+}
+
+export async function pauseTask(taskId: string, reason?: string, atTs?: number): Promise<void> {
+  await invoke("pause_task", { taskId, reason: reason ?? null, atTs: atTs ?? null });
+  notifyDataChanged();
+}
+
+/** Pauses the running task for a break; returns its id, or null if nothing was running. */
+export async function startBreak(): Promise<string | null> {
+  const pausedTaskId = await invoke<string | null>("start_break");
+  notifyDataChanged();
+  return pausedTaskId;
+}
+
+/** Resumes the task paused by `startBreak` unless it was paused or switched manually since. */
+export async function endBreak(): Promise<string | null> {
+  const resumedTaskId = await invoke<string | null>("end_break");
   notifyDataChanged();
+  return resumedTaskId;
 }
 
-export async function pauseTask(taskId: string): Promise<void> {
-  await invoke("pause_task", { taskId });
+/** Logs a quick interruption against the running task; tracked time is unaffected. */
+export async function logInterruption(note?: string): Promise<string> {
+  const taskId = await invoke<string>("log_interruption", { note: note ?? null });
   notifyDataChanged();
+  return taskId;
 }
 
 export async function resumeTask(taskId: string): Promise<void> {
@@ -155,8 +1173,35 @@ export async function resumeTask(taskId: string): Promise<void> {
   notifyDataChanged();
 }
 
-export async function stopTask(taskId: string): Promise<void> {
-  await invoke("stop_task", { taskId });
+export async function toggleTask(taskId: string): Promise<TaskStatus> {
+  const status = await invoke<TaskStatus>("toggle_task", { taskId });
+  notifyDataChanged();
+  return status;
+}
+
+export async function stopTask(taskId: string, atTs?: number): Promise<void> {
+  await invoke("stop_task", { taskId, atTs: atTs ?? null });
+  notifyDataChanged();
+}
+
+export async function completeTask(taskId: string): Promise<void> {
+  await invoke("complete_task", { taskId });
+  notifyDataChanged();
+}
+
+export async function setTaskDue(taskId: string, dueAt: number | null): Promise<void> {
+  await invoke("set_task_due", { taskId, dueAt });
+  notifyDataChanged();
+}
+
+/** Pass an empty string to clear the link. */
+export async function setTaskReference(taskId: string, url: string): Promise<void> {
+  await invoke("set_task_reference", { taskId, url });
+  notifyDataChanged();
+}
+
+export async function reopenTask(taskId: string): Promise<void> {
+  await invoke("reopen_task", { taskId });
   notifyDataChanged();
 }
 
@@ -165,6 +1210,12 @@ export async function adjustTaskFocus(taskId: string, deltaSeconds: number): Pro
   notifyDataChanged();
 }
 
+export async function coalesceTaskSessions(taskId: string, maxGapSeconds: number): Promise<number> {
+  const merged = await invoke<number>("coalesce_task_sessions", { taskId, maxGapSeconds });
+  notifyDataChanged();
+  return merged;
+}
+
 export async function insertSubtaskAndStart(parentTaskId: string, title: string): Promise<string> {
   const childTaskId = await invoke<string>("insert_subtask_and_start", { parentTaskId, title });
   notifyDataChanged();
@@ -176,13 +1227,103 @@ export async function addTagToTask(taskId: string, tagName: string): Promise<voi
   notifyDataChanged();
 }
 
+/** Tags the task and returns the overview filtered to that tag. */
+export async function tagAndView(
+  taskId: string,
+  tagName: string,
+  range: OverviewRange,
+): Promise<OverviewResponse> {
+  const overview = await invoke<OverviewResponse>("tag_and_view", { taskId, tagName, range });
+  notifyDataChanged();
+  return overview;
+}
+
 export async function removeTagFromTask(taskId: string, tagName: string): Promise<void> {
   await invoke("remove_tag_from_task", { taskId, tagName });
   notifyDataChanged();
 }
 
+export async function importCsvSessions(csv: string): Promise<CsvImportReport> {
+  const report = await invoke<CsvImportReport>("import_csv_sessions", { csv });
+  notifyDataChanged();
+  return report;
+}
+
+export async function setTagColor(tagName: string, color: string | null): Promise<void> {
+  await invoke("set_tag_color", { tagName, color });
+  notifyDataChanged();
+}
+
+export async function getTopTags(limit?: number): Promise<TagRecord[]> {
+  return invoke<TagRecord[]>("get_top_tags", { limit: limit ?? null });
+}
+
+export async function getTagsByKey(key: string): Promise<TagRecord[]> {
+  return invoke<TagRecord[]>("get_tags_by_key", { key });
+}
+
+export async function listUntaggedTasks(): Promise<TaskRecord[]> {
+  return invoke<TaskRecord[]>("list_untagged_tasks");
+}
+
+export async function suggestTags(taskId: string, limit?: number): Promise<TagSuggestion[]> {
+  return invoke<TagSuggestion[]>("suggest_tags", { taskId, limit: limit ?? null });
+}
+
 export async function respondRestSuggestion(suggestionId: number, accept: boolean): Promise<void> {
   await invoke("respond_rest_suggestion", { suggestionId, accept });
   notifyDataChanged();
 }
+
+export async function respondAllPendingRestSuggestions(accept: boolean): Promise<number> {
+  const updated = await invoke<number>("respond_all_pending_rest_suggestions", { accept });
+  notifyDataChanged();
+  return updated;
+}
+
+export async function acceptRestSuggestionWithMinutes(
+  suggestionId: number,
+  minutes: number,
+): Promise<void> {
+  await invoke("accept_rest_suggestion_with_minutes", { suggestionId, minutes });
+  notifyDataChanged();
+}
+
+export async function getRestSignals(): Promise<RestSignals> {
+  return invoke<RestSignals>("get_rest_signals");
+}
+
+export async function previewCurrentRest(): Promise<RestPreview> {
+  return invoke<RestPreview>("preview_current_rest");
+}
+
+export async function simulateRestRules(
+  config: RestRulesConfig,
+  range: FocusSummaryRange,
+): Promise<RestSimulationResponse> {
+  return invoke<RestSimulationResponse>("simulate_rest_rules", { config, range });
+}
+
+/** Recomputes every task's cached deviation baseline; returns how many tasks have one. */
+export async function rebuildBaselines(): Promise<number> {
+  return invoke<number>("rebuild_baselines");
+}
+
+/** Stores all-time totals of every task with tracked time for later trend charts. */
+export async function snapshotOverview(): Promise<OverviewSnapshotSummary> {
+  return invoke<OverviewSnapshotSummary>("snapshot_overview");
+}
+
+export async function getSnapshots(taskId: string): Promise<OverviewSnapshot[]> {
+  return invoke<OverviewSnapshot[]>("get_snapshots", { taskId });
+}
+
+export async function getSettings(): Promise<SettingRecord[]> {
+  return invoke<SettingRecord[]>("get_settings");
+}
+
+export async function updateSetting(key: string, value: string): Promise<void> {
+  await invoke("update_setting", { key, value });
+  notifyDataChanged();
+}
 
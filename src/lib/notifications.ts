@@ -186,7 +186,12 @@ function createNotificationId(): string {
 
 function errorLevelFromCode(code: string | undefined): NotificationLevel {
   if (!code) return "error";
-  if (code === "validation" || code === "conflict" || code === "not_found") {
+  if (
+    code === "validation" ||
+    code === "conflict" ||
+    code === "not_found" ||
+    code === "busy"
+  ) {
     return "warning";
   }
   return "error";
@@ -6,6 +6,8 @@ export type AppErrorPayload = {
   code?: string;
   message: string;
   detail?: string;
+  /** Present on conflicts caused by another running task. */
+  running_task_id?: string;
 };
 
 export type RestSessionSource = "manual" | "suggestion";
@@ -194,6 +196,8 @@ export function extractErrorPayload(error: unknown): AppErrorPayload {
         code: typeof maybe.code === "string" ? maybe.code : undefined,
         message: maybe.message,
         detail: typeof maybe.detail === "string" ? maybe.detail : undefined,
+        running_task_id:
+          typeof maybe.running_task_id === "string" ? maybe.running_task_id : undefined,
       };
     }
     if (typeof maybe.error === "string") {